@@ -8,6 +8,7 @@ use oboe::{
 };
 use ringbuf::traits::{Consumer, Observer, Producer, Split};
 use ringbuf::HeapRb;
+use parking_lot::Mutex;
 use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::thread;
@@ -15,15 +16,23 @@ use std::time::Duration;
 
 use crate::command::Command;
 
-use crate::scheduler::TrackScheduler;
+use crate::scheduler::{RepeatPosition, TrackScheduler};
 
 /// Shared state atomics for tracking playback position from the UI thread
 pub struct PlaybackState {
     pub elapsed_samples: Arc<AtomicU64>,
     pub current_step: Arc<AtomicU64>,
+    /// Every step currently audible, including steps of overlapping runs
+    pub active_steps: Arc<Mutex<Vec<u64>>>,
+    /// Repeat block and pass of the current step, if it is inside a block
+    pub current_repeat: Arc<Mutex<Option<RepeatPosition>>>,
     pub is_paused: Arc<AtomicBool>,
 }
 
+/// Most steps reported as audible at once. `PlaybackState::active_steps`
+/// is allocated with this capacity so the worker never grows it.
+pub const MAX_ACTIVE_STEPS: usize = 16;
+
 const AUDIO_RING_MIN_SECONDS: f32 = 1.0;  // Increased from 0.5 for mobile stability
 const AUDIO_RING_MAX_SECONDS: f32 = 3.0;  // Increased from 2.0 for mobile stability
 const AUDIO_WORKER_BLOCK_FRAMES: usize = 1024;  // Increased from 512 to reduce per-block overhead
@@ -65,8 +74,22 @@ fn update_playback_state(playback_state: &Option<PlaybackState>, scheduler: &Tra
             .store(scheduler.absolute_sample, Ordering::Relaxed);
         state
             .current_step
-            .store(scheduler.current_step as u64, Ordering::Relaxed);
+            .store(scheduler.current_step_index() as u64, Ordering::Relaxed);
         state.is_paused.store(scheduler.paused, Ordering::Relaxed);
+        // Never block the audio worker on a UI reader; a skipped update is
+        // picked up on the next block.
+        if let Some(mut active) = state.active_steps.try_lock() {
+            active.clear();
+            active.extend(
+                scheduler
+                    .active_steps()
+                    .take(MAX_ACTIVE_STEPS)
+                    .map(|i| i as u64),
+            );
+        }
+        if let Some(mut repeat) = state.current_repeat.try_lock() {
+            *repeat = scheduler.current_repeat();
+        }
     }
 }
 
@@ -0,0 +1,258 @@
+//! Breakpoint automation lanes.
+//!
+//! A lane moves one param through a list of breakpoints, each shaping the
+//! segment to the next point with its own curve. Voice lanes drive params
+//! through the same field access as modulation routes, and the scheduler
+//! reads step lanes for `binaural_volume` and `noise_volume` every sample.
+//! Lanes are evaluated from their breakpoints as they play; nothing is
+//! rendered ahead.
+
+use crate::models::{AutomationLane, Breakpoint};
+
+/// Segment curves accepted in `Breakpoint::curve`.
+pub const CURVES: &[&str] = &["hold", "linear", "exp", "sigmoid", "bezier"];
+
+/// Step fields that accept lanes.
+pub const STEP_TARGETS: &[&str] = &["binaural_volume", "noise_volume"];
+
+/// `exp` segments default to `a²`, like exponential transitions.
+const DEFAULT_EXP_TENSION: f64 = 2.0;
+const DEFAULT_SIGMOID_TENSION: f64 = 10.0;
+/// Points per Bézier segment checked for overshoot in [`Lane::max_value`].
+const OVERSHOOT_SAMPLES: usize = 64;
+/// CSS `ease-in-out`.
+const DEFAULT_BEZIER_HANDLES: [f64; 4] = [0.42, 0.0, 0.58, 1.0];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Curve {
+    Hold,
+    Linear,
+    Exp(f64),
+    Sigmoid(f64),
+    Bezier([f64; 4]),
+}
+
+impl Curve {
+    /// Unknown names fall back to linear.
+    fn from_breakpoint(point: &Breakpoint) -> Self {
+        match point.curve.to_lowercase().as_str() {
+            "hold" => Curve::Hold,
+            "exp" => Curve::Exp(
+                point
+                    .tension
+                    .filter(|t| t.is_finite() && *t > 0.0)
+                    .unwrap_or(DEFAULT_EXP_TENSION),
+            ),
+            "sigmoid" => Curve::Sigmoid(
+                point
+                    .tension
+                    .filter(|t| t.is_finite() && *t > 0.0)
+                    .unwrap_or(DEFAULT_SIGMOID_TENSION),
+            ),
+            "bezier" => {
+                let mut h = point
+                    .handles
+                    .filter(|h| h.iter().all(|v| v.is_finite()))
+                    .unwrap_or(DEFAULT_BEZIER_HANDLES);
+                // Handles outside 0..1 in x would make time run backwards.
+                h[0] = h[0].clamp(0.0, 1.0);
+                h[2] = h[2].clamp(0.0, 1.0);
+                Curve::Bezier(h)
+            }
+            _ => Curve::Linear,
+        }
+    }
+
+    /// Eased progress through a segment for `x` in `0..=1`.
+    fn ease(self, x: f64) -> f64 {
+        match self {
+            Curve::Hold => 0.0,
+            Curve::Linear => x,
+            Curve::Exp(k) => x.powf(k),
+            Curve::Sigmoid(k) => {
+                let logistic = |v: f64| 1.0 / (1.0 + (-k * (v - 0.5)).exp());
+                let lo = logistic(0.0);
+                (logistic(x) - lo) / (logistic(1.0) - lo)
+            }
+            Curve::Bezier([x1, y1, x2, y2]) => {
+                let s = bezier_param_for_x(x, x1, x2);
+                cubic(s, y1, y2)
+            }
+        }
+    }
+}
+
+/// One coordinate of a cubic Bézier from 0 to 1 with inner controls `p1`, `p2`.
+fn cubic(s: f64, p1: f64, p2: f64) -> f64 {
+    let inv = 1.0 - s;
+    3.0 * inv * inv * s * p1 + 3.0 * inv * s * s * p2 + s * s * s
+}
+
+/// Curve parameter whose x coordinate is `x`: Newton steps, with bisection
+/// when the slope flattens out.
+fn bezier_param_for_x(x: f64, x1: f64, x2: f64) -> f64 {
+    let mut s = x;
+    for _ in 0..8 {
+        let err = cubic(s, x1, x2) - x;
+        if err.abs() < 1e-7 {
+            return s;
+        }
+        let inv = 1.0 - s;
+        let slope = 3.0 * inv * inv * x1 + 6.0 * inv * s * (x2 - x1) + 3.0 * s * s * (1.0 - x2);
+        if slope.abs() < 1e-6 {
+            break;
+        }
+        s = (s - err / slope).clamp(0.0, 1.0);
+    }
+    let (mut lo, mut hi) = (0.0, 1.0);
+    s = x;
+    for _ in 0..40 {
+        let v = cubic(s, x1, x2);
+        if (v - x).abs() < 1e-7 {
+            break;
+        }
+        if v < x {
+            lo = s;
+        } else {
+            hi = s;
+        }
+        s = 0.5 * (lo + hi);
+    }
+    s
+}
+
+/// A lane ready to be read at any time within its step.
+#[derive(Clone, Debug)]
+pub struct Lane {
+    times: Vec<f64>,
+    values: Vec<f64>,
+    /// `curves[i]` shapes the segment from point `i` to point `i + 1`.
+    curves: Vec<Curve>,
+}
+
+impl Lane {
+    /// Points are put in time order; non-finite points are dropped. Returns
+    /// `None` when no point remains.
+    pub fn new(data: &AutomationLane) -> Option<Self> {
+        Self::from_points(&data.points)
+    }
+
+    /// A lane through `points`, ordered and filtered as in [`Self::new`].
+    pub fn from_points(points: &[Breakpoint]) -> Option<Self> {
+        let mut points: Vec<&Breakpoint> = points
+            .iter()
+            .filter(|p| p.time.is_finite() && p.value.is_finite())
+            .collect();
+        if points.is_empty() {
+            return None;
+        }
+        points.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Some(Self {
+            times: points.iter().map(|p| p.time).collect(),
+            values: points.iter().map(|p| p.value).collect(),
+            curves: points.iter().map(|p| Curve::from_breakpoint(p)).collect(),
+        })
+    }
+
+    /// Value `t` seconds into the step. Before the first point and after
+    /// the last, the nearest point's value holds.
+    pub fn value_at(&self, t: f64) -> f64 {
+        let next = self.times.partition_point(|&time| time <= t);
+        if next == 0 {
+            return self.values[0];
+        }
+        if next == self.times.len() {
+            return self.values[next - 1];
+        }
+        let (t0, t1) = (self.times[next - 1], self.times[next]);
+        let (v0, v1) = (self.values[next - 1], self.values[next]);
+        let x = ((t - t0) / (t1 - t0)).clamp(0.0, 1.0);
+        v0 + (v1 - v0) * self.curves[next - 1].ease(x)
+    }
+
+    /// Largest value the lane reaches. Eased segments stay between their
+    /// points, except Bézier handles outside 0..1 in y, which can overshoot
+    /// and are sampled.
+    pub fn max_value(&self) -> f64 {
+        let mut max = self.values.iter().copied().fold(f64::MIN, f64::max);
+        for (i, curve) in self.curves.iter().enumerate().take(self.values.len() - 1) {
+            if let Curve::Bezier([_, y1, _, y2]) = *curve {
+                if !(0.0..=1.0).contains(&y1) || !(0.0..=1.0).contains(&y2) {
+                    let (v0, v1) = (self.values[i], self.values[i + 1]);
+                    for step in 1..OVERSHOOT_SAMPLES {
+                        let x = step as f64 / OVERSHOOT_SAMPLES as f64;
+                        max = max.max(v0 + (v1 - v0) * curve.ease(x));
+                    }
+                }
+            }
+        }
+        max
+    }
+}
+
+/// The lane for `target`. When several are given, the last usable one wins,
+/// as it does for voice lanes.
+pub fn lane_for(lanes: &[AutomationLane], target: &str) -> Option<Lane> {
+    lanes
+        .iter()
+        .rev()
+        .filter(|lane| lane.target == target)
+        .find_map(Lane::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(time: f64, value: f64, curve: &str) -> Breakpoint {
+        Breakpoint {
+            time,
+            value,
+            curve: curve.to_string(),
+            tension: None,
+            handles: None,
+        }
+    }
+
+    #[test]
+    fn lanes_follow_breakpoints_and_curves() {
+        // beatFreq 10 -> 7 -> 7 -> 4, given out of order.
+        let lane = Lane::new(&AutomationLane {
+            target: "beatFreq".to_string(),
+            points: vec![
+                point(20.0, 7.0, "exp"),
+                point(0.0, 10.0, "linear"),
+                point(10.0, 7.0, "hold"),
+                point(30.0, 4.0, "linear"),
+            ],
+        })
+        .unwrap();
+        assert_eq!(lane.value_at(-1.0), 10.0);
+        assert!((lane.value_at(5.0) - 8.5).abs() < 1e-9);
+        assert_eq!(lane.value_at(15.0), 7.0);
+        assert!((lane.value_at(25.0) - (7.0 - 3.0 * 0.25)).abs() < 1e-9);
+        assert_eq!(lane.value_at(30.0), 4.0);
+        assert_eq!(lane.value_at(99.0), 4.0);
+    }
+
+    #[test]
+    fn eased_curves_span_the_segment_monotonically() {
+        for curve in [
+            Curve::Exp(0.5),
+            Curve::Sigmoid(DEFAULT_SIGMOID_TENSION),
+            Curve::Bezier(DEFAULT_BEZIER_HANDLES),
+            Curve::Bezier([0.9, 0.1, 0.1, 0.9]),
+        ] {
+            assert!(curve.ease(0.0).abs() < 1e-6, "{curve:?}");
+            assert!((curve.ease(1.0) - 1.0).abs() < 1e-6, "{curve:?}");
+            let mut prev = 0.0;
+            for i in 1..=100 {
+                let v = curve.ease(i as f64 / 100.0);
+                assert!(v >= prev - 1e-9, "{curve:?} falls at {i}");
+                prev = v;
+            }
+        }
+        let ease_in_out = Curve::Bezier(DEFAULT_BEZIER_HANDLES);
+        assert!((ease_in_out.ease(0.5) - 0.5).abs() < 1e-6);
+    }
+}
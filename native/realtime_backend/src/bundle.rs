@@ -0,0 +1,451 @@
+//! Self-contained session bundles.
+//!
+//! A bundle is an uncompressed POSIX tar archive holding `manifest.json`, the
+//! track in canonical form (`track.json`) and every file the track refers to
+//! under `files/`: overlay clips, `.noise` parameter files together with the
+//! audio they read, and voice audio such as subliminal `audio_paths`. Paths
+//! inside the bundled track are relative to the bundle root, so an opened
+//! bundle plays from wherever it was extracted. Audio is stored as-is; the
+//! formats we play are already compressed.
+
+use crate::migration;
+use crate::models::{map_voice_file_paths, TrackData, TRACK_FORMAT_VERSION};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// Version of the bundle layout written by [`export_bundle`].
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_NAME: &str = "manifest.json";
+const TRACK_NAME: &str = "track.json";
+const FILES_DIR: &str = "files";
+const BLOCK: usize = 512;
+/// Longest entry name that fits the tar header without the ustar prefix.
+const MAX_NAME_LEN: usize = 99;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BundleManifest {
+    pub bundle_version: u32,
+    pub track_format_version: u32,
+    /// Path of the track document inside the bundle.
+    pub track: String,
+    pub files: Vec<BundleFile>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BundleFile {
+    /// Path inside the bundle.
+    pub path: String,
+    /// Where the file was packaged from, for reference only.
+    pub original_path: String,
+}
+
+/// Package `track` and every file it references into a bundle at `out_path`.
+///
+/// Relative paths in the track are resolved against `base_dir` when given.
+/// Fails without writing anything if a referenced file is missing.
+pub fn export_bundle(
+    track: &TrackData,
+    base_dir: Option<&Path>,
+    out_path: &Path,
+) -> Result<BundleManifest, Box<dyn Error>> {
+    let mut track = track.clone();
+    if let Some(base) = base_dir {
+        track.resolve_relative_paths(base);
+    }
+
+    let mut packer = Packer::default();
+    for clip in &mut track.clips {
+        clip.file_path = packer.add_file(&clip.file_path);
+    }
+    if let Some(noise) = &mut track.background_noise {
+        // Only `.noise` files are read by the scheduler; anything else in
+        // file_path is a label and stays as written.
+        if noise.file_path.ends_with(".noise") {
+            noise.file_path = packer.add_noise_file(&noise.file_path)?;
+        }
+        if let Some(params) = &mut noise.params {
+            params.input_audio_path = packer.add_file(&params.input_audio_path);
+        }
+    }
+    for step in &mut track.steps {
+        for voice in &mut step.voices {
+            map_voice_file_paths(&mut voice.params, |path| packer.add_file(path));
+        }
+    }
+    if !packer.missing.is_empty() {
+        return Err(format!("Referenced files not found: {}", packer.missing.join(", ")).into());
+    }
+
+    track.format_version = TRACK_FORMAT_VERSION;
+    let manifest = BundleManifest {
+        bundle_version: BUNDLE_FORMAT_VERSION,
+        track_format_version: TRACK_FORMAT_VERSION,
+        track: TRACK_NAME.to_string(),
+        files: packer
+            .entries
+            .iter()
+            .map(|e| BundleFile {
+                path: e.path.clone(),
+                original_path: e.original.to_string_lossy().into_owned(),
+            })
+            .collect(),
+    };
+
+    if let Some(parent) = out_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut out = io::BufWriter::new(File::create(out_path)?);
+    let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+    write_entry(
+        &mut out,
+        MANIFEST_NAME,
+        &mut manifest_json.as_slice(),
+        manifest_json.len() as u64,
+    )?;
+    let track_json = serde_json::to_vec_pretty(&track)?;
+    write_entry(
+        &mut out,
+        TRACK_NAME,
+        &mut track_json.as_slice(),
+        track_json.len() as u64,
+    )?;
+    for entry in &packer.entries {
+        match &entry.contents {
+            Some(bytes) => write_entry(
+                &mut out,
+                &entry.path,
+                &mut bytes.as_slice(),
+                bytes.len() as u64,
+            )?,
+            None => {
+                let file = File::open(&entry.original)?;
+                let size = file.metadata()?.len();
+                write_entry(&mut out, &entry.path, &mut file.take(size), size)?;
+            }
+        }
+    }
+    // End-of-archive marker: two zero blocks.
+    out.write_all(&[0u8; BLOCK * 2])?;
+    out.flush()?;
+    Ok(manifest)
+}
+
+/// Extract the bundle at `bundle_path` into `dest_dir` and return its track
+/// with every file path pointing at the extracted copy.
+pub fn open_bundle(bundle_path: &Path, dest_dir: &Path) -> Result<TrackData, Box<dyn Error>> {
+    fs::create_dir_all(dest_dir)?;
+    let mut input = io::BufReader::new(File::open(bundle_path)?);
+    let mut header = [0u8; BLOCK];
+    loop {
+        if let Err(e) = input.read_exact(&mut header) {
+            // Tolerate archives missing the end-of-archive blocks.
+            if e.kind() == io::ErrorKind::UnexpectedEof {
+                break;
+            }
+            return Err(e.into());
+        }
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        let name = entry_name(&header)?;
+        let size = parse_octal(&header[124..136])?;
+        let padding = (BLOCK as u64 - size % BLOCK as u64) % BLOCK as u64;
+        let regular_file = matches!(header[156], b'0' | 0);
+        if regular_file {
+            let target = dest_dir.join(safe_relative_path(&name)?);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut file = File::create(&target)?;
+            let copied = io::copy(&mut (&mut input).take(size), &mut file)?;
+            if copied != size {
+                return Err(format!("Bundle entry '{name}' is truncated").into());
+            }
+        } else {
+            io::copy(&mut (&mut input).take(size), &mut io::sink())?;
+        }
+        io::copy(&mut (&mut input).take(padding), &mut io::sink())?;
+    }
+
+    let manifest: BundleManifest = serde_json::from_reader(
+        File::open(dest_dir.join(MANIFEST_NAME))
+            .map_err(|_| format!("'{}' is not a session bundle", bundle_path.display()))?,
+    )?;
+    if manifest.bundle_version > BUNDLE_FORMAT_VERSION {
+        return Err(format!(
+            "Bundle version {} is newer than supported version {BUNDLE_FORMAT_VERSION}",
+            manifest.bundle_version
+        )
+        .into());
+    }
+    let track_path = dest_dir.join(safe_relative_path(&manifest.track)?);
+    let mut track = migration::parse_track(&fs::read_to_string(track_path)?)?;
+    track.resolve_relative_paths(dest_dir);
+    Ok(track)
+}
+
+struct PackedFile {
+    path: String,
+    original: PathBuf,
+    /// Replacement contents, for files rewritten while packaging.
+    contents: Option<Vec<u8>>,
+}
+
+#[derive(Default)]
+struct Packer {
+    entries: Vec<PackedFile>,
+    by_source: HashMap<PathBuf, String>,
+    names: HashSet<String>,
+    missing: Vec<String>,
+}
+
+impl Packer {
+    /// Queue `path` for packaging and return its path inside the bundle.
+    /// Missing files are recorded and their path returned unchanged.
+    fn add_file(&mut self, path: &str) -> String {
+        if path.is_empty() {
+            return String::new();
+        }
+        let source = PathBuf::from(path);
+        if let Some(existing) = self.by_source.get(&source) {
+            return existing.clone();
+        }
+        if !source.is_file() {
+            self.missing.push(path.to_string());
+            return path.to_string();
+        }
+        self.push(source, None)
+    }
+
+    /// Queue a `.noise` file, packaging the audio it reads alongside it and
+    /// rewriting its `input_audio_path` to match.
+    fn add_noise_file(&mut self, path: &str) -> Result<String, Box<dyn Error>> {
+        let source = PathBuf::from(path);
+        if let Some(existing) = self.by_source.get(&source) {
+            return Ok(existing.clone());
+        }
+        if !source.is_file() {
+            self.missing.push(path.to_string());
+            return Ok(path.to_string());
+        }
+        let mut params: Value = serde_json::from_str(&fs::read_to_string(&source)?)?;
+        if let Some(Value::String(audio)) = params.get_mut("input_audio_path") {
+            if !audio.is_empty() {
+                // Relative audio paths are relative to the .noise file itself.
+                let audio_path = match source.parent() {
+                    Some(dir) if Path::new(audio.as_str()).is_relative() => {
+                        dir.join(audio.as_str())
+                    }
+                    _ => PathBuf::from(audio.as_str()),
+                };
+                let packed = self.add_file(&audio_path.to_string_lossy());
+                // Both files live in FILES_DIR, so the bare name is enough.
+                *audio = packed
+                    .strip_prefix(&format!("{FILES_DIR}/"))
+                    .unwrap_or(&packed)
+                    .to_string();
+            }
+        }
+        Ok(self.push(source, Some(serde_json::to_vec_pretty(&params)?)))
+    }
+
+    fn push(&mut self, source: PathBuf, contents: Option<Vec<u8>>) -> String {
+        let path = self.unique_name(&source);
+        self.names.insert(path.clone());
+        self.by_source.insert(source.clone(), path.clone());
+        self.entries.push(PackedFile {
+            path: path.clone(),
+            original: source,
+            contents,
+        });
+        path
+    }
+
+    /// `files/<name>`, made safe for tar headers and unique within the bundle.
+    fn unique_name(&self, source: &Path) -> String {
+        let file_name = source
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "file".to_string());
+        let mut clean: String = file_name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        // Leave room for the directory and a numeric prefix; keep the extension.
+        let limit = MAX_NAME_LEN - FILES_DIR.len() - 1 - 6;
+        if clean.len() > limit {
+            let ext = Path::new(&clean)
+                .extension()
+                .map(|e| format!(".{}", e.to_string_lossy()))
+                .filter(|e| e.len() < limit)
+                .unwrap_or_default();
+            clean = format!("{}{ext}", &clean[..limit - ext.len()]);
+        }
+        let mut candidate = format!("{FILES_DIR}/{clean}");
+        let mut n = 1;
+        while self.names.contains(&candidate) {
+            n += 1;
+            candidate = format!("{FILES_DIR}/{n}_{clean}");
+        }
+        candidate
+    }
+}
+
+fn write_entry<W: Write, R: Read>(
+    out: &mut W,
+    name: &str,
+    data: &mut R,
+    size: u64,
+) -> io::Result<()> {
+    let mut header = [0u8; BLOCK];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    write_octal(&mut header[100..108], 0o644);
+    write_octal(&mut header[108..116], 0);
+    write_octal(&mut header[116..124], 0);
+    write_octal(&mut header[124..136], size);
+    write_octal(&mut header[136..148], 0);
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    // The checksum is computed with its own field filled with spaces.
+    header[148..156].fill(b' ');
+    let checksum: u32 = header.iter().map(|&b| b as u32).sum();
+    write_octal(&mut header[148..155], checksum as u64);
+    header[155] = b' ';
+    out.write_all(&header)?;
+
+    let copied = io::copy(data, out)?;
+    if copied != size {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("'{name}' changed size while being bundled"),
+        ));
+    }
+    let padding = (BLOCK - (size as usize % BLOCK)) % BLOCK;
+    out.write_all(&[0u8; BLOCK][..padding])
+}
+
+/// Zero-padded octal terminated by NUL, filling the field.
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = format!("{:0width$o}", value, width = field.len() - 1);
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+    field[digits.len()] = 0;
+}
+
+fn parse_octal(field: &[u8]) -> Result<u64, Box<dyn Error>> {
+    let text: String = field
+        .iter()
+        .take_while(|&&b| b != 0)
+        .map(|&b| b as char)
+        .collect();
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(0);
+    }
+    Ok(
+        u64::from_str_radix(text, 8)
+            .map_err(|_| format!("Corrupt bundle header field '{text}'"))?,
+    )
+}
+
+fn entry_name(header: &[u8; BLOCK]) -> Result<String, Box<dyn Error>> {
+    let field = |range: std::ops::Range<usize>| {
+        let bytes = &header[range];
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        String::from_utf8(bytes[..end].to_vec())
+    };
+    let name = field(0..100)?;
+    let prefix = field(345..500)?;
+    Ok(if prefix.is_empty() {
+        name
+    } else {
+        format!("{prefix}/{name}")
+    })
+}
+
+/// Reject absolute paths and `..` so entries cannot escape the destination.
+fn safe_relative_path(name: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let path = Path::new(name);
+    if path
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        Ok(path.to_path_buf())
+    } else {
+        Err(format!("Bundle entry '{name}' points outside the bundle").into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundle_round_trips_track_and_referenced_files() {
+        let root = std::env::temp_dir().join(format!("bundle_test_{}", std::process::id()));
+        let src = root.join("src");
+        fs::create_dir_all(src.join("sounds")).unwrap();
+        fs::write(src.join("sounds/bell.wav"), vec![7u8; 1300]).unwrap();
+        fs::write(src.join("rain.flac"), b"rain").unwrap();
+        fs::write(src.join("affirm.wav"), b"affirm").unwrap();
+        fs::write(
+            src.join("rain.noise"),
+            r#"{"duration_seconds": 1.0, "input_audio_path": "rain.flac"}"#,
+        )
+        .unwrap();
+
+        let track: TrackData = serde_json::from_str(
+            r#"{
+                "global_settings": {"sample_rate": 44100},
+                "steps": [{
+                    "duration": 10.0,
+                    "voices": [{
+                        "synth_function_name": "subliminal_encode",
+                        "params": {"audio_paths": "affirm.wav; sounds/bell.wav"}
+                    }]
+                }],
+                "clips": [{"file_path": "sounds/bell.wav", "start": 1.0}],
+                "background_noise": {"file_path": "rain.noise", "amp": 0.4}
+            }"#,
+        )
+        .unwrap();
+
+        let bundle = root.join("session.sbundle");
+        let manifest = export_bundle(&track, Some(&src), &bundle).expect("export");
+        assert_eq!(manifest.files.len(), 4);
+
+        let opened = open_bundle(&bundle, &root.join("out")).expect("open");
+        let clip = &opened.clips[0].file_path;
+        assert_eq!(fs::read(clip).unwrap(), vec![7u8; 1300]);
+        let noise_path = &opened.background_noise.as_ref().unwrap().file_path;
+        let noise = crate::noise_params::load_noise_params(noise_path).unwrap();
+        assert_eq!(fs::read(&noise.input_audio_path).unwrap(), b"rain");
+        let paths = opened.steps[0].voices[0].params["audio_paths"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let mut parts = paths.split(';');
+        assert_eq!(fs::read(parts.next().unwrap()).unwrap(), b"affirm");
+        assert_eq!(parts.next(), Some(clip.as_str()));
+
+        let missing = root.join("missing.sbundle");
+        let mut broken = track.clone();
+        broken.clips[0].file_path = "nope.wav".to_string();
+        assert!(export_bundle(&broken, Some(&src), &missing).is_err());
+        assert!(!missing.exists());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
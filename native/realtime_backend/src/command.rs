@@ -1,9 +1,9 @@
-use crate::models::TrackData;
+use crate::scheduler::PreparedTrack;
 
 #[derive(Debug)]
 pub enum Command {
-    UpdateTrack(TrackData),
-    UpdateRealtime(TrackData),
+    UpdateTrack(Box<PreparedTrack>),
+    UpdateRealtime(Box<PreparedTrack>),
     /// Enable or disable GPU accelerated mixing
     EnableGpu(bool),
     /// Pause or resume playback
@@ -0,0 +1,662 @@
+//! Volume envelopes for enveloped voices.
+//!
+//! An envelope is evaluated from its params at any sample of the step, so it
+//! holds no buffer however long the step is, and a voice can pick it up
+//! anywhere after a seek.
+//!
+//! Besides the legacy `linear_fade` (in, out or both, as its `fade_type`
+//! says), `adsr` and `linen`, envelopes can fade in, out or both on linear or
+//! dB ramps, follow breakpoints with the curves
+//! of automation lanes, or add tremolo. Times are in seconds, or in
+//! fractions of the step when `time_unit` is `fraction`.
+
+use crate::automation::Lane;
+use crate::models::{Breakpoint, VolumeEnvelope};
+
+/// `fade_type` values of `linear_fade`.
+pub const LINEAR_FADE_TYPES: &[&str] = &["in", "out", "in_out"];
+
+/// `curve` values of the fade envelopes. A dB-linear ramp is exponential in
+/// amplitude, so `exponential` names the same curve as `db`.
+pub const FADE_CURVES: &[&str] = &["linear", "db", "exponential"];
+
+/// `time_unit` values.
+pub const TIME_UNITS: &[&str] = &["seconds", "fraction"];
+
+/// Level a dB ramp treats as silence, unless `floor_db` says otherwise.
+const DEFAULT_FLOOR_DB: f32 = -60.0;
+
+#[derive(Clone, Copy, Debug)]
+enum FadeCurve {
+    Linear,
+    /// Straight in dB, with gains at or below `floor` taken as silence.
+    Db {
+        floor: f32,
+    },
+}
+
+impl FadeCurve {
+    /// Gain `x` of the way from `a` to `b`.
+    fn ramp(self, a: f32, b: f32, x: f32) -> f32 {
+        match self {
+            FadeCurve::Linear => a + x * (b - a),
+            FadeCurve::Db { floor } => {
+                let db = |v: f32| 20.0 * v.max(floor).log10();
+                let (da, db_b) = (db(a), db(b));
+                let level = 10f32.powf((da + x * (db_b - da)) / 20.0);
+                // Allow for rounding on the way through dB.
+                if level <= floor * 1.0001 {
+                    0.0
+                } else {
+                    level
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Shape {
+    /// Unknown types play at unity.
+    Flat,
+    Adsr {
+        attack: usize,
+        decay: usize,
+        sustain: usize,
+        release: usize,
+        sustain_level: f32,
+    },
+    Linen {
+        attack: usize,
+        sustain: usize,
+        release: usize,
+    },
+    /// From `start_amp` up to `amp` over the first `fade_in` samples, and
+    /// on to `end_amp` over the last `fade_out`.
+    Fade {
+        fade_in: usize,
+        fade_out: usize,
+        start_amp: f32,
+        amp: f32,
+        end_amp: f32,
+        curve: FadeCurve,
+    },
+    Breakpoints {
+        lane: Lane,
+        sample_rate: f32,
+    },
+    /// Dips by `depth` at `rate` Hz, starting from unity.
+    Tremolo {
+        rate: f64,
+        depth: f32,
+        sample_rate: f64,
+    },
+}
+
+/// A volume envelope over one step.
+#[derive(Clone, Debug)]
+pub struct Envelope {
+    /// Samples in the step; later samples hold the last value.
+    len: usize,
+    shape: Shape,
+}
+
+impl Envelope {
+    /// Envelope described by `env` over a step of `duration` seconds. Unknown
+    /// types and missing params fall back to a flat envelope and defaults.
+    pub fn new(env: &VolumeEnvelope, duration: f32, sample_rate: u32) -> Self {
+        let len = (duration * sample_rate as f32) as usize;
+        let param =
+            |key: &str, default: f64| env.params.get(key).copied().unwrap_or(default) as f32;
+        let fraction = env
+            .time_unit
+            .as_deref()
+            .is_some_and(|u| u.eq_ignore_ascii_case("fraction"));
+        let time_scale = if fraction { duration } else { 1.0 };
+        let time = |key: &str, default: f64| param(key, default) * time_scale;
+        let samples =
+            |seconds: f32| (seconds.max(0.0) * sample_rate as f32).min(len as f32) as usize;
+        // ADSR and linen measure their stages against the step's length as
+        // the sum of its sample times, which is not exactly `sample_rate`.
+        let dt = 1.0 / sample_rate as f32;
+        let span = (len.max(1) - 1) as f32 * dt + if len > 1 { dt } else { 0.0 };
+        let curve = match env.curve.as_deref().map(str::to_lowercase).as_deref() {
+            Some("db" | "exponential") => FadeCurve::Db {
+                floor: 10f32.powf(param("floor_db", DEFAULT_FLOOR_DB as f64) / 20.0),
+            },
+            _ => FadeCurve::Linear,
+        };
+        let shape = match env.envelope_type.as_str() {
+            "linear_fade" => {
+                // Fades in from `start_amp` to `end_amp`, out from `start_amp`
+                // to `end_amp`, or in and back out to `start_amp`.
+                let fade = samples(time("fade_duration", 0.0));
+                let (start_amp, end_amp) = (param("start_amp", 0.0), param("end_amp", 1.0));
+                let fade_type = env.fade_type.as_deref().map(str::to_lowercase);
+                let (mut fade_in, mut fade_out, start_amp, amp, end_amp) =
+                    match fade_type.as_deref() {
+                        Some("out") => (0, fade, start_amp, start_amp, end_amp),
+                        Some("in_out") => (fade, fade, start_amp, end_amp, start_amp),
+                        _ => (fade, 0, start_amp, end_amp, end_amp),
+                    };
+                if fade_in + fade_out > len {
+                    let scale = len as f32 / (fade_in + fade_out) as f32;
+                    fade_in = (fade_in as f32 * scale) as usize;
+                    fade_out = len - fade_in;
+                }
+                Shape::Fade {
+                    fade_in,
+                    fade_out,
+                    start_amp,
+                    amp,
+                    end_amp,
+                    curve,
+                }
+            }
+            "adsr" => {
+                let sr = len as f32 / span;
+                let attack = (time("attack", 0.01) * sr) as usize;
+                let decay = (time("decay", 0.1) * sr) as usize;
+                let release = (time("release", 0.1) * sr) as usize;
+                Shape::Adsr {
+                    attack,
+                    decay,
+                    sustain: len
+                        .saturating_sub(attack.saturating_add(decay).saturating_add(release)),
+                    release,
+                    sustain_level: param("sustain_level", 0.8),
+                }
+            }
+            "linen" => {
+                let sr = if span > 0.0 {
+                    len as f32 / span
+                } else {
+                    44100.0
+                };
+                let mut attack = (time("attack", 0.01).max(0.0) * sr) as usize;
+                let mut release = (time("release", 0.1).max(0.0) * sr) as usize;
+                let ramps = attack.saturating_add(release);
+                if ramps > len {
+                    // Squeeze both ramps into the step, keeping their ratio.
+                    let scale = len as f32 / ramps as f32;
+                    attack = (attack as f32 * scale) as usize;
+                    release = len.saturating_sub(attack);
+                }
+                Shape::Linen {
+                    attack,
+                    sustain: len.saturating_sub(attack.saturating_add(release)),
+                    release,
+                }
+            }
+            "fade_in" | "fade_out" | "fade_in_out" => {
+                let (mut fade_in, mut fade_out, start_amp, amp, end_amp) =
+                    match env.envelope_type.as_str() {
+                        "fade_in" => (
+                            samples(time("fade_duration", 0.0)),
+                            0,
+                            param("start_amp", 0.0),
+                            param("end_amp", 1.0),
+                            0.0,
+                        ),
+                        "fade_out" => (
+                            0,
+                            samples(time("fade_duration", 0.0)),
+                            0.0,
+                            param("start_amp", 1.0),
+                            param("end_amp", 0.0),
+                        ),
+                        _ => (
+                            samples(time("fade_in", 0.0)),
+                            samples(time("fade_out", 0.0)),
+                            param("start_amp", 0.0),
+                            param("amp", 1.0),
+                            param("end_amp", 0.0),
+                        ),
+                    };
+                if fade_in + fade_out > len {
+                    // Squeeze both fades into the step, keeping their ratio.
+                    let scale = len as f32 / (fade_in + fade_out) as f32;
+                    fade_in = (fade_in as f32 * scale) as usize;
+                    fade_out = len - fade_in;
+                }
+                Shape::Fade {
+                    fade_in,
+                    fade_out,
+                    start_amp,
+                    amp,
+                    end_amp,
+                    curve,
+                }
+            }
+            "breakpoints" => {
+                let points: Vec<Breakpoint> = env
+                    .points
+                    .iter()
+                    .map(|p| Breakpoint {
+                        time: p.time * time_scale as f64,
+                        ..p.clone()
+                    })
+                    .collect();
+                match Lane::from_points(&points) {
+                    Some(lane) => Shape::Breakpoints {
+                        lane,
+                        sample_rate: sample_rate as f32,
+                    },
+                    None => Shape::Flat,
+                }
+            }
+            "tremolo" => Shape::Tremolo {
+                rate: param("rate", 4.0).max(0.0) as f64,
+                depth: param("depth", 0.5).clamp(0.0, 1.0),
+                sample_rate: sample_rate as f64,
+            },
+            _ => Shape::Flat,
+        };
+        Self { len, shape }
+    }
+
+    /// Samples in the step.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Gain at sample `index` of the step. Past the end of the step the last
+    /// value holds; an envelope over an empty step is unity.
+    pub fn value_at(&self, index: usize) -> f32 {
+        if self.len == 0 {
+            return 1.0;
+        }
+        let i = index.min(self.len - 1);
+        match self.shape {
+            Shape::Flat => 1.0,
+            Shape::Adsr {
+                attack,
+                decay,
+                sustain,
+                release,
+                sustain_level,
+            } => {
+                if i < attack {
+                    return i as f32 / attack as f32;
+                }
+                let i = i - attack;
+                if i < decay {
+                    return 1.0 - (1.0 - sustain_level) * (i as f32 / decay as f32);
+                }
+                let i = i - decay;
+                if i < sustain {
+                    return sustain_level;
+                }
+                let i = i - sustain;
+                sustain_level * (1.0 - (i as f32 / release as f32))
+            }
+            Shape::Linen {
+                attack,
+                sustain,
+                release,
+            } => {
+                if i < attack {
+                    i as f32 / attack as f32
+                } else if i < attack + sustain {
+                    1.0
+                } else {
+                    1.0 - ((i - attack - sustain) as f32 / release as f32)
+                }
+            }
+            Shape::Fade {
+                fade_in,
+                fade_out,
+                start_amp,
+                amp,
+                end_amp,
+                curve,
+            } => {
+                let out_start = self.len - fade_out;
+                if i < fade_in {
+                    curve.ramp(start_amp, amp, i as f32 / fade_in as f32)
+                } else if i >= out_start {
+                    curve.ramp(amp, end_amp, (i - out_start) as f32 / fade_out as f32)
+                } else {
+                    amp
+                }
+            }
+            Shape::Breakpoints {
+                ref lane,
+                sample_rate,
+            } => (lane.value_at(i as f64 / sample_rate as f64) as f32).max(0.0),
+            Shape::Tremolo {
+                rate,
+                depth,
+                sample_rate,
+            } => {
+                let phase = (rate * i as f64 / sample_rate).fract() * std::f64::consts::TAU;
+                1.0 - depth * 0.5 * (1.0 - phase.cos() as f32)
+            }
+        }
+    }
+
+    /// Largest gain the envelope reaches. Every stage of the staged shapes
+    /// is a monotonic ramp, so only the samples either side of a stage
+    /// boundary need checking.
+    pub fn peak(&self) -> f32 {
+        let edges = match self.shape {
+            Shape::Flat | Shape::Tremolo { .. } => return 1.0,
+            Shape::Breakpoints { ref lane, .. } => return (lane.max_value() as f32).max(0.0),
+            Shape::Fade {
+                fade_in, fade_out, ..
+            } => vec![fade_in, self.len.saturating_sub(fade_out)],
+            Shape::Adsr {
+                attack,
+                decay,
+                sustain,
+                ..
+            } => vec![
+                attack,
+                attack.saturating_add(decay),
+                attack.saturating_add(decay).saturating_add(sustain),
+            ],
+            Shape::Linen {
+                attack, sustain, ..
+            } => vec![attack, attack + sustain],
+        };
+        if self.len == 0 {
+            return 1.0;
+        }
+        [0, self.len - 1]
+            .into_iter()
+            .chain(
+                edges
+                    .iter()
+                    .flat_map(|&e| [e.saturating_sub(1), e])
+                    .filter(|&i| i < self.len),
+            )
+            .map(|i| self.value_at(i))
+            .fold(f32::MIN, f32::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn adsr_envelope(
+        t: &[f32],
+        attack: f32,
+        decay: f32,
+        sustain_level: f32,
+        release: f32,
+    ) -> Vec<f32> {
+        let total_samples = t.len();
+        if total_samples == 0 {
+            return Vec::new();
+        }
+        let duration =
+            t[total_samples - 1] - t[0] + if total_samples > 1 { t[1] - t[0] } else { 0.0 };
+        let sr = total_samples as f32 / duration;
+        let attack_samples = (attack * sr) as usize;
+        let decay_samples = (decay * sr) as usize;
+        let release_samples = (release * sr) as usize;
+        let sustain_samples =
+            total_samples.saturating_sub(attack_samples + decay_samples + release_samples);
+        let mut env = Vec::with_capacity(total_samples);
+        for i in 0..attack_samples {
+            env.push(i as f32 / attack_samples as f32);
+        }
+        for i in 0..decay_samples {
+            let level = 1.0 - (1.0 - sustain_level) * (i as f32 / decay_samples as f32);
+            env.push(level);
+        }
+        env.resize(env.len() + sustain_samples, sustain_level);
+        for i in 0..release_samples {
+            let level = sustain_level * (1.0 - (i as f32 / release_samples as f32));
+            env.push(level);
+        }
+        env.truncate(total_samples);
+        env
+    }
+
+    fn linen_envelope(t: &[f32], attack: f32, release: f32) -> Vec<f32> {
+        let total_samples = t.len();
+        if total_samples == 0 {
+            return Vec::new();
+        }
+        let duration =
+            t[total_samples - 1] - t[0] + if total_samples > 1 { t[1] - t[0] } else { 0.0 };
+        let sr = if duration > 0.0 {
+            total_samples as f32 / duration
+        } else {
+            44100.0
+        };
+
+        let mut attack_s = (attack.max(0.0) * sr) as usize;
+        let mut release_s = (release.max(0.0) * sr) as usize;
+        if attack_s + release_s > total_samples && attack_s + release_s > 0 {
+            let scale = total_samples as f32 / (attack_s + release_s) as f32;
+            attack_s = (attack_s as f32 * scale) as usize;
+            release_s = total_samples.saturating_sub(attack_s);
+        }
+        let sustain_s = total_samples.saturating_sub(attack_s + release_s);
+
+        let mut env = Vec::with_capacity(total_samples);
+        for i in 0..attack_s {
+            env.push(i as f32 / attack_s as f32);
+        }
+        env.resize(env.len() + sustain_s, 1.0);
+        for i in 0..release_s {
+            env.push(1.0 - (i as f32 / release_s as f32));
+        }
+        env.truncate(total_samples);
+        env
+    }
+
+    fn create_linear_fade_envelope(
+        total_duration: f32,
+        sample_rate: u32,
+        fade_duration: f32,
+        start_amp: f32,
+        end_amp: f32,
+    ) -> Vec<f32> {
+        let total_samples = (total_duration * sample_rate as f32) as usize;
+        if total_samples == 0 {
+            return Vec::new();
+        }
+        let fade_samples = (fade_duration * sample_rate as f32).min(total_samples as f32) as usize;
+        let mut env = vec![end_amp; total_samples];
+        for (i, v) in env.iter_mut().enumerate().take(fade_samples) {
+            let alpha = i as f32 / fade_samples as f32;
+            *v = start_amp + alpha * (end_amp - start_amp);
+        }
+        env
+    }
+
+    /// The whole step's envelope, as it used to be rendered up front.
+    fn rendered(env: &VolumeEnvelope, duration: f32, sample_rate: u32) -> Vec<f32> {
+        let total_samples = (duration * sample_rate as f32) as usize;
+        if total_samples == 0 {
+            return Vec::new();
+        }
+
+        match env.envelope_type.as_str() {
+            "linear_fade" => {
+                let fade_duration = env.params.get("fade_duration").copied().unwrap_or(0.0) as f32;
+                let start_amp = env.params.get("start_amp").copied().unwrap_or(0.0) as f32;
+                let end_amp = env.params.get("end_amp").copied().unwrap_or(1.0) as f32;
+                create_linear_fade_envelope(
+                    duration,
+                    sample_rate,
+                    fade_duration,
+                    start_amp,
+                    end_amp,
+                )
+            }
+            "adsr" => {
+                let attack = env.params.get("attack").copied().unwrap_or(0.01) as f32;
+                let decay = env.params.get("decay").copied().unwrap_or(0.1) as f32;
+                let sustain_level = env.params.get("sustain_level").copied().unwrap_or(0.8) as f32;
+                let release = env.params.get("release").copied().unwrap_or(0.1) as f32;
+                let dt = 1.0 / sample_rate as f32;
+                let t: Vec<f32> = (0..total_samples).map(|i| i as f32 * dt).collect();
+                adsr_envelope(&t, attack, decay, sustain_level, release)
+            }
+            "linen" => {
+                let attack = env.params.get("attack").copied().unwrap_or(0.01) as f32;
+                let release = env.params.get("release").copied().unwrap_or(0.1) as f32;
+                let dt = 1.0 / sample_rate as f32;
+                let t: Vec<f32> = (0..total_samples).map(|i| i as f32 * dt).collect();
+                linen_envelope(&t, attack, release)
+            }
+            _ => vec![1.0; total_samples],
+        }
+    }
+
+    fn envelope(kind: &str, params: &[(&str, f64)]) -> VolumeEnvelope {
+        VolumeEnvelope {
+            envelope_type: kind.to_string(),
+            params: params
+                .iter()
+                .map(|&(k, v)| (k.to_string(), v))
+                .collect::<HashMap<_, _>>(),
+            points: Vec::new(),
+            curve: None,
+            fade_type: None,
+            time_unit: None,
+        }
+    }
+
+    #[test]
+    fn envelopes_match_the_rendered_step() {
+        let cases = [
+            envelope(
+                "linear_fade",
+                &[("fade_duration", 0.7), ("start_amp", 0.2), ("end_amp", 1.3)],
+            ),
+            envelope("linear_fade", &[("fade_duration", 5.0)]),
+            envelope("linear_fade", &[]),
+            envelope("adsr", &[]),
+            envelope(
+                "adsr",
+                &[
+                    ("attack", 0.3),
+                    ("decay", 0.4),
+                    ("sustain_level", 0.5),
+                    ("release", 0.6),
+                ],
+            ),
+            envelope("adsr", &[("attack", 1.5), ("release", 1.0)]),
+            envelope("linen", &[("attack", 0.25), ("release", 0.5)]),
+            envelope("linen", &[("attack", 2.0), ("release", 1.0)]),
+            envelope("unknown", &[]),
+        ];
+        for env in &cases {
+            for (duration, sample_rate) in [(2.0, 1000), (1.37, 44_100), (0.003, 1000), (0.0, 1000)]
+            {
+                let expected = rendered(env, duration, sample_rate);
+                let streamed = Envelope::new(env, duration, sample_rate);
+                let values: Vec<f32> = (0..expected.len()).map(|i| streamed.value_at(i)).collect();
+                assert_eq!(values, expected, "{env:?} over {duration} s");
+                let last = expected.last().copied().unwrap_or(1.0);
+                assert_eq!(streamed.value_at(expected.len() + 10), last);
+                let peak = expected.iter().copied().fold(f32::MIN, f32::max);
+                if !expected.is_empty() {
+                    assert_eq!(streamed.peak(), peak, "{env:?} over {duration} s");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fades_run_in_seconds_or_fractions_on_linear_or_db_ramps() {
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+        // Four seconds at 100 Hz throughout.
+        let out = Envelope::new(&envelope("fade_out", &[("fade_duration", 1.0)]), 4.0, 100);
+        assert_eq!((out.value_at(0), out.value_at(300)), (1.0, 1.0));
+        assert!(close(out.value_at(350), 0.5));
+        assert!(close(out.value_at(399), 0.01));
+
+        let mut both = envelope("fade_in_out", &[("fade_in", 0.25), ("fade_out", 0.5)]);
+        both.time_unit = Some("fraction".to_string());
+        let both = Envelope::new(&both, 4.0, 100);
+        assert!(close(both.value_at(50), 0.5));
+        assert_eq!(both.value_at(150), 1.0);
+        assert!(close(both.value_at(300), 0.5));
+
+        // Fades longer than the step share it in proportion.
+        let squeezed = Envelope::new(
+            &envelope("fade_in_out", &[("fade_in", 3.0), ("fade_out", 3.0)]),
+            4.0,
+            100,
+        );
+        assert!(close(squeezed.value_at(100), 0.5));
+        assert!(close(squeezed.value_at(200), 1.0));
+        assert!(close(squeezed.value_at(300), 0.5));
+
+        // Half way through a dB fade up from silence is half way in dB.
+        let mut db = envelope("fade_in", &[("fade_duration", 1.0)]);
+        db.curve = Some("db".to_string());
+        let db = Envelope::new(&db, 4.0, 100);
+        assert_eq!(db.value_at(0), 0.0);
+        assert!(close(db.value_at(50), 10f32.powf(-30.0 / 20.0)));
+        assert_eq!(db.value_at(100), 1.0);
+        assert_eq!(db.peak(), 1.0);
+
+        // Legacy linear fades keep fade_type and curve among their params.
+        let linear_fade = |fade_type: &str, curve: &str| {
+            let env: VolumeEnvelope = serde_json::from_value(serde_json::json!({
+                "type": "linear_fade",
+                "params": {"fade_duration": 1.0, "start_amp": 0.0, "end_amp": 1.0,
+                           "fade_type": fade_type, "curve": curve},
+            }))
+            .unwrap();
+            Envelope::new(&env, 4.0, 100)
+        };
+        let out = linear_fade("out", "linear");
+        assert_eq!((out.value_at(0), out.value_at(299)), (0.0, 0.0));
+        assert!(close(out.value_at(350), 0.5));
+        let in_out = linear_fade("in_out", "linear");
+        assert!(close(in_out.value_at(50), 0.5));
+        assert_eq!(in_out.value_at(200), 1.0);
+        assert!(close(in_out.value_at(350), 0.5));
+        let db = linear_fade("in", "db");
+        assert!(close(db.value_at(50), 10f32.powf(-30.0 / 20.0)));
+        assert_eq!(db.value_at(200), 1.0);
+    }
+
+    #[test]
+    fn breakpoints_and_tremolo_shape_the_step() {
+        let point = |time: f64, value: f64, curve: &str| Breakpoint {
+            time,
+            value,
+            curve: curve.to_string(),
+            tension: None,
+            handles: None,
+        };
+        let mut env = envelope("breakpoints", &[]);
+        env.time_unit = Some("fraction".to_string());
+        env.points = vec![
+            point(0.0, 0.0, "linear"),
+            point(0.5, 1.0, "hold"),
+            point(0.75, 0.2, "linear"),
+        ];
+        let lane = Envelope::new(&env, 2.0, 100);
+        assert!((lane.value_at(50) - 0.5).abs() < 1e-6);
+        assert_eq!(lane.value_at(125), 1.0);
+        assert!((lane.value_at(199) - 0.2).abs() < 1e-6);
+        assert_eq!(lane.peak(), 1.0);
+
+        // Handles past 1 in y overshoot the target.
+        env.points[0].curve = "bezier".to_string();
+        env.points[0].handles = Some([0.3, 1.6, 0.6, 1.4]);
+        assert!(Envelope::new(&env, 2.0, 100).peak() > 1.1);
+
+        let tremolo = Envelope::new(&envelope("tremolo", &[("rate", 1.0)]), 2.0, 100);
+        assert_eq!(tremolo.value_at(0), 1.0);
+        assert!((tremolo.value_at(50) - 0.5).abs() < 1e-6);
+        assert!((tremolo.value_at(100) - 1.0).abs() < 1e-6);
+        assert_eq!(tremolo.peak(), 1.0);
+    }
+}
@@ -0,0 +1,539 @@
+use std::f32::consts::PI;
+
+use super::skewed_triangle_phase;
+use super::trig::sin_lut;
+
+/// Upper bound for the feedback gain so the comb never self-oscillates.
+const MAX_FEEDBACK: f32 = 0.95;
+
+/// Shortest delay in samples: the cubic read looks two samples past the
+/// integer delay, so anything shorter would read ahead of the write head.
+const MIN_DELAY_SAMPLES: f32 = 3.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FlangerShape {
+    Sine,
+    Triangle,
+}
+
+impl FlangerShape {
+    pub fn from_name(s: &str) -> Self {
+        match s {
+            "triangle" => FlangerShape::Triangle,
+            _ => FlangerShape::Sine,
+        }
+    }
+}
+
+/// Parameters that may change while the flanger is running. Every field is
+/// dezippered inside [`Flanger`], so they can be updated per sample (e.g. by
+/// transition voices) without audible steps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FlangerTargets {
+    pub rate_hz: f32,
+    pub delay_ms: f32,
+    pub depth_ms: f32,
+    pub feedback: f32,
+    pub mix: f32,
+    pub loop_hpf_hz: f32,
+    pub loop_lpf_hz: f32,
+}
+
+impl FlangerTargets {
+    pub fn lerp(&self, other: &Self, alpha: f32) -> Self {
+        let mix = |a: f32, b: f32| a + (b - a) * alpha;
+        Self {
+            rate_hz: mix(self.rate_hz, other.rate_hz),
+            delay_ms: mix(self.delay_ms, other.delay_ms),
+            depth_ms: mix(self.depth_ms, other.depth_ms),
+            feedback: mix(self.feedback, other.feedback),
+            mix: mix(self.mix, other.mix),
+            loop_hpf_hz: mix(self.loop_hpf_hz, other.loop_hpf_hz),
+            loop_lpf_hz: mix(self.loop_lpf_hz, other.loop_lpf_hz),
+        }
+    }
+}
+
+/// Full flanger configuration, mirroring the `flange*` voice parameters.
+#[derive(Clone, Debug)]
+pub struct FlangerParams {
+    pub targets: FlangerTargets,
+    pub shape: FlangerShape,
+    pub min_delay_ms: f32,
+    pub max_delay_ms: f32,
+    /// When true the delay sweeps geometrically around `delay_ms` instead of linearly.
+    pub delay_law: bool,
+    /// Cubic (true) or linear (false) fractional delay interpolation.
+    pub interp: bool,
+    /// When true the right channel LFO is offset by `spread_deg`.
+    pub stereo_mode: bool,
+    pub spread_deg: f32,
+    pub loudness_mode: bool,
+    pub loudness_tc_ms: f32,
+    pub loudness_min_gain: f32,
+    pub loudness_max_gain: f32,
+    pub dezipper_rate_ms: f32,
+    pub dezipper_delay_ms: f32,
+    pub dezipper_depth_ms: f32,
+    pub dezipper_feedback_ms: f32,
+    pub dezipper_wet_ms: f32,
+    pub dezipper_filter_ms: f32,
+}
+
+impl Default for FlangerParams {
+    fn default() -> Self {
+        Self {
+            targets: FlangerTargets {
+                rate_hz: 0.12,
+                delay_ms: 1.2,
+                depth_ms: 0.6,
+                feedback: 0.5,
+                mix: 0.3,
+                loop_hpf_hz: 0.0,
+                loop_lpf_hz: 7000.0,
+            },
+            shape: FlangerShape::Sine,
+            min_delay_ms: 0.25,
+            max_delay_ms: 8.0,
+            delay_law: false,
+            interp: false,
+            stereo_mode: false,
+            spread_deg: 0.0,
+            loudness_mode: true,
+            loudness_tc_ms: 80.0,
+            loudness_min_gain: 0.5,
+            loudness_max_gain: 2.0,
+            dezipper_rate_ms: 200.0,
+            dezipper_delay_ms: 30.0,
+            dezipper_depth_ms: 30.0,
+            dezipper_feedback_ms: 30.0,
+            dezipper_wet_ms: 40.0,
+            dezipper_filter_ms: 60.0,
+        }
+    }
+}
+
+/// One-pole parameter smoother.
+#[derive(Clone, Copy)]
+struct Smoother {
+    value: f32,
+    target: f32,
+    coeff: f32,
+}
+
+impl Smoother {
+    fn new(value: f32, time_ms: f32, sample_rate: f32) -> Self {
+        Self {
+            value,
+            target: value,
+            coeff: smoothing_coeff(time_ms, sample_rate),
+        }
+    }
+
+    #[inline]
+    fn next(&mut self) -> f32 {
+        self.value = self.target + (self.value - self.target) * self.coeff;
+        self.value
+    }
+}
+
+fn smoothing_coeff(time_ms: f32, sample_rate: f32) -> f32 {
+    if time_ms <= 0.0 {
+        0.0
+    } else {
+        (-1.0 / (time_ms * 0.001 * sample_rate)).exp()
+    }
+}
+
+fn one_pole_coeff(freq: f32, sample_rate: f32) -> f32 {
+    (-2.0 * PI * freq / sample_rate).exp()
+}
+
+/// Per-channel delay line, feedback filters and loudness follower state.
+struct FlangerChannel {
+    buffer: Vec<f32>,
+    write_pos: usize,
+    hpf_x1: f32,
+    hpf_y1: f32,
+    lpf_y1: f32,
+    env_in: f32,
+    env_out: f32,
+    gain: f32,
+}
+
+impl FlangerChannel {
+    fn new(len: usize) -> Self {
+        Self {
+            buffer: vec![0.0; len],
+            write_pos: 0,
+            hpf_x1: 0.0,
+            hpf_y1: 0.0,
+            lpf_y1: 0.0,
+            env_in: 0.0,
+            env_out: 0.0,
+            gain: 1.0,
+        }
+    }
+
+    #[inline]
+    fn read(&self, delay_samples: f32, cubic: bool) -> f32 {
+        let mask = self.buffer.len() - 1;
+        let pos = self.write_pos as f32 - delay_samples;
+        let base = pos.floor();
+        let frac = pos - base;
+        let idx = base as isize;
+        let at = |offset: isize| self.buffer[((idx + offset) as usize) & mask];
+        if cubic {
+            // 4-point, 3rd-order Hermite
+            let xm1 = at(-1);
+            let x0 = at(0);
+            let x1 = at(1);
+            let x2 = at(2);
+            let c1 = 0.5 * (x1 - xm1);
+            let c2 = xm1 - 2.5 * x0 + 2.0 * x1 - 0.5 * x2;
+            let c3 = 0.5 * (x2 - xm1) + 1.5 * (x0 - x1);
+            ((c3 * frac + c2) * frac + c1) * frac + x0
+        } else {
+            let a = at(0);
+            let b = at(1);
+            a + (b - a) * frac
+        }
+    }
+}
+
+/// Stereo feedback flanger with dezippered controls, band-limited feedback
+/// loop and optional loudness compensation.
+pub struct Flanger {
+    sample_rate: f32,
+    shape: FlangerShape,
+    min_delay_ms: f32,
+    max_delay_ms: f32,
+    delay_law: bool,
+    interp: bool,
+    spread: f32,
+    loudness_mode: bool,
+    loudness_coeff: f32,
+    loudness_min_gain: f32,
+    loudness_max_gain: f32,
+    rate: Smoother,
+    delay: Smoother,
+    depth: Smoother,
+    feedback: Smoother,
+    mix: Smoother,
+    hpf_hz: Smoother,
+    lpf_hz: Smoother,
+    hpf_coeff: f32,
+    lpf_coeff: f32,
+    last_hpf_hz: f32,
+    last_lpf_hz: f32,
+    lfo_phase: f32,
+    channels: [FlangerChannel; 2],
+}
+
+impl Flanger {
+    pub fn new(params: &FlangerParams, sample_rate: f32) -> Self {
+        let t = &params.targets;
+        let min_delay_ms = params
+            .min_delay_ms
+            .max(MIN_DELAY_SAMPLES / sample_rate * 1000.0);
+        let max_delay_ms = params.max_delay_ms.max(min_delay_ms + 0.01);
+        // Room for the longest delay plus the interpolation taps.
+        let len = ((max_delay_ms * 0.001 * sample_rate).ceil() as usize + 4).next_power_of_two();
+        let spread = if params.stereo_mode {
+            params.spread_deg.to_radians() / (2.0 * PI)
+        } else {
+            0.0
+        };
+        let mut flanger = Self {
+            sample_rate,
+            shape: params.shape,
+            min_delay_ms,
+            max_delay_ms,
+            delay_law: params.delay_law,
+            interp: params.interp,
+            spread,
+            loudness_mode: params.loudness_mode,
+            loudness_coeff: smoothing_coeff(params.loudness_tc_ms, sample_rate),
+            loudness_min_gain: params.loudness_min_gain.min(params.loudness_max_gain),
+            loudness_max_gain: params.loudness_max_gain.max(params.loudness_min_gain),
+            rate: Smoother::new(t.rate_hz, params.dezipper_rate_ms, sample_rate),
+            delay: Smoother::new(t.delay_ms, params.dezipper_delay_ms, sample_rate),
+            depth: Smoother::new(t.depth_ms, params.dezipper_depth_ms, sample_rate),
+            feedback: Smoother::new(
+                t.feedback.clamp(-MAX_FEEDBACK, MAX_FEEDBACK),
+                params.dezipper_feedback_ms,
+                sample_rate,
+            ),
+            mix: Smoother::new(t.mix.clamp(0.0, 1.0), params.dezipper_wet_ms, sample_rate),
+            hpf_hz: Smoother::new(t.loop_hpf_hz, params.dezipper_filter_ms, sample_rate),
+            lpf_hz: Smoother::new(t.loop_lpf_hz, params.dezipper_filter_ms, sample_rate),
+            hpf_coeff: 0.0,
+            lpf_coeff: 0.0,
+            last_hpf_hz: f32::NAN,
+            last_lpf_hz: f32::NAN,
+            lfo_phase: 0.0,
+            channels: [FlangerChannel::new(len), FlangerChannel::new(len)],
+        };
+        flanger.update_filter_coeffs(t.loop_hpf_hz, t.loop_lpf_hz);
+        flanger
+    }
+
+    /// Largest gain the flanger applies to a full-scale input at its current
+    /// targets.
+    pub fn max_gain(&self) -> f32 {
+        self.gain_bound(self.mix.target, self.feedback.target.abs())
+    }
+
+    /// Largest gain while the targets move from the current ones to `end`.
+    pub fn max_gain_towards(&self, end: &FlangerTargets) -> f32 {
+        self.gain_bound(
+            self.mix.target.max(end.mix.clamp(0.0, 1.0)),
+            self.feedback
+                .target
+                .abs()
+                .max(end.feedback.abs().min(MAX_FEEDBACK)),
+        )
+    }
+
+    /// The feedback loop sums a geometric series of the wet signal, which
+    /// its filters only ever cut, and loudness compensation can then boost
+    /// by up to its maximum gain.
+    fn gain_bound(&self, mix: f32, feedback: f32) -> f32 {
+        let comb = 1.0 - mix + mix / (1.0 - feedback);
+        let loudness = if self.loudness_mode {
+            self.loudness_max_gain.max(1.0)
+        } else {
+            1.0
+        };
+        comb * loudness
+    }
+
+    /// Set new targets; the running values glide towards them using the
+    /// configured dezipper times.
+    pub fn set_targets(&mut self, t: &FlangerTargets) {
+        self.rate.target = t.rate_hz;
+        self.delay.target = t.delay_ms;
+        self.depth.target = t.depth_ms;
+        self.feedback.target = t.feedback.clamp(-MAX_FEEDBACK, MAX_FEEDBACK);
+        self.mix.target = t.mix.clamp(0.0, 1.0);
+        self.hpf_hz.target = t.loop_hpf_hz;
+        self.lpf_hz.target = t.loop_lpf_hz;
+    }
+
+    fn update_filter_coeffs(&mut self, hpf_hz: f32, lpf_hz: f32) {
+        let nyquist = self.sample_rate * 0.49;
+        // Only recompute the exponentials when the cutoff moved noticeably.
+        if (hpf_hz - self.last_hpf_hz).abs() > 0.01 || self.last_hpf_hz.is_nan() {
+            self.hpf_coeff = if hpf_hz > 0.0 && hpf_hz < nyquist {
+                one_pole_coeff(hpf_hz, self.sample_rate)
+            } else {
+                1.0
+            };
+            self.last_hpf_hz = hpf_hz;
+        }
+        if (lpf_hz - self.last_lpf_hz).abs() > 0.01 || self.last_lpf_hz.is_nan() {
+            self.lpf_coeff = if lpf_hz > 0.0 && lpf_hz < nyquist {
+                one_pole_coeff(lpf_hz, self.sample_rate)
+            } else {
+                0.0
+            };
+            self.last_lpf_hz = lpf_hz;
+        }
+    }
+
+    #[inline]
+    fn lfo(&self, phase: f32) -> f32 {
+        let frac = phase.rem_euclid(1.0);
+        match self.shape {
+            FlangerShape::Sine => sin_lut(2.0 * PI * frac),
+            FlangerShape::Triangle => skewed_triangle_phase(frac, 0.0),
+        }
+    }
+
+    #[inline]
+    fn delay_for(&self, center_ms: f32, depth_ms: f32, lfo: f32) -> f32 {
+        let ms = if self.delay_law && center_ms > 0.0 {
+            // Geometric sweep keeps the comb notch spacing moving at a constant
+            // musical rate rather than bunching up at short delays.
+            let ratio = ((center_ms + depth_ms.abs()) / center_ms).max(1.0);
+            center_ms * ratio.powf(lfo)
+        } else {
+            center_ms + depth_ms * lfo
+        };
+        ms.clamp(self.min_delay_ms, self.max_delay_ms) * 0.001 * self.sample_rate
+    }
+
+    /// Process one stereo frame.
+    #[inline]
+    pub fn process(&mut self, in_l: f32, in_r: f32) -> (f32, f32) {
+        let rate = self.rate.next();
+        let center = self.delay.next();
+        let depth = self.depth.next();
+        let feedback = self.feedback.next();
+        let mix = self.mix.next();
+        let hpf_hz = self.hpf_hz.next();
+        let lpf_hz = self.lpf_hz.next();
+        self.update_filter_coeffs(hpf_hz, lpf_hz);
+
+        let lfo_l = self.lfo(self.lfo_phase);
+        let lfo_r = self.lfo(self.lfo_phase + self.spread);
+        self.lfo_phase = (self.lfo_phase + rate / self.sample_rate).rem_euclid(1.0);
+
+        let delays = [
+            self.delay_for(center, depth, lfo_l),
+            self.delay_for(center, depth, lfo_r),
+        ];
+        let inputs = [in_l, in_r];
+        let mut outputs = [0.0f32; 2];
+
+        for ch in 0..2 {
+            let x = inputs[ch];
+            let hpf_coeff = self.hpf_coeff;
+            let lpf_coeff = self.lpf_coeff;
+            let interp = self.interp;
+            let state = &mut self.channels[ch];
+            let wet = state.read(delays[ch], interp);
+
+            // Band-limit the recirculating signal so repeated passes don't
+            // build up rumble or fizz.
+            let mut fb = wet;
+            if hpf_coeff < 1.0 {
+                let y = hpf_coeff * (state.hpf_y1 + fb - state.hpf_x1);
+                state.hpf_x1 = fb;
+                state.hpf_y1 = y;
+                fb = y;
+            }
+            if lpf_coeff > 0.0 {
+                state.lpf_y1 = fb + (state.lpf_y1 - fb) * lpf_coeff;
+                fb = state.lpf_y1;
+            }
+
+            let mask = state.buffer.len() - 1;
+            state.buffer[state.write_pos] = x + feedback * fb;
+            state.write_pos = (state.write_pos + 1) & mask;
+
+            let mut y = x * (1.0 - mix) + wet * mix;
+
+            if self.loudness_mode {
+                let c = self.loudness_coeff;
+                state.env_in = x * x + (state.env_in - x * x) * c;
+                state.env_out = y * y + (state.env_out - y * y) * c;
+                let target = if state.env_out > 1e-12 && state.env_in > 1e-12 {
+                    (state.env_in / state.env_out)
+                        .sqrt()
+                        .clamp(self.loudness_min_gain, self.loudness_max_gain)
+                } else {
+                    1.0
+                };
+                state.gain = target + (state.gain - target) * c;
+                y *= state.gain;
+            }
+            outputs[ch] = y;
+        }
+
+        (outputs[0], outputs[1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Flanger, FlangerParams, FlangerTargets};
+
+    fn sine_block(freq: f32, sample_rate: f32, n: usize) -> Vec<f32> {
+        (0..n)
+            .map(|i| (2.0 * std::f32::consts::PI * freq * i as f32 / sample_rate).sin())
+            .collect()
+    }
+
+    #[test]
+    fn zero_mix_passes_dry_signal() {
+        let sample_rate = 48_000.0;
+        let mut params = FlangerParams::default();
+        params.targets.mix = 0.0;
+        params.loudness_mode = false;
+        let mut flanger = Flanger::new(&params, sample_rate);
+        for x in sine_block(220.0, sample_rate, 4800) {
+            let (l, r) = flanger.process(x, -x);
+            assert!((l - x).abs() < 1e-6);
+            assert!((r + x).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn zero_min_delay_reads_only_written_samples() {
+        let sample_rate = 48_000.0;
+        let d = FlangerParams::default();
+        let params = FlangerParams {
+            targets: FlangerTargets {
+                delay_ms: 0.0,
+                depth_ms: 0.0,
+                feedback: 0.0,
+                mix: 1.0,
+                ..d.targets
+            },
+            min_delay_ms: 0.0,
+            interp: true,
+            loudness_mode: false,
+            ..d
+        };
+        // An impulse must come out of the wet path after the shortest
+        // delay, never before it or out of unwritten buffer.
+        let mut flanger = Flanger::new(&params, sample_rate);
+        let out: Vec<f32> = (0..16)
+            .map(|i| flanger.process(if i == 0 { 1.0 } else { 0.0 }, 0.0).0)
+            .collect();
+        let silent = |ys: &[f32]| ys.iter().all(|y| y.abs() < 1e-5);
+        assert!(silent(&out[..3]), "{out:?}");
+        assert!((out[3] - 1.0).abs() < 1e-5, "{out:?}");
+        assert!(silent(&out[4..]), "{out:?}");
+    }
+
+    #[test]
+    fn max_gain_bounds_feedback_and_loudness() {
+        let sample_rate = 48_000.0;
+        let d = FlangerParams::default();
+        let params = FlangerParams {
+            targets: FlangerTargets {
+                feedback: 0.9,
+                mix: 1.0,
+                loop_lpf_hz: 0.0,
+                ..d.targets
+            },
+            loudness_mode: false,
+            ..d
+        };
+        let mut flanger = Flanger::new(&params, sample_rate);
+        let bound = flanger.max_gain();
+        assert!((bound - 10.0).abs() < 1e-3, "{bound}");
+        let mut peak = 0.0f32;
+        // A low tone lines up with the comb's first peak at short delays.
+        for x in sine_block(30.0, sample_rate, 48_000) {
+            let (l, r) = flanger.process(x, x);
+            peak = peak.max(l.abs()).max(r.abs());
+        }
+        assert!(peak <= bound, "{peak} vs {bound}");
+        assert!(peak > 2.0, "feedback builds up: {peak}");
+    }
+
+    #[test]
+    fn loudness_mode_keeps_output_level_near_input() {
+        let sample_rate = 48_000.0;
+        let mut params = FlangerParams::default();
+        params.targets.mix = 0.5;
+        params.targets.feedback = 0.9;
+        let input = sine_block(440.0, sample_rate, 48_000);
+        let mut flanger = Flanger::new(&params, sample_rate);
+        let mut sum_in = 0.0f32;
+        let mut sum_out = 0.0f32;
+        for (i, &x) in input.iter().enumerate() {
+            let (l, _) = flanger.process(x, x);
+            assert!(l.is_finite());
+            if i > 24_000 {
+                sum_in += x * x;
+                sum_out += l * l;
+            }
+        }
+        let ratio = (sum_out / sum_in).sqrt();
+        assert!(ratio > 0.5 && ratio < 2.0, "level ratio {ratio}");
+    }
+}
@@ -1,8 +1,13 @@
 use rand::Rng;
 use rand_distr::{Distribution, Normal};
 
+pub mod envelope;
+pub mod flanger;
 pub mod noise_flanger;
+pub mod spatializer;
+pub mod subliminal;
 pub mod trig;
+pub mod wavetable;
 
 pub fn generate_pink_noise_samples(n_samples: usize) -> Vec<f32> {
     // Pink noise via Paul Kellett filter with Gaussian input
@@ -54,36 +59,6 @@ pub fn sine_wave(freq: f32, t: f32, phase: f32) -> f32 {
     crate::dsp::trig::sin_lut(2.0 * std::f32::consts::PI * freq * t + phase)
 }
 
-pub fn adsr_envelope(t: &[f32], attack: f32, decay: f32, sustain_level: f32, release: f32) -> Vec<f32> {
-    let total_samples = t.len();
-    if total_samples == 0 {
-        return Vec::new();
-    }
-    let duration = t[total_samples - 1] - t[0] + if total_samples > 1 { t[1] - t[0] } else { 0.0 };
-    let sr = total_samples as f32 / duration;
-    let attack_samples = (attack * sr) as usize;
-    let decay_samples = (decay * sr) as usize;
-    let release_samples = (release * sr) as usize;
-    let sustain_samples = total_samples.saturating_sub(attack_samples + decay_samples + release_samples);
-    let mut env = Vec::with_capacity(total_samples);
-    for i in 0..attack_samples {
-        env.push(i as f32 / attack_samples as f32);
-    }
-    for i in 0..decay_samples {
-        let level = 1.0 - (1.0 - sustain_level) * (i as f32 / decay_samples as f32);
-        env.push(level);
-    }
-    for _ in 0..sustain_samples {
-        env.push(sustain_level);
-    }
-    for i in 0..release_samples {
-        let level = sustain_level * (1.0 - (i as f32 / release_samples as f32));
-        env.push(level);
-    }
-    env.truncate(total_samples);
-    env
-}
-
 pub fn pan2(signal: f32, pan: f32) -> (f32, f32) {
     let pan = pan.clamp(-1.0, 1.0);
     let angle = (pan + 1.0) * std::f32::consts::FRAC_PI_4;
@@ -92,6 +67,23 @@ pub fn pan2(signal: f32, pan: f32) -> (f32, f32) {
     (left, right)
 }
 
+/// Balance a stereo pair without mixing the channels, so interaural content
+/// such as a binaural beat survives. Both laws are unity at the centre; the
+/// linear law only attenuates the far side, the equal-power law keeps the
+/// total power constant (+3 dB on the near side at the extremes).
+pub fn balance2(left: f32, right: f32, pan: f32, equal_power: bool) -> (f32, f32) {
+    let pan = pan.clamp(-1.0, 1.0);
+    if equal_power {
+        let angle = (pan + 1.0) * std::f32::consts::FRAC_PI_4;
+        (
+            left * crate::dsp::trig::cos_lut(angle) * std::f32::consts::SQRT_2,
+            right * crate::dsp::trig::sin_lut(angle) * std::f32::consts::SQRT_2,
+        )
+    } else {
+        (left * (1.0 - pan).min(1.0), right * (1.0 + pan).min(1.0))
+    }
+}
+
 pub fn trapezoid_envelope(
     t_in_cycle: f32,
     cycle_len: f32,
@@ -187,82 +179,6 @@ pub fn sine_wave_varying(freq_array: &[f32], t: &[f32], _sample_rate: f32) -> Ve
     out
 }
 
-pub fn linen_envelope(t: &[f32], attack: f32, release: f32) -> Vec<f32> {
-    let total_samples = t.len();
-    if total_samples == 0 {
-        return Vec::new();
-    }
-    let duration = t[total_samples - 1] - t[0] + if total_samples > 1 { t[1] - t[0] } else { 0.0 };
-    let sr = if duration > 0.0 { total_samples as f32 / duration } else { 44100.0 };
-
-    let mut attack_s = (attack.max(0.0) * sr) as usize;
-    let mut release_s = (release.max(0.0) * sr) as usize;
-    if attack_s + release_s > total_samples && attack_s + release_s > 0 {
-        let scale = total_samples as f32 / (attack_s + release_s) as f32;
-        attack_s = (attack_s as f32 * scale) as usize;
-        release_s = total_samples.saturating_sub(attack_s);
-    }
-    let sustain_s = total_samples.saturating_sub(attack_s + release_s);
-
-    let mut env = Vec::with_capacity(total_samples);
-    for i in 0..attack_s {
-        env.push(i as f32 / attack_s as f32);
-    }
-    for _ in 0..sustain_s {
-        env.push(1.0);
-    }
-    for i in 0..release_s {
-        env.push(1.0 - (i as f32 / release_s as f32));
-    }
-    env.truncate(total_samples);
-    env
-}
-
-pub fn create_linear_fade_envelope(
-    total_duration: f32,
-    sample_rate: u32,
-    fade_duration: f32,
-    start_amp: f32,
-    end_amp: f32,
-    fade_type: &str,
-) -> Vec<f32> {
-    let total_samples = (total_duration * sample_rate as f32) as usize;
-    if total_samples == 0 {
-        return Vec::new();
-    }
-    let fade_samples = (fade_duration * sample_rate as f32).min(total_samples as f32) as usize;
-    let mut env = vec![1.0f32; total_samples];
-    if fade_samples == 0 {
-        let fill = if fade_type == "in" { end_amp } else { start_amp };
-        env.fill(fill);
-        return env;
-    }
-
-    match fade_type {
-        "in" => {
-            for i in 0..fade_samples {
-                let alpha = i as f32 / fade_samples as f32;
-                env[i] = start_amp + alpha * (end_amp - start_amp);
-            }
-            for v in env.iter_mut().skip(fade_samples) {
-                *v = end_amp;
-            }
-        }
-        "out" => {
-            let sustain = total_samples.saturating_sub(fade_samples);
-            for i in 0..sustain {
-                env[i] = start_amp;
-            }
-            for i in 0..fade_samples {
-                let alpha = i as f32 / fade_samples as f32;
-                env[sustain + i] = start_amp + alpha * (end_amp - start_amp);
-            }
-        }
-        _ => {}
-    }
-    env
-}
-
 pub fn calculate_transition_alpha(
     total_duration: f32,
     sample_rate: f32,
@@ -309,61 +225,3 @@ pub fn calculate_transition_alpha(
 
     alpha
 }
-
-use crate::models::VolumeEnvelope;
-
-/// Build a volume envelope vector from a `VolumeEnvelope` description.
-/// If the type is unknown or parameters are missing, a flat envelope is returned.
-pub fn build_volume_envelope(
-    env: &VolumeEnvelope,
-    duration: f32,
-    sample_rate: u32,
-) -> Vec<f32> {
-    let total_samples = (duration * sample_rate as f32) as usize;
-    if total_samples == 0 {
-        return Vec::new();
-    }
-
-    match env.envelope_type.as_str() {
-        "linear_fade" => {
-            let fade_duration = env
-                .params
-                .get("fade_duration")
-                .copied()
-                .unwrap_or(0.0) as f32;
-            let start_amp = env
-                .params
-                .get("start_amp")
-                .copied()
-                .unwrap_or(0.0) as f32;
-            let end_amp = env
-                .params
-                .get("end_amp")
-                .copied()
-                .unwrap_or(1.0) as f32;
-            let fade_type = "in"; // only 'in' currently used
-            create_linear_fade_envelope(duration, sample_rate, fade_duration, start_amp, end_amp, fade_type)
-        }
-        "adsr" => {
-            let attack = env.params.get("attack").copied().unwrap_or(0.01) as f32;
-            let decay = env.params.get("decay").copied().unwrap_or(0.1) as f32;
-            let sustain_level = env
-                .params
-                .get("sustain_level")
-                .copied()
-                .unwrap_or(0.8) as f32;
-            let release = env.params.get("release").copied().unwrap_or(0.1) as f32;
-            let dt = 1.0 / sample_rate as f32;
-            let t: Vec<f32> = (0..total_samples).map(|i| i as f32 * dt).collect();
-            adsr_envelope(&t, attack, decay, sustain_level, release)
-        }
-        "linen" => {
-            let attack = env.params.get("attack").copied().unwrap_or(0.01) as f32;
-            let release = env.params.get("release").copied().unwrap_or(0.1) as f32;
-            let dt = 1.0 / sample_rate as f32;
-            let t: Vec<f32> = (0..total_samples).map(|i| i as f32 * dt).collect();
-            linen_envelope(&t, attack, release)
-        }
-        _ => vec![1.0; total_samples],
-    }
-}
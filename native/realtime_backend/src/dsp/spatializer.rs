@@ -0,0 +1,447 @@
+use std::f32::consts::PI;
+
+use super::trig::{cos_lut, sin_lut};
+
+const SPEED_OF_SOUND_M_S: f32 = 343.0;
+/// Corner of the shelf used to model high-frequency air absorption.
+const AIR_XOVER_HZ: f32 = 4000.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpatialDecoder {
+    /// Spherical head model: equal-power level split plus Woodworth ITD and a
+    /// high-band ILD on the far ear.
+    ItdHead,
+    /// Plain equal-power panning, no interaural delay or shelving.
+    Pan,
+}
+
+impl SpatialDecoder {
+    pub fn from_name(s: &str) -> Self {
+        match s {
+            "pan" | "equal_power" => SpatialDecoder::Pan,
+            _ => SpatialDecoder::ItdHead,
+        }
+    }
+}
+
+/// Position keyframe. Azimuth is in degrees, 0 = front, +90 = right; values
+/// are interpolated linearly so e.g. 0 -> 720 describes two full orbits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrajectoryPoint {
+    pub time: f32,
+    pub azimuth_deg: f32,
+    pub distance_m: f32,
+}
+
+/// Full spatializer configuration, mirroring the `spatial*` voice parameters.
+#[derive(Clone, Debug)]
+pub struct SpatialParams {
+    /// Keyframes sorted by time. When empty the source sits at
+    /// `azimuth_deg`/`distance_m`.
+    pub trajectory: Vec<TrajectoryPoint>,
+    pub azimuth_deg: f32,
+    pub distance_m: f32,
+    pub decoder: SpatialDecoder,
+    pub use_itd_ild: bool,
+    pub head_radius_m: f32,
+    pub ild_max_db: f32,
+    pub ild_xover_hz: f32,
+    pub itd_scale: f32,
+    /// Half the angle between the virtual sources of the left and right input channels.
+    pub ear_angle_deg: f32,
+    pub max_deg_per_s: f32,
+    pub max_delay_step_samples: f32,
+    pub min_distance_m: f32,
+    pub ref_distance_m: f32,
+    pub rolloff: f32,
+    pub hf_roll_db_per_m: f32,
+    pub dezipper_theta_ms: f32,
+    pub dezipper_dist_ms: f32,
+}
+
+impl Default for SpatialParams {
+    fn default() -> Self {
+        Self {
+            trajectory: Vec::new(),
+            azimuth_deg: 0.0,
+            distance_m: 1.0,
+            decoder: SpatialDecoder::ItdHead,
+            use_itd_ild: true,
+            head_radius_m: 0.0875,
+            ild_max_db: 3.0,
+            ild_xover_hz: 700.0,
+            itd_scale: 1.0,
+            ear_angle_deg: 30.0,
+            max_deg_per_s: 90.0,
+            max_delay_step_samples: 0.02,
+            min_distance_m: 0.1,
+            ref_distance_m: 1.0,
+            rolloff: 1.0,
+            hf_roll_db_per_m: 0.0,
+            dezipper_theta_ms: 25.0,
+            dezipper_dist_ms: 60.0,
+        }
+    }
+}
+
+impl SpatialParams {
+    /// Azimuth (degrees) and distance (metres) at time `t`, holding the first
+    /// and last keyframes outside the trajectory.
+    pub fn position_at(&self, t: f32) -> (f32, f32) {
+        let points = &self.trajectory;
+        match points.len() {
+            0 => (self.azimuth_deg, self.distance_m),
+            1 => (points[0].azimuth_deg, points[0].distance_m),
+            _ => {
+                let idx = points.partition_point(|p| p.time <= t);
+                if idx == 0 {
+                    return (points[0].azimuth_deg, points[0].distance_m);
+                }
+                if idx >= points.len() {
+                    let last = points[points.len() - 1];
+                    return (last.azimuth_deg, last.distance_m);
+                }
+                let a = points[idx - 1];
+                let b = points[idx];
+                let span = b.time - a.time;
+                let frac = if span > 0.0 { (t - a.time) / span } else { 1.0 };
+                (
+                    a.azimuth_deg + (b.azimuth_deg - a.azimuth_deg) * frac,
+                    a.distance_m + (b.distance_m - a.distance_m) * frac,
+                )
+            }
+        }
+    }
+
+    /// Gain applied for a source at `distance_m` (inverse distance law).
+    pub fn distance_gain(&self, distance_m: f32) -> f32 {
+        let d = distance_m.max(self.min_distance_m).max(1e-3);
+        (self.ref_distance_m.max(1e-3) / d).powf(self.rolloff)
+    }
+
+    /// Loudest distance gain the trajectory will reach.
+    pub fn peak_distance_gain(&self) -> f32 {
+        if self.trajectory.is_empty() {
+            self.distance_gain(self.distance_m)
+        } else {
+            // Distance is interpolated linearly, so the extremes sit on keyframes.
+            self.trajectory
+                .iter()
+                .map(|p| self.distance_gain(p.distance_m))
+                .fold(0.0f32, f32::max)
+        }
+    }
+}
+
+#[inline]
+fn one_pole_coeff(freq: f32, sample_rate: f32) -> f32 {
+    (-2.0 * PI * freq / sample_rate).exp()
+}
+
+#[inline]
+fn smoothing_coeff(time_ms: f32, sample_rate: f32) -> f32 {
+    if time_ms <= 0.0 {
+        0.0
+    } else {
+        (-1.0 / (time_ms * 0.001 * sample_rate)).exp()
+    }
+}
+
+/// Signal path from one virtual source to one ear.
+#[derive(Clone, Copy, Default)]
+struct EarPath {
+    delay: f32,
+    ild_lp: f32,
+}
+
+/// Per input channel state: the virtual source's delay line and air filter.
+struct Source {
+    buffer: Vec<f32>,
+    write_pos: usize,
+    air_lp: f32,
+    ears: [EarPath; 2],
+}
+
+impl Source {
+    fn new(len: usize) -> Self {
+        Self {
+            buffer: vec![0.0; len],
+            write_pos: 0,
+            air_lp: 0.0,
+            ears: [EarPath::default(); 2],
+        }
+    }
+
+    #[inline]
+    fn read(&self, delay: f32) -> f32 {
+        let mask = self.buffer.len() - 1;
+        // write_pos already points past the newest sample
+        let pos = self.write_pos as f32 - 1.0 - delay;
+        let base = pos.floor();
+        let frac = pos - base;
+        let idx = base as isize as usize;
+        let a = self.buffer[idx & mask];
+        let b = self.buffer[idx.wrapping_add(1) & mask];
+        a + (b - a) * frac
+    }
+}
+
+/// Places a stereo signal on a (moving) position around the listener. Each
+/// input channel becomes a virtual source `ear_angle_deg` either side of the
+/// trajectory azimuth, so binaural content keeps its channel separation at
+/// the front while the whole image can still be moved.
+pub struct Spatializer {
+    params: SpatialParams,
+    sample_rate: f32,
+    head_model: bool,
+    theta: f32,
+    distance: f32,
+    theta_coeff: f32,
+    dist_coeff: f32,
+    max_theta_step: f32,
+    max_delay_step: f32,
+    ear_angle: f32,
+    itd_max_samples: f32,
+    ild_coeff: f32,
+    air_coeff: f32,
+    last_distance: f32,
+    dist_gain: f32,
+    air_gain: f32,
+    sample_idx: usize,
+    primed: bool,
+    sources: [Source; 2],
+}
+
+impl Spatializer {
+    pub fn new(params: SpatialParams, sample_rate: f32) -> Self {
+        let head_model = params.use_itd_ild && params.decoder == SpatialDecoder::ItdHead;
+        let itd_max_samples = params.itd_scale.abs() * params.head_radius_m.max(0.0)
+            / SPEED_OF_SOUND_M_S
+            * (PI * 0.5 + 1.0)
+            * sample_rate;
+        let len = (itd_max_samples.ceil() as usize + 4).next_power_of_two();
+        let (theta_deg, distance) = params.position_at(0.0);
+        let max_theta_step = if params.max_deg_per_s > 0.0 {
+            (params.max_deg_per_s / sample_rate).to_radians()
+        } else {
+            f32::INFINITY
+        };
+        let max_delay_step = if params.max_delay_step_samples > 0.0 {
+            params.max_delay_step_samples
+        } else {
+            f32::INFINITY
+        };
+        Self {
+            sample_rate,
+            head_model,
+            theta: theta_deg.to_radians(),
+            distance,
+            theta_coeff: smoothing_coeff(params.dezipper_theta_ms, sample_rate),
+            dist_coeff: smoothing_coeff(params.dezipper_dist_ms, sample_rate),
+            max_theta_step,
+            max_delay_step,
+            ear_angle: params.ear_angle_deg.to_radians(),
+            itd_max_samples,
+            ild_coeff: one_pole_coeff(params.ild_xover_hz.max(1.0), sample_rate),
+            air_coeff: one_pole_coeff(AIR_XOVER_HZ.min(sample_rate * 0.45), sample_rate),
+            last_distance: f32::NAN,
+            dist_gain: 1.0,
+            air_gain: 1.0,
+            sample_idx: 0,
+            primed: false,
+            sources: [Source::new(len), Source::new(len)],
+            params,
+        }
+    }
+
+    pub fn params(&self) -> &SpatialParams {
+        &self.params
+    }
+
+    /// Largest gain from a full-scale input to either ear. Both virtual
+    /// sources reach each ear, so an ear can take more than unity from them
+    /// together; the air and ILD shelves only ever cut.
+    pub fn peak_gain(&self) -> f32 {
+        let ear_sum = |theta: f32| {
+            let (a, _, _) = self.ear_response(theta - self.ear_angle);
+            let (b, _, _) = self.ear_response(theta + self.ear_angle);
+            (a[0] + b[0]).max(a[1] + b[1])
+        };
+        let sources = if self.params.trajectory.is_empty() {
+            ear_sum(self.theta)
+        } else {
+            // A moving source can face any way between keyframes. Each ear
+            // gain changes by at most pi/4 per radian, so scanning in half
+            // degrees and allowing for the gap bounds the sum.
+            let step = 0.5f32.to_radians();
+            (0..720)
+                .map(|i| ear_sum(i as f32 * step))
+                .fold(0.0f32, f32::max)
+                + PI * 0.5 * step * 0.5
+        };
+        sources * self.params.peak_distance_gain()
+    }
+
+    /// Move a fresh spatializer to `frame` frames along its trajectory.
+    pub fn seek(&mut self, frame: usize) {
+        self.sample_idx = frame;
+        let (deg, distance) = self.params.position_at(frame as f32 / self.sample_rate);
+        self.theta = deg.to_radians();
+        self.distance = distance;
+    }
+
+    /// Equal-power ear gains, ITD (samples) and far-ear high-band gain for a
+    /// source at `angle` radians. Returned as `([gain_l, gain_r], [delay_l, delay_r], [ild_l, ild_r])`.
+    #[inline]
+    fn ear_response(&self, angle: f32) -> ([f32; 2], [f32; 2], [f32; 2]) {
+        let lateral = sin_lut(angle);
+        let pan_angle = (lateral + 1.0) * 0.25 * PI;
+        let gains = [cos_lut(pan_angle), sin_lut(pan_angle)];
+        if !self.head_model {
+            return (gains, [0.0; 2], [1.0; 2]);
+        }
+        // Woodworth ITD uses the lateral angle folded into [-pi/2, pi/2].
+        let phi = lateral.clamp(-1.0, 1.0).asin().abs();
+        let itd = self.itd_max_samples * (phi + phi.sin()) / (PI * 0.5 + 1.0);
+        let ild = 10f32.powf(-self.params.ild_max_db * lateral.abs() / 20.0);
+        if lateral >= 0.0 {
+            // Source on the right: left ear is the far ear.
+            (gains, [itd, 0.0], [ild, 1.0])
+        } else {
+            (gains, [0.0, itd], [1.0, ild])
+        }
+    }
+
+    /// Process one stereo frame.
+    #[inline]
+    pub fn process(&mut self, in_l: f32, in_r: f32) -> (f32, f32) {
+        let t = self.sample_idx as f32 / self.sample_rate;
+        self.sample_idx += 1;
+        let (target_deg, target_dist) = self.params.position_at(t);
+
+        let target_theta = target_deg.to_radians();
+        let smoothed = target_theta + (self.theta - target_theta) * self.theta_coeff;
+        let step = (smoothed - self.theta).clamp(-self.max_theta_step, self.max_theta_step);
+        self.theta += step;
+        self.distance = target_dist + (self.distance - target_dist) * self.dist_coeff;
+
+        if (self.distance - self.last_distance).abs() > 1e-4 || self.last_distance.is_nan() {
+            self.dist_gain = self.params.distance_gain(self.distance);
+            self.air_gain =
+                10f32.powf(-self.params.hf_roll_db_per_m * self.distance.max(0.0) / 20.0);
+            self.last_distance = self.distance;
+        }
+
+        let angles = [self.theta - self.ear_angle, self.theta + self.ear_angle];
+        let inputs = [in_l, in_r];
+        let mut out = [0.0f32; 2];
+
+        for (src_idx, &x) in inputs.iter().enumerate() {
+            let (gains, delays, ilds) = self.ear_response(angles[src_idx]);
+            let primed = self.primed;
+            let max_delay_step = self.max_delay_step;
+            let (air_coeff, air_gain, ild_coeff) = (self.air_coeff, self.air_gain, self.ild_coeff);
+            let src = &mut self.sources[src_idx];
+
+            // High-frequency air absorption grows with distance.
+            src.air_lp = x + (src.air_lp - x) * air_coeff;
+            let x = src.air_lp + (x - src.air_lp) * air_gain;
+
+            let mask = src.buffer.len() - 1;
+            src.buffer[src.write_pos] = x;
+            src.write_pos = (src.write_pos + 1) & mask;
+
+            for ear in 0..2 {
+                let target = delays[ear];
+                let delay = if primed {
+                    let d = src.ears[ear].delay;
+                    d + (target - d).clamp(-max_delay_step, max_delay_step)
+                } else {
+                    target
+                };
+                src.ears[ear].delay = delay;
+                let mut y = src.read(delay);
+                if ilds[ear] < 1.0 {
+                    let path = &mut src.ears[ear];
+                    path.ild_lp = y + (path.ild_lp - y) * ild_coeff;
+                    y = path.ild_lp + (y - path.ild_lp) * ilds[ear];
+                } else {
+                    // Keep the crossover state warm so a source crossing the
+                    // median plane doesn't click.
+                    let path = &mut src.ears[ear];
+                    path.ild_lp = y + (path.ild_lp - y) * ild_coeff;
+                }
+                out[ear] += y * gains[ear];
+            }
+        }
+        self.primed = true;
+
+        (out[0] * self.dist_gain, out[1] * self.dist_gain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SpatialParams, Spatializer, TrajectoryPoint};
+
+    fn render(params: SpatialParams, frames: usize) -> (f32, f32) {
+        let sample_rate = 48_000.0;
+        let mut sp = Spatializer::new(params, sample_rate);
+        let mut energy = (0.0f32, 0.0f32);
+        for i in 0..frames {
+            let x = (2.0 * std::f32::consts::PI * 3000.0 * i as f32 / sample_rate).sin();
+            let (l, r) = sp.process(x, x);
+            assert!(l.is_finite() && r.is_finite());
+            energy.0 += l * l;
+            energy.1 += r * r;
+        }
+        energy
+    }
+
+    #[test]
+    fn source_on_the_right_is_louder_in_right_ear() {
+        let params = SpatialParams {
+            azimuth_deg: 90.0,
+            ..SpatialParams::default()
+        };
+        let (l, r) = render(params, 9600);
+        assert!(r > l * 2.0, "left {l} right {r}");
+    }
+
+    #[test]
+    fn distance_rolloff_attenuates() {
+        let near = render(SpatialParams::default(), 9600);
+        let far = render(
+            SpatialParams {
+                distance_m: 4.0,
+                ..SpatialParams::default()
+            },
+            9600,
+        );
+        let ratio = (far.0 / near.0).sqrt();
+        assert!((ratio - 0.25).abs() < 0.02, "ratio {ratio}");
+    }
+
+    #[test]
+    fn trajectory_interpolates_and_holds() {
+        let params = SpatialParams {
+            trajectory: vec![
+                TrajectoryPoint {
+                    time: 1.0,
+                    azimuth_deg: 0.0,
+                    distance_m: 1.0,
+                },
+                TrajectoryPoint {
+                    time: 3.0,
+                    azimuth_deg: 180.0,
+                    distance_m: 3.0,
+                },
+            ],
+            ..SpatialParams::default()
+        };
+        assert_eq!(params.position_at(0.0), (0.0, 1.0));
+        assert_eq!(params.position_at(2.0), (90.0, 2.0));
+        assert_eq!(params.position_at(10.0), (180.0, 3.0));
+        assert!((params.peak_distance_gain() - 1.0).abs() < 1e-6);
+    }
+}
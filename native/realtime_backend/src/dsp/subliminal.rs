@@ -0,0 +1,535 @@
+//! Encoding and arrangement of subliminal clips over a step.
+//!
+//! Clips are held once, already encoded at the output rate, and the step is
+//! read from them as it plays: in sequence, one clip after another with a
+//! pause between, or stacked, every clip looping on top of the others.
+//! Nothing longer than the clips themselves is ever allocated.
+//!
+//! Besides plain AM onto the carrier, clips can be shifted up by it as a
+//! single sideband, sped up, or kept audible and masked by the noise bed.
+//! These encodings resample through the clip's spectrum and cut it below
+//! the band they must fit in, so nothing folds back.
+
+use rustfft::{num_complex::Complex, FftPlanner};
+
+/// `mode` values of `subliminal_encode`. `sequence` and `stack` amplitude
+/// modulate the carrier; the others name an [`Encoding`] and are laid out
+/// by `layout`.
+pub const MODES: &[&str] = &["sequence", "stack", "ssb", "compressed", "masked"];
+
+/// `layout` values for the encodings other than AM.
+pub const LAYOUTS: &[&str] = &["sequence", "stack"];
+
+/// Frame length, in seconds, for measuring the level of speech.
+const LEVEL_FRAME_SECONDS: f64 = 0.02;
+/// Frames this far below the loudest one are pauses, not speech.
+const SPEECH_FLOOR_DB: f32 = -30.0;
+/// Encoded clips this far below the source peak (-80 dB) are silent.
+const SILENCE_FLOOR: f32 = 1e-4;
+/// Share of the band, below its edge, over which the spectrum is faded out.
+const BAND_EDGE_TAPER: f64 = 0.1;
+
+/// How clips are laid out over the step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+    Sequence,
+    Stack,
+}
+
+impl Layout {
+    /// `stack`; anything else plays in sequence.
+    pub fn from_name(name: &str) -> Self {
+        if name.eq_ignore_ascii_case("stack") {
+            Layout::Stack
+        } else {
+            Layout::Sequence
+        }
+    }
+}
+
+pub struct Arrangement {
+    clips: Vec<Vec<f32>>,
+    layout: Layout,
+    /// Silent frames after each clip in sequence.
+    pause: usize,
+    /// Loudest sample of the whole step, or a bound on it for stacks.
+    peak: f32,
+    /// Frames read so far, for stacks.
+    frame: usize,
+    /// Sequence cursor: clip number (not wrapped), offset into the clip or
+    /// pause, and whether the pause is playing.
+    clip: usize,
+    offset: usize,
+    pausing: bool,
+}
+
+impl Arrangement {
+    /// Arrange `clips` over `total` frames. Empty clips are dropped.
+    pub fn new(mut clips: Vec<Vec<f32>>, layout: Layout, pause: usize, total: usize) -> Self {
+        clips.retain(|c| !c.is_empty());
+        let mut arrangement = Self {
+            clips,
+            layout,
+            pause,
+            peak: 0.0,
+            frame: 0,
+            clip: 0,
+            offset: 0,
+            pausing: false,
+        };
+        arrangement.peak = arrangement.step_peak(total);
+        arrangement
+    }
+
+    /// Loudest sample over the first `total` frames, found without storing
+    /// them. Stacks of several clips are bounded by the mean of the clip
+    /// peaks instead: their loudest frame can take as long as the clips'
+    /// common period to come round.
+    fn step_peak(&self, total: usize) -> f32 {
+        if self.clips.is_empty() {
+            return 0.0;
+        }
+        match self.layout {
+            Layout::Stack => {
+                let sum: f32 = self
+                    .clips
+                    .iter()
+                    .map(|c| abs_peak(&c[..c.len().min(total)]))
+                    .sum();
+                sum / self.clips.len() as f32
+            }
+            Layout::Sequence => {
+                let peaks: Vec<f32> = self.clips.iter().map(|c| abs_peak(c)).collect();
+                let mut peak = 0.0f32;
+                let mut pos = 0usize;
+                let mut idx = 0usize;
+                while pos < total {
+                    let clip = &self.clips[idx % self.clips.len()];
+                    let len = clip.len().min(total - pos);
+                    peak = peak.max(if len == clip.len() {
+                        peaks[idx % self.clips.len()]
+                    } else {
+                        abs_peak(&clip[..len])
+                    });
+                    pos += len + self.pause;
+                    idx += 1;
+                    // Every clip has played whole; later rounds repeat them.
+                    if idx >= self.clips.len() {
+                        break;
+                    }
+                }
+                peak
+            }
+        }
+    }
+
+    /// Move a fresh arrangement to `frame` frames into the step.
+    pub fn seek(&mut self, frame: usize) {
+        match self.layout {
+            Layout::Stack => self.frame = frame,
+            Layout::Sequence => {
+                let round: usize = self.clips.iter().map(|c| c.len() + self.pause).sum();
+                if round == 0 {
+                    return;
+                }
+                let mut rest = frame % round;
+                self.clip = frame / round * self.clips.len();
+                for clip in &self.clips {
+                    if rest < clip.len() {
+                        (self.offset, self.pausing) = (rest, false);
+                        return;
+                    }
+                    rest -= clip.len();
+                    if rest < self.pause {
+                        (self.offset, self.pausing) = (rest, true);
+                        return;
+                    }
+                    rest -= self.pause;
+                    self.clip += 1;
+                }
+            }
+        }
+    }
+
+    /// Loudest sample of the step; 0 when it is silent.
+    pub fn peak(&self) -> f32 {
+        self.peak
+    }
+
+    #[inline]
+    fn stack_sample(&self, frame: usize) -> f32 {
+        let sum = self
+            .clips
+            .iter()
+            .fold(0.0f32, |s, c| s + c[frame % c.len()]);
+        if self.clips.len() > 1 {
+            sum / self.clips.len() as f32
+        } else {
+            sum
+        }
+    }
+
+    /// Next mono sample of the step.
+    #[inline]
+    pub fn next_sample(&mut self) -> f32 {
+        if self.clips.is_empty() {
+            return 0.0;
+        }
+        match self.layout {
+            Layout::Stack => {
+                let v = self.stack_sample(self.frame);
+                self.frame += 1;
+                v
+            }
+            Layout::Sequence => {
+                if self.pausing {
+                    self.offset += 1;
+                    if self.offset >= self.pause {
+                        self.pausing = false;
+                        self.offset = 0;
+                        self.clip += 1;
+                    }
+                    return 0.0;
+                }
+                let clip = &self.clips[self.clip % self.clips.len()];
+                let v = clip[self.offset];
+                self.offset += 1;
+                if self.offset == clip.len() {
+                    self.offset = 0;
+                    if self.pause > 0 {
+                        self.pausing = true;
+                    } else {
+                        self.clip += 1;
+                    }
+                }
+                v
+            }
+        }
+    }
+}
+
+/// How a clip is encoded, other than plain AM onto the carrier.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Encoding {
+    /// Upper sideband only: the clip is shifted up by `carrier` Hz, with
+    /// no mirror band below it.
+    Ssb { carrier: f32 },
+    /// Played `speed` (> 0) times faster, pitch rising with it.
+    Compressed { speed: f32 },
+    /// Left audible, with its speech level `snr_db` relative to the noise
+    /// bed it plays under.
+    Masked { snr_db: f32 },
+}
+
+impl Encoding {
+    /// The encoding for a `mode`, or `None` for the AM modes.
+    pub fn from_mode(mode: &str, carrier: f32, speed: f32, snr_db: f32) -> Option<Self> {
+        match mode.to_lowercase().as_str() {
+            "ssb" => Some(Encoding::Ssb { carrier }),
+            "compressed" => Some(Encoding::Compressed { speed }),
+            "masked" => Some(Encoding::Masked { snr_db }),
+            _ => None,
+        }
+    }
+
+    /// Encode a mono clip at `from` Hz for playback at `to` Hz. SSB and
+    /// compressed clips peak at 1; masked clips have a speech level of
+    /// `snr_db` against a bed of unit RMS. Returns `None` when nothing of
+    /// the clip fits the output band.
+    pub fn encode(self, clip: &[f32], from: u32, to: u32) -> Option<Vec<f32>> {
+        if clip.is_empty() || from == 0 || to == 0 {
+            return None;
+        }
+        let nyquist = 0.5 * to as f64;
+        let mut out = match self {
+            Encoding::Ssb { carrier } => {
+                // The shifted band must end below Nyquist.
+                let carrier = carrier as f64;
+                let analytic = band_limit(clip, from as f64, to, nyquist - carrier, true);
+                let step = carrier / to as f64;
+                analytic
+                    .iter()
+                    .enumerate()
+                    .map(|(i, z)| {
+                        let phase = (i as f64 * step).fract() * std::f64::consts::TAU;
+                        (z.re as f64 * phase.cos() - z.im as f64 * phase.sin()) as f32
+                    })
+                    .collect()
+            }
+            Encoding::Compressed { speed } => {
+                // Read at `speed` times the clip's rate: everything pushed
+                // past the output Nyquist is cut rather than folded back.
+                real(band_limit(
+                    clip,
+                    from as f64 * speed as f64,
+                    to,
+                    nyquist,
+                    false,
+                ))
+            }
+            Encoding::Masked { .. } => real(band_limit(clip, from as f64, to, nyquist, false)),
+        };
+        // What is left of a clip with nothing in the band is rounding noise.
+        if abs_peak(&out) <= abs_peak(clip) * SILENCE_FLOOR {
+            return None;
+        }
+        match self {
+            Encoding::Masked { snr_db } => {
+                let gain = 10f32.powf(snr_db / 20.0) / speech_rms(&out, to);
+                out.iter_mut().for_each(|v| *v *= gain);
+            }
+            _ => {
+                let peak = abs_peak(&out);
+                out.iter_mut().for_each(|v| *v /= peak);
+            }
+        }
+        Some(out)
+    }
+}
+
+/// `x` at `from` Hz resampled to `to` Hz through its spectrum, keeping only
+/// content below `cutoff` Hz (and below both Nyquists). The top tenth of
+/// the band is faded out so the cut does not ring, and DC is removed. With
+/// `analytic` the negative frequencies are dropped too, giving the analytic
+/// signal whose real part is the band-limited clip.
+fn band_limit(x: &[f32], from: f64, to: u32, cutoff: f64, analytic: bool) -> Vec<Complex<f32>> {
+    let n = x.len();
+    let m = ((n as f64 * to as f64 / from).round() as usize).max(1);
+    let mut planner = FftPlanner::new();
+    let mut spectrum: Vec<Complex<f32>> = x.iter().map(|&v| Complex::new(v, 0.0)).collect();
+    planner.plan_fft_forward(n).process(&mut spectrum);
+
+    let bin_hz = from / n as f64;
+    let cutoff = cutoff.min(0.5 * from).min(0.5 * to as f64);
+    let fade_start = cutoff * (1.0 - BAND_EDGE_TAPER);
+    // Bins strictly below both Nyquist bins, so each has a distinct mirror.
+    let last = ((n - 1) / 2).min((m - 1) / 2);
+    let mut out = vec![Complex::new(0.0f32, 0.0); m];
+    let scale = 1.0 / n as f32;
+    for k in 1..=last {
+        let freq = k as f64 * bin_hz;
+        if freq >= cutoff {
+            break;
+        }
+        let taper = if freq <= fade_start {
+            1.0
+        } else {
+            let x = (freq - fade_start) / (cutoff - fade_start);
+            0.5 * (1.0 + (std::f64::consts::PI * x).cos())
+        };
+        let weight = taper as f32 * scale;
+        if analytic {
+            out[k] = spectrum[k] * (2.0 * weight);
+        } else {
+            out[k] = spectrum[k] * weight;
+            out[m - k] = spectrum[n - k] * weight;
+        }
+    }
+    planner.plan_fft_inverse(m).process(&mut out);
+    out
+}
+
+fn real(samples: Vec<Complex<f32>>) -> Vec<f32> {
+    samples.into_iter().map(|z| z.re).collect()
+}
+
+/// RMS of the speech in a clip: the power of 20 ms frames, averaged over
+/// those within 30 dB of the loudest so pauses do not pull it down.
+pub fn speech_rms(samples: &[f32], sample_rate: u32) -> f32 {
+    let frame = ((LEVEL_FRAME_SECONDS * sample_rate as f64) as usize).max(1);
+    let powers: Vec<f32> = samples
+        .chunks(frame)
+        .map(|c| c.iter().map(|v| v * v).sum::<f32>() / c.len() as f32)
+        .collect();
+    let loudest = powers.iter().fold(0.0f32, |m, &p| m.max(p));
+    if loudest <= 0.0 {
+        return 0.0;
+    }
+    let floor = loudest * 10f32.powf(SPEECH_FLOOR_DB / 10.0);
+    let (sum, count) = powers
+        .iter()
+        .filter(|&&p| p >= floor)
+        .fold((0.0f32, 0usize), |(s, c), &p| (s + p, c + 1));
+    (sum / count as f32).sqrt()
+}
+
+fn abs_peak(samples: &[f32]) -> f32 {
+    samples.iter().fold(0.0f32, |m, v| m.max(v.abs()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Voiced syllables: harmonics of a 120 Hz glottal pulse shaped by two
+    /// formants, gated four times a second, with faint fricative harmonics
+    /// reaching up to 0.45 of `sample_rate`.
+    fn speech_like(sample_rate: u32, seconds: f64) -> Vec<f32> {
+        let sr = sample_rate as f64;
+        let f0 = 120.0;
+        let formant =
+            |f: f64, centre: f64, width: f64| 1.0 / (1.0 + ((f - centre) / width).powi(2));
+        let sine = |f: f64, t: f64| (std::f64::consts::TAU * (f * t).fract()).sin();
+        (0..(seconds * sr) as usize)
+            .map(|i| {
+                let t = i as f64 / sr;
+                let mut v = 0.0;
+                let mut f = f0;
+                while f < 0.45 * sr {
+                    let amp = formant(f, 700.0, 150.0) + 0.5 * formant(f, 1200.0, 200.0) + 0.02;
+                    v += amp * sine(f, t);
+                    f += f0;
+                }
+                (0.1 * sine(2.0, t).powi(2) * v) as f32
+            })
+            .collect()
+    }
+
+    fn tone(freq: f32, sample_rate: u32) -> Vec<f32> {
+        (0..sample_rate as usize)
+            .map(|i| {
+                (std::f64::consts::TAU * (freq as f64 * i as f64 / sample_rate as f64).fract())
+                    .sin() as f32
+            })
+            .collect()
+    }
+
+    /// Energy between `lo` and `hi` Hz.
+    fn band_energy(x: &[f32], sample_rate: u32, lo: f32, hi: f32) -> f64 {
+        let mut spectrum: Vec<Complex<f32>> = x.iter().map(|&v| Complex::new(v, 0.0)).collect();
+        FftPlanner::new()
+            .plan_fft_forward(x.len())
+            .process(&mut spectrum);
+        let bin_hz = sample_rate as f32 / x.len() as f32;
+        spectrum[..x.len() / 2]
+            .iter()
+            .enumerate()
+            .filter(|(k, _)| (lo..hi).contains(&(*k as f32 * bin_hz)))
+            .map(|(_, c)| c.norm_sqr() as f64)
+            .sum()
+    }
+
+    #[test]
+    fn ssb_keeps_only_the_upper_sideband() {
+        let sr = 44_100;
+        let speech = speech_like(sr, 1.0);
+        let out = Encoding::Ssb { carrier: 17_500.0 }
+            .encode(&speech, sr, sr)
+            .unwrap();
+        assert_eq!(out.len(), speech.len());
+        let total = band_energy(&out, sr, 0.0, 22_050.0);
+        // No mirror band under the carrier, and nothing that would have
+        // been shifted past Nyquist folds back down.
+        assert!(band_energy(&out, sr, 0.0, 17_400.0) < 1e-4 * total);
+        assert!(band_energy(&out, sr, 17_500.0, 22_050.0) > 0.999 * total);
+
+        // A 1 kHz tone lands at 18.5 kHz; one at 8 kHz has no room left.
+        let shifted = Encoding::Ssb { carrier: 17_500.0 }
+            .encode(&tone(1_000.0, sr), sr, sr)
+            .unwrap();
+        let total = band_energy(&shifted, sr, 0.0, 22_050.0);
+        assert!(band_energy(&shifted, sr, 18_450.0, 18_550.0) > 0.999 * total);
+        assert!(Encoding::Ssb { carrier: 17_500.0 }
+            .encode(&tone(8_000.0, sr), sr, sr)
+            .is_none());
+    }
+
+    #[test]
+    fn compressed_speech_is_shorter_and_cut_below_nyquist() {
+        let sr = 44_100;
+        let speech = speech_like(sr, 1.0);
+        let out = Encoding::Compressed { speed: 2.0 }
+            .encode(&speech, sr, sr)
+            .unwrap();
+        assert_eq!(out.len(), speech.len() / 2);
+        assert!((abs_peak(&out) - 1.0).abs() < 1e-6);
+        let total = band_energy(&out, sr, 0.0, 22_050.0);
+        // The voice rises an octave: most energy moves from the first
+        // formant around 700 Hz to around 1400 Hz.
+        assert!(band_energy(&out, sr, 1_100.0, 1_700.0) > 0.5 * total);
+        // A 15 kHz tone played at double speed would fold back to 14.1 kHz.
+        let compressed = Encoding::Compressed { speed: 2.0 };
+        assert!(compressed.encode(&tone(15_000.0, sr), sr, sr).is_none());
+        let up = compressed.encode(&tone(5_000.0, sr), sr, sr).unwrap();
+        let total = band_energy(&up, sr, 0.0, 22_050.0);
+        assert!(band_energy(&up, sr, 9_950.0, 10_050.0) > 0.999 * total);
+    }
+
+    #[test]
+    fn masked_speech_sits_at_its_snr() {
+        // 48 kHz speech for a 44.1 kHz output: what lies between the two
+        // Nyquists must be cut before it can alias.
+        let speech = speech_like(48_000, 1.0);
+        let out = Encoding::Masked { snr_db: -15.0 }
+            .encode(&speech, 48_000, 44_100)
+            .unwrap();
+        assert_eq!(out.len(), 44_100);
+        let level = speech_rms(&out, 44_100);
+        assert!((20.0 * level.log10() + 15.0).abs() < 0.05, "{level}");
+        // A 23 kHz tone would fold back to 21.1 kHz.
+        assert!(Encoding::Masked { snr_db: -15.0 }
+            .encode(&tone(23_000.0, 48_000), 48_000, 44_100)
+            .is_none());
+    }
+
+    /// The whole step laid out in one buffer, as it used to be rendered.
+    fn rendered(clips: &[Vec<f32>], layout: Layout, pause: usize, total: usize) -> Vec<f32> {
+        let mut mono = vec![0.0f32; total];
+        match layout {
+            Layout::Stack => {
+                for clip in clips {
+                    for (i, v) in mono.iter_mut().enumerate() {
+                        *v += clip[i % clip.len()];
+                    }
+                }
+                if clips.len() > 1 {
+                    for v in &mut mono {
+                        *v /= clips.len() as f32;
+                    }
+                }
+            }
+            Layout::Sequence => {
+                let (mut pos, mut idx) = (0, 0);
+                while pos < total {
+                    let clip = &clips[idx % clips.len()];
+                    let len = clip.len().min(total - pos);
+                    mono[pos..pos + len].copy_from_slice(&clip[..len]);
+                    pos += len + pause;
+                    idx += 1;
+                }
+            }
+        }
+        mono
+    }
+
+    #[test]
+    fn streamed_steps_match_the_rendered_layout() {
+        let clip = |len: usize, scale: f32| -> Vec<f32> {
+            (0..len).map(|i| scale * (i as f32 * 0.7).sin()).collect()
+        };
+        let clips = vec![clip(37, 0.4), clip(53, 0.9), clip(20, 0.6)];
+        for layout in [Layout::Sequence, Layout::Stack] {
+            for (pause, total) in [(11, 400), (0, 95), (11, 60), (5, 4000)] {
+                let expected = rendered(&clips, layout, pause, total);
+                let mut arrangement = Arrangement::new(clips.clone(), layout, pause, total);
+                let streamed: Vec<f32> = (0..total).map(|_| arrangement.next_sample()).collect();
+                assert_eq!(streamed, expected, "{layout:?} pause {pause} total {total}");
+                for from in [1, 36, 37, 48, total / 2, total - 1] {
+                    let mut seeked = Arrangement::new(clips.clone(), layout, pause, total);
+                    seeked.seek(from);
+                    let rest: Vec<f32> = (from..total).map(|_| seeked.next_sample()).collect();
+                    assert_eq!(rest, expected[from..], "{layout:?} seek {from}");
+                }
+                let peak = abs_peak(&expected);
+                match layout {
+                    Layout::Sequence => assert_eq!(arrangement.peak(), peak, "{total}"),
+                    Layout::Stack => {
+                        let bound = clips.iter().map(|c| abs_peak(c)).sum::<f32>() / 3.0;
+                        assert!(arrangement.peak() >= peak, "{total}");
+                        assert!(arrangement.peak() <= bound, "{total}");
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,220 @@
+//! Band-limited carrier oscillators for the tonal voices.
+//!
+//! Non-sine carriers are read from mipmapped single-cycle tables. Each level
+//! holds half the harmonics of the one before it, and every lookup uses the
+//! richest level whose top harmonic stays below Nyquist at the current
+//! frequency, so high or sweeping carriers do not alias.
+
+use once_cell::sync::Lazy;
+use rustfft::{num_complex::Complex, FftPlanner};
+use std::sync::Arc;
+
+use super::trig::sin_lut;
+
+const TABLE_SIZE: usize = 2048;
+/// Harmonics held by level 0; level `i` holds `MAX_HARMONICS >> i`.
+const MAX_HARMONICS: usize = 512;
+const LEVELS: usize = MAX_HARMONICS.trailing_zeros() as usize + 1;
+const TWO_PI: f32 = std::f32::consts::PI * 2.0;
+
+/// Shortest single cycle that still carries a fundamental.
+pub const MIN_CYCLE_SAMPLES: usize = 3;
+
+pub struct BandLimitedTable {
+    /// `LEVELS` tables of `TABLE_SIZE + 1` samples (the last repeats the
+    /// first for interpolation), scaled so the loudest level peaks at 1.
+    levels: Vec<Vec<f32>>,
+}
+
+impl BandLimitedTable {
+    /// Build from complex harmonic coefficients, `harmonics[h - 1]` being
+    /// harmonic `h` of a cycle `x(θ) = Σ 2·Re(c_h·e^{ihθ})`.
+    fn from_spectrum(harmonics: &[Complex<f32>]) -> Self {
+        let ifft = FftPlanner::new().plan_fft_inverse(TABLE_SIZE);
+        let mut levels = Vec::with_capacity(LEVELS);
+        let mut peak = 0.0f32;
+        for level in 0..LEVELS {
+            let count = (MAX_HARMONICS >> level).min(harmonics.len());
+            let mut buf = vec![Complex::new(0.0, 0.0); TABLE_SIZE];
+            for (i, c) in harmonics[..count].iter().enumerate() {
+                buf[i + 1] = *c;
+                buf[TABLE_SIZE - i - 1] = c.conj();
+            }
+            ifft.process(&mut buf);
+            let mut table: Vec<f32> = buf.iter().map(|c| c.re).collect();
+            table.push(table[0]);
+            peak = table.iter().fold(peak, |p, v| p.max(v.abs()));
+            levels.push(table);
+        }
+        if peak > 0.0 {
+            for v in levels.iter_mut().flatten() {
+                *v /= peak;
+            }
+        }
+        Self { levels }
+    }
+
+    /// Build from a sine series `x(θ) = Σ b_h·sin(hθ)`.
+    fn from_sine_series(b: impl Fn(usize) -> f32) -> Self {
+        let harmonics: Vec<Complex<f32>> = (1..=MAX_HARMONICS)
+            .map(|h| Complex::new(0.0, -0.5 * b(h)))
+            .collect();
+        Self::from_spectrum(&harmonics)
+    }
+
+    /// Build from one cycle of arbitrary length. DC is removed and the
+    /// result is normalised to a peak of 1.
+    fn from_cycle(cycle: &[f32]) -> Option<Self> {
+        let len = cycle.len();
+        if len < MIN_CYCLE_SAMPLES {
+            return None;
+        }
+        let mut buf: Vec<Complex<f32>> = cycle.iter().map(|&v| Complex::new(v, 0.0)).collect();
+        FftPlanner::new().plan_fft_forward(len).process(&mut buf);
+        // Skip the Nyquist bin of even lengths: its phase is ambiguous.
+        let count = ((len - 1) / 2).min(MAX_HARMONICS);
+        let scale = 1.0 / len as f32;
+        let harmonics: Vec<Complex<f32>> = buf[1..=count].iter().map(|c| c * scale).collect();
+        if harmonics.iter().all(|c| c.norm() < 1e-9) {
+            return None;
+        }
+        Some(Self::from_spectrum(&harmonics))
+    }
+
+    #[inline(always)]
+    fn sample(&self, phase: f32, freq: f32, sample_rate: f32) -> f32 {
+        let table = &self.levels[level_for(freq, sample_rate)];
+        let pos = phase.rem_euclid(TWO_PI) / TWO_PI * TABLE_SIZE as f32;
+        let idx = (pos as usize).min(TABLE_SIZE - 1);
+        let frac = pos - idx as f32;
+        let a = table[idx];
+        let b = table[idx + 1];
+        a + (b - a) * frac
+    }
+}
+
+/// Richest mip level whose harmonics all stay below Nyquist at `freq`.
+#[inline(always)]
+fn level_for(freq: f32, sample_rate: f32) -> usize {
+    let limit = 0.5 * sample_rate / freq.abs().max(1e-6);
+    if limit >= MAX_HARMONICS as f32 {
+        return 0;
+    }
+    let harmonics = limit as usize;
+    if harmonics <= 1 {
+        return LEVELS - 1;
+    }
+    let floor_log2 = usize::BITS - 1 - harmonics.leading_zeros();
+    (MAX_HARMONICS.trailing_zeros() - floor_log2) as usize
+}
+
+static TRIANGLE: Lazy<Arc<BandLimitedTable>> = Lazy::new(|| {
+    Arc::new(BandLimitedTable::from_sine_series(|h| {
+        if h % 2 == 0 {
+            0.0
+        } else {
+            let sign = if (h / 2) % 2 == 0 { 1.0 } else { -1.0 };
+            sign / (h * h) as f32
+        }
+    }))
+});
+
+static SQUARE: Lazy<Arc<BandLimitedTable>> = Lazy::new(|| {
+    Arc::new(BandLimitedTable::from_sine_series(|h| {
+        if h % 2 == 0 {
+            0.0
+        } else {
+            1.0 / h as f32
+        }
+    }))
+});
+
+static SAW: Lazy<Arc<BandLimitedTable>> = Lazy::new(|| {
+    Arc::new(BandLimitedTable::from_sine_series(|h| {
+        let sign = if h % 2 == 0 { -1.0 } else { 1.0 };
+        sign / h as f32
+    }))
+});
+
+/// Carrier oscillator of a tonal voice. Every shape starts at zero and
+/// rises like a sine, and peaks at ±1.
+#[derive(Clone, Default)]
+pub enum Carrier {
+    #[default]
+    Sine,
+    Table(Arc<BandLimitedTable>),
+}
+
+impl Carrier {
+    /// `sine`, `triangle`, `square` or `saw` (also `sawtooth`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "sine" => Some(Carrier::Sine),
+            "triangle" => Some(Carrier::Table(TRIANGLE.clone())),
+            "square" => Some(Carrier::Table(SQUARE.clone())),
+            "saw" | "sawtooth" => Some(Carrier::Table(SAW.clone())),
+            _ => None,
+        }
+    }
+
+    /// A user-supplied single-cycle wavetable of any length. Returns `None`
+    /// when the cycle is shorter than [`MIN_CYCLE_SAMPLES`] or silent.
+    pub fn from_cycle(cycle: &[f32]) -> Option<Self> {
+        BandLimitedTable::from_cycle(cycle).map(|t| Carrier::Table(Arc::new(t)))
+    }
+
+    /// Sample at `phase` radians for a carrier currently at `freq` Hz.
+    #[inline(always)]
+    pub fn sample(&self, phase: f32, freq: f32, sample_rate: f32) -> f32 {
+        match self {
+            Carrier::Sine => sin_lut(phase),
+            Carrier::Table(table) => table.sample(phase, freq, sample_rate),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mip_levels_stay_below_nyquist() {
+        for sample_rate in [44_100.0f32, 48_000.0] {
+            let mut freq = 10.0f32;
+            while freq < 20_000.0 {
+                let harmonics = MAX_HARMONICS >> level_for(freq, sample_rate);
+                assert!(
+                    harmonics == 1 || harmonics as f32 * freq < 0.5 * sample_rate,
+                    "{harmonics} harmonics of {freq} Hz alias at {sample_rate} Hz"
+                );
+                freq *= 1.07;
+            }
+        }
+        assert_eq!(level_for(20.0, 44_100.0), 0);
+    }
+
+    #[test]
+    fn single_cycle_tables_are_reproduced_and_normalised() {
+        let cycle: Vec<f32> = (0..600)
+            .map(|i| 0.25 * (TWO_PI * i as f32 / 600.0).sin() + 0.1)
+            .collect();
+        let carrier = Carrier::from_cycle(&cycle).unwrap();
+        for i in 0..64 {
+            let phase = TWO_PI * i as f32 / 64.0;
+            let v = carrier.sample(phase, 200.0, 44_100.0);
+            assert!((v - phase.sin()).abs() < 1e-3, "{v} at {phase}");
+        }
+        assert!(Carrier::from_cycle(&[0.0; 16]).is_none());
+        assert!(Carrier::from_cycle(&[1.0, -1.0]).is_none());
+
+        let Some(Carrier::Table(square)) = Carrier::from_name("square") else {
+            panic!("square should be a table");
+        };
+        let peak = square
+            .levels
+            .iter()
+            .flatten()
+            .fold(0.0f32, |p, v| p.max(v.abs()));
+        assert!((peak - 1.0).abs() < 1e-6);
+    }
+}
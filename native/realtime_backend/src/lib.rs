@@ -4,15 +4,24 @@ mod frb_generated; /* AUTO INJECTED BY flutter_rust_bridge. This line may not be
 
 #[cfg(not(target_arch = "wasm32"))]
 pub mod audio_io;
+pub mod automation;
+pub mod bundle;
 pub mod command;
 pub mod config;
 pub mod dsp;
 pub mod gpu;
+pub mod migration;
 pub mod models;
+pub mod modulation;
 pub mod noise_params;
+pub mod plan;
+pub mod presets;
 pub mod scheduler;
 pub mod streaming_noise;
+pub mod template;
+pub mod voice_factory;
 pub mod voice_loader;
+pub mod validation;
 pub mod voices;
 pub mod mobile_api;
 pub mod logging;
@@ -69,6 +78,9 @@ use pyo3::prelude::*;
 use wasm_bindgen::prelude::*;
 
 static ENGINE_STATE: Lazy<Mutex<Option<HeapProd<Command>>>> = Lazy::new(|| Mutex::new(None));
+/// Output rate of the running stream, which edits are laid out for.
+#[cfg(any(feature = "python", feature = "web"))]
+static STREAM_RATE: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
 #[cfg(feature = "python")]
 static STOP_SENDER: Lazy<Mutex<Option<Sender<()>>>> = Lazy::new(|| Mutex::new(None));
 #[cfg(feature = "web")]
@@ -109,6 +121,7 @@ fn start_stream(track_json_str: String, start_time: Option<f64>) -> PyResult<()>
     let rb = HeapRb::<Command>::new(1024);
     let (prod, cons) = rb.split();
     *ENGINE_STATE.lock() = Some(prod);
+    STREAM_RATE.store(stream_rate, std::sync::atomic::Ordering::Relaxed);
 
     let (tx, rx) = unbounded();
     *STOP_SENDER.lock() = Some(tx);
@@ -135,7 +148,8 @@ fn update_track(track_json_str: String) -> PyResult<()> {
     let track_data: TrackData = serde_json::from_str(&track_json_str)
         .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
     if let Some(prod) = &mut *ENGINE_STATE.lock() {
-        let _ = prod.try_push(Command::UpdateRealtime(track_data));
+        let rate = STREAM_RATE.load(std::sync::atomic::Ordering::Relaxed);
+        let _ = prod.try_push(Command::UpdateRealtime(Box::new(scheduler::PreparedTrack::new(track_data, rate))));
     }
     Ok(())
 }
@@ -212,11 +226,7 @@ fn render_sample_wav(track_json_str: String, out_path: String) -> PyResult<()> {
     // Use GPU acceleration when rendering to a file if available.
     // Streaming paths keep GPU disabled.
     scheduler.gpu_enabled = true;
-    let track_frames: usize = track_data
-        .steps
-        .iter()
-        .map(|s| (s.duration * sample_rate as f64) as usize)
-        .sum();
+    let track_frames: usize = track_data.total_samples(sample_rate);
     let target_frames = (sample_rate as usize * 60).min(track_frames);
 
     let spec = WavSpec {
@@ -272,11 +282,7 @@ fn render_full_wav(track_json_str: String, out_path: String) -> PyResult<()> {
     let mut scheduler = TrackScheduler::new(track_data.clone(), sample_rate);
     // Enable GPU acceleration during full track rendering if available.
     scheduler.gpu_enabled = true;
-    let target_frames: usize = track_data
-        .steps
-        .iter()
-        .map(|s| (s.duration * sample_rate as f64) as usize)
-        .sum();
+    let target_frames: usize = track_data.total_samples(sample_rate);
 
     let spec = WavSpec {
         channels: 2,
@@ -331,6 +337,7 @@ pub fn start_stream(track_json_str: &str, sample_rate: u32, start_time: f64) {
     let rb = HeapRb::<Command>::new(1024);
     let (prod, cons) = rb.split();
     *ENGINE_STATE.lock() = Some(prod);
+    STREAM_RATE.store(sample_rate, std::sync::atomic::Ordering::Relaxed);
     // In wasm mode we don't spawn a thread; scheduler is stored globally for pull processing
     WASM_SCHED.with(|s| *s.borrow_mut() = Some((scheduler, cons)));
 }
@@ -340,7 +347,8 @@ pub fn start_stream(track_json_str: &str, sample_rate: u32, start_time: f64) {
 pub fn update_track(track_json_str: &str) {
     if let Some(prod) = &mut *ENGINE_STATE.lock() {
         if let Ok(track_data) = serde_json::from_str(track_json_str) {
-            let _ = prod.try_push(Command::UpdateRealtime(track_data));
+            let rate = STREAM_RATE.load(std::sync::atomic::Ordering::Relaxed);
+            let _ = prod.try_push(Command::UpdateRealtime(Box::new(scheduler::PreparedTrack::new(track_data, rate))));
         }
     }
 }
@@ -437,6 +445,50 @@ pub fn stop_stream() {
     WASM_SCHED.with(|s| *s.borrow_mut() = None);
 }
 
+#[cfg(feature = "python")]
+#[pyfunction]
+fn validate_track(
+    track_json_str: String,
+) -> PyResult<Vec<std::collections::HashMap<&'static str, String>>> {
+    let diagnostics = validation::validate_track_json(&track_json_str)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+    Ok(diagnostics
+        .into_iter()
+        .map(|d| {
+            std::collections::HashMap::from([
+                ("path", d.path),
+                ("severity", d.severity.as_str().to_string()),
+                ("message", d.message),
+            ])
+        })
+        .collect())
+}
+
+#[cfg(feature = "python")]
+#[pyfunction]
+fn normalize_track(track_json_str: String) -> PyResult<String> {
+    migration::normalize_track_json(&track_json_str)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+}
+
+#[cfg(feature = "python")]
+#[pyfunction]
+#[pyo3(signature = (template_json_str, overrides_json_str=None))]
+fn instantiate_template(
+    template_json_str: String,
+    overrides_json_str: Option<String>,
+) -> PyResult<String> {
+    let overrides = match overrides_json_str {
+        Some(json) => serde_json::from_str(&json)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?,
+        None => serde_json::Map::new(),
+    };
+    let track = template::instantiate_template(&template_json_str, &overrides)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+    serde_json::to_string_pretty(&track)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+}
+
 #[cfg(feature = "python")]
 #[pymodule]
 fn realtime_backend(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
@@ -451,5 +503,8 @@ fn realtime_backend(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(render_full_wav, m)?)?;
     m.add_function(wrap_pyfunction!(enable_gpu, m)?)?;
     m.add_function(wrap_pyfunction!(set_master_gain, m)?)?;
+    m.add_function(wrap_pyfunction!(validate_track, m)?)?;
+    m.add_function(wrap_pyfunction!(normalize_track, m)?)?;
+    m.add_function(wrap_pyfunction!(instantiate_template, m)?)?;
     Ok(())
 }
@@ -0,0 +1,261 @@
+//! Upgrading stored track documents to the canonical schema.
+//!
+//! Track JSON has been written by several tools over time and accepts many
+//! spellings (`globalSettings`/`global`, `progression`, `overlay_clips`, ...).
+//! [`migrate_track_value`] upgrades a document one version at a time to
+//! [`TRACK_FORMAT_VERSION`], after which it deserializes into [`TrackData`]
+//! and serializes back out in a single canonical form. Fields the models do
+//! not know survive normalization untouched.
+
+use crate::models::{TrackData, TRACK_FORMAT_VERSION};
+use crate::template;
+use crate::voices::synth_spec;
+use serde_json::{Map, Value};
+use std::error::Error;
+
+type Migration = fn(&mut Map<String, Value>);
+
+/// `MIGRATIONS[n]` upgrades a version `n` document to version `n + 1`.
+const MIGRATIONS: &[Migration] = &[canonicalize_keys];
+
+/// Upgrade a track document to the current format version.
+///
+/// Documents without `format_version` are version 0. Documents written by a
+/// newer backend are rejected rather than guessed at.
+pub fn migrate_track_value(mut value: Value) -> Result<Value, Box<dyn Error>> {
+    let root = value
+        .as_object_mut()
+        .ok_or("Track document must be a JSON object")?;
+    rename(root, "format_version", &["formatVersion"]);
+    let version = match root.get("format_version") {
+        None => 0,
+        Some(v) => v
+            .as_u64()
+            .ok_or_else(|| format!("Invalid format_version {v}"))?,
+    };
+    if version > TRACK_FORMAT_VERSION as u64 {
+        return Err(format!(
+            "Track format version {version} is newer than supported version {TRACK_FORMAT_VERSION}"
+        )
+        .into());
+    }
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(root);
+    }
+    root.insert("format_version".into(), TRACK_FORMAT_VERSION.into());
+    Ok(value)
+}
+
+/// Parse track JSON of any supported version. Templates are instantiated
+/// with their declared variable values.
+pub fn parse_track(track_json: &str) -> Result<TrackData, Box<dyn Error>> {
+    let track = serde_json::from_value(current_track_value(track_json)?)?;
+    Ok(track)
+}
+
+/// Track JSON of any supported version as a current, concrete document.
+fn current_track_value(track_json: &str) -> Result<Value, Box<dyn Error>> {
+    let value: Value = serde_json::from_str(track_json)?;
+    let value = migrate_track_value(value)?;
+    if template::is_template(&value) {
+        return Ok(template::instantiate_value(value, &Map::new())?);
+    }
+    Ok(value)
+}
+
+/// Rewrite track JSON of any supported version in the canonical schema.
+/// Templates come out instantiated, as concrete tracks.
+pub fn normalize_track_json(track_json: &str) -> Result<String, Box<dyn Error>> {
+    let value = current_track_value(track_json)?;
+    let track: TrackData = serde_json::from_value(value.clone())?;
+    let mut canonical = serde_json::to_value(&track)?;
+    keep_unknown_fields(&mut canonical, value);
+    Ok(serde_json::to_string_pretty(&canonical)?)
+}
+
+/// Copy into `canonical` every field of `original` it lacks, at any depth.
+/// Array items pair up by index.
+fn keep_unknown_fields(canonical: &mut Value, original: Value) {
+    match (canonical, original) {
+        (Value::Object(canonical), Value::Object(original)) => {
+            for (key, value) in original {
+                match canonical.get_mut(&key) {
+                    Some(existing) => keep_unknown_fields(existing, value),
+                    None => {
+                        canonical.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(canonical), Value::Array(original)) => {
+            for (existing, value) in canonical.iter_mut().zip(original) {
+                keep_unknown_fields(existing, value);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Version 0 -> 1: replace every accepted alias with its canonical key and
+/// synth function aliases with their registry names.
+fn canonicalize_keys(root: &mut Map<String, Value>) {
+    rename(root, "global_settings", &["globalSettings", "global"]);
+    rename(root, "steps", &["progression"]);
+    rename(root, "clips", &["overlay_clips"]);
+    rename(root, "background_noise", &["noise"]);
+
+    if let Some(global) = object(root, "global_settings") {
+        rename(global, "sample_rate", &["sampleRate"]);
+        rename(global, "crossfade_duration", &["crossfadeDuration"]);
+        rename(global, "crossfade_curve", &["crossfadeCurve"]);
+        rename(global, "output_filename", &["outputFilename"]);
+    }
+
+    for step in objects(root, "steps") {
+        rename(
+            step,
+            "duration",
+            &["Duration", "durationSeconds", "stepDuration"],
+        );
+        rename(step, "crossfade_duration", &["crossfadeDuration"]);
+        rename(step, "crossfade_curve", &["crossfadeCurve"]);
+        for voice in objects(step, "voices") {
+            rename(
+                voice,
+                "synth_function_name",
+                &["synthFunctionName", "synth_function"],
+            );
+            rename(voice, "params", &["parameters"]);
+            rename(voice, "volume_envelope", &["volumeEnvelope"]);
+            rename(voice, "is_transition", &["isTransition"]);
+            if let Some(env) = object(voice, "volume_envelope") {
+                // Older envelopes keep these strings among their params.
+                for key in ["fade_type", "curve"] {
+                    let Some(params) = object(env, "params") else {
+                        break;
+                    };
+                    if !params.get(key).is_some_and(Value::is_string) {
+                        continue;
+                    }
+                    if let Some(value) = params.remove(key) {
+                        env.entry(key).or_insert(value);
+                    }
+                }
+            }
+            if let Some(Value::String(name)) = voice.get_mut("synth_function_name") {
+                if let Some(spec) = synth_spec(name) {
+                    *name = spec.name.to_string();
+                }
+            }
+        }
+    }
+
+    for clip in objects(root, "clips") {
+        rename(clip, "file_path", &["path", "file"]);
+        rename(clip, "start", &["start_time"]);
+        rename(clip, "amp", &["gain"]);
+    }
+
+    if let Some(noise) = object(root, "background_noise") {
+        rename(noise, "file_path", &["file", "params_path", "noise_file"]);
+        rename(noise, "amp", &["gain"]);
+        rename(noise, "start_time", &["start_time_seconds"]);
+        if let Some(params) = object(noise, "params") {
+            rename(params, "noise_parameters", &["color_params"]);
+        }
+    }
+}
+
+/// Move the first alias present to `canonical` unless it is already set, and
+/// drop the remaining aliases so the document has a single spelling.
+fn rename(obj: &mut Map<String, Value>, canonical: &str, aliases: &[&str]) {
+    for alias in aliases {
+        if let Some(value) = obj.remove(*alias) {
+            if !obj.contains_key(canonical) {
+                obj.insert(canonical.to_string(), value);
+            }
+        }
+    }
+}
+
+fn object<'a>(obj: &'a mut Map<String, Value>, key: &str) -> Option<&'a mut Map<String, Value>> {
+    obj.get_mut(key).and_then(Value::as_object_mut)
+}
+
+fn objects<'a>(
+    obj: &'a mut Map<String, Value>,
+    key: &str,
+) -> impl Iterator<Item = &'a mut Map<String, Value>> {
+    obj.get_mut(key)
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object_mut)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_aliases_normalize_to_canonical_form() {
+        let legacy = r#"{
+            "globalSettings": {"sampleRate": 44100, "crossfadeDuration": 2.0},
+            "progression": [{
+                "durationSeconds": 60.0,
+                "voices": [{
+                    "synthFunctionName": "noise",
+                    "parameters": {"amp": 0.5},
+                    "isTransition": false,
+                    "color": "blue",
+                    "volumeEnvelope": {"type": "linear_fade",
+                                       "params": {"fade_duration": 2.0, "fade_type": "out"}}
+                }]
+            }],
+            "overlay_clips": [{"path": "bell.wav", "start_time": 5.0, "gain": 0.8}],
+            "noise": {"file": "rain.noise", "gain": 0.3, "pan": -0.5}
+        }"#;
+
+        let canonical = normalize_track_json(legacy).expect("legacy track normalizes");
+        let value: Value = serde_json::from_str(&canonical).unwrap();
+        assert_eq!(value["format_version"], TRACK_FORMAT_VERSION);
+        assert_eq!(value["global_settings"]["crossfade_duration"], 2.0);
+        assert_eq!(value["steps"][0]["duration"], 60.0);
+        assert_eq!(
+            value["steps"][0]["voices"][0]["synth_function_name"],
+            "noise_swept_notch"
+        );
+        assert_eq!(value["clips"][0]["file_path"], "bell.wav");
+        assert_eq!(value["background_noise"]["file_path"], "rain.noise");
+        assert_eq!(value["background_noise"]["pan"], -0.5);
+        assert_eq!(value["steps"][0]["voices"][0]["color"], "blue");
+        let envelope = &value["steps"][0]["voices"][0]["volume_envelope"];
+        assert_eq!(envelope["fade_type"], "out");
+        assert_eq!(
+            envelope["params"],
+            serde_json::json!({"fade_duration": 2.0})
+        );
+
+        // Canonical documents are a fixed point.
+        assert_eq!(normalize_track_json(&canonical).unwrap(), canonical);
+    }
+
+    #[test]
+    fn newer_format_versions_are_rejected() {
+        let track = format!(
+            r#"{{"format_version": {}, "global_settings": {{"sample_rate": 44100}}, "steps": []}}"#,
+            TRACK_FORMAT_VERSION + 1
+        );
+        assert!(parse_track(&track).is_err());
+        assert_eq!(MIGRATIONS.len(), TRACK_FORMAT_VERSION as usize);
+
+        // A document without a version is version 0 however it is read.
+        let unversioned = r#"{"global_settings": {"sample_rate": 44100}, "steps": []}"#;
+        let track: TrackData = serde_json::from_str(unversioned).unwrap();
+        assert_eq!(track.format_version, 0);
+        assert_eq!(
+            parse_track(unversioned).unwrap().format_version,
+            TRACK_FORMAT_VERSION
+        );
+    }
+}
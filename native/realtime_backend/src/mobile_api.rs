@@ -1,8 +1,15 @@
 use crate::audio_io::{self, PlaybackState};
+use crate::bundle;
 use crate::command::Command;
 use crate::config::CONFIG;
+use crate::migration;
 use crate::models::TrackData;
-use crate::scheduler::TrackScheduler;
+use crate::plan::{self, EntrainmentPlan};
+use crate::presets::{self, PresetData, PresetLibrary};
+use crate::scheduler::{PreparedTrack, RepeatPosition, TrackScheduler};
+use crate::template;
+use crate::validation::{self, Severity};
+use crate::voices::{self, ParamType};
 use crate::voice_loader;
 use lazy_static::lazy_static;
 use parking_lot::Mutex;
@@ -21,6 +28,10 @@ struct EngineState {
     elapsed_samples: Arc<AtomicU64>,
     /// Shared state for tracking current step
     current_step: Arc<AtomicU64>,
+    /// Shared state for tracking every audible step when steps overlap
+    active_steps: Arc<Mutex<Vec<u64>>>,
+    /// Shared state for tracking the pass through the current repeat block
+    current_repeat: Arc<Mutex<Option<RepeatPosition>>>,
     /// Shared state for tracking pause status
     is_paused: Arc<AtomicBool>,
     /// Sample rate used for converting samples to time
@@ -32,6 +43,8 @@ struct EngineState {
 // but for a single active audio session, this is sufficient.
 lazy_static! {
     static ref ENGINE: Mutex<Option<EngineState>> = Mutex::new(None);
+    /// Preset library shared by the preset APIs, set by load_preset_library.
+    static ref PRESETS: Mutex<Option<PresetLibrary>> = Mutex::new(None);
 }
 
 #[frb(init)]
@@ -48,7 +61,7 @@ pub fn start_audio_session(track_json: String, start_time: Option<f64>) -> anyho
     // Stop existing session
     stop_audio_session();
 
-    let track_data: TrackData = serde_json::from_str(&track_json)
+    let track_data: TrackData = migration::parse_track(&track_json)
         .map_err(|e| anyhow::anyhow!("Invalid track JSON: {}", e))?;
     
     log::info!("track_data parsed successfully");
@@ -86,12 +99,16 @@ pub fn start_audio_session(track_json: String, start_time: Option<f64>) -> anyho
     // Create shared playback state atomics
     let elapsed_samples = Arc::new(AtomicU64::new(0));
     let current_step = Arc::new(AtomicU64::new(0));
+    let active_steps = Arc::new(Mutex::new(Vec::with_capacity(audio_io::MAX_ACTIVE_STEPS)));
+    let current_repeat = Arc::new(Mutex::new(None));
     let is_paused = Arc::new(AtomicBool::new(false));
 
     // Clone Arcs for the audio thread
     let playback_state = PlaybackState {
         elapsed_samples: Arc::clone(&elapsed_samples),
         current_step: Arc::clone(&current_step),
+        active_steps: Arc::clone(&active_steps),
+        current_repeat: Arc::clone(&current_repeat),
         is_paused: Arc::clone(&is_paused),
     };
 
@@ -119,6 +136,8 @@ pub fn start_audio_session(track_json: String, start_time: Option<f64>) -> anyho
         stop_sender: stop_tx,
         elapsed_samples,
         current_step,
+        active_steps,
+        current_repeat,
         is_paused,
         sample_rate,
     });
@@ -157,15 +176,16 @@ pub fn set_volume(volume: f32) {
 }
 
 pub fn update_session(track_json: String) -> anyhow::Result<()> {
-    let track_data: TrackData = serde_json::from_str(&track_json)
+    let track_data: TrackData = migration::parse_track(&track_json)
         .map_err(|e| anyhow::anyhow!("Invalid track JSON: {}", e))?;
     
     let mut guard = ENGINE.lock();
     if let Some(state) = guard.as_mut() {
+        let track = Box::new(PreparedTrack::new(track_data, state.sample_rate));
         let cmd = if state.is_paused.load(Ordering::Relaxed) {
-            Command::UpdateTrack(track_data)
+            Command::UpdateTrack(track)
         } else {
-            Command::UpdateRealtime(track_data)
+            Command::UpdateRealtime(track)
         };
         let _ = state.command_producer.try_push(cmd);
     }
@@ -204,7 +224,7 @@ pub fn enable_gpu(enable: bool) {
 /// Render up to 60 seconds of audio to a WAV file
 /// Maps to Python's render_sample_wav function
 pub fn render_sample_wav(track_json: String, out_path: String) -> anyhow::Result<()> {
-    let track_data: TrackData = serde_json::from_str(&track_json)
+    let track_data: TrackData = migration::parse_track(&track_json)
         .map_err(|e| anyhow::anyhow!("Invalid track JSON: {}", e))?;
 
     let sample_rate = track_data.global_settings.sample_rate;
@@ -212,11 +232,7 @@ pub fn render_sample_wav(track_json: String, out_path: String) -> anyhow::Result
     // Use GPU acceleration when rendering to a file if available
     scheduler.gpu_enabled = true;
 
-    let track_frames: usize = track_data
-        .steps
-        .iter()
-        .map(|s| (s.duration * sample_rate as f64) as usize)
-        .sum();
+    let track_frames: usize = track_data.total_samples(sample_rate);
     // Limit to 60 seconds for sample rendering
     let target_frames = (sample_rate as usize * 60).min(track_frames);
 
@@ -266,7 +282,7 @@ pub fn render_sample_wav(track_json: String, out_path: String) -> anyhow::Result
 /// Render the complete audio track to a WAV file
 /// Maps to Python's render_full_wav function
 pub fn render_full_wav(track_json: String, out_path: String) -> anyhow::Result<()> {
-    let track_data: TrackData = serde_json::from_str(&track_json)
+    let track_data: TrackData = migration::parse_track(&track_json)
         .map_err(|e| anyhow::anyhow!("Invalid track JSON: {}", e))?;
 
     let sample_rate = track_data.global_settings.sample_rate;
@@ -274,11 +290,7 @@ pub fn render_full_wav(track_json: String, out_path: String) -> anyhow::Result<(
     // Enable GPU acceleration for full track rendering
     scheduler.gpu_enabled = true;
 
-    let target_frames: usize = track_data
-        .steps
-        .iter()
-        .map(|s| (s.duration * sample_rate as f64) as usize)
-        .sum();
+    let target_frames: usize = track_data.total_samples(sample_rate);
 
     let spec = WavSpec {
         channels: 2,
@@ -411,30 +423,30 @@ pub fn generate_waveform_snippet(duration_sec: f32) -> Vec<f32> {
 /// Generate waveform data from a track JSON configuration
 /// This creates waveform visualization based on the step structure
 pub fn generate_track_waveform(track_json: String, samples_per_second: u32) -> anyhow::Result<Vec<f32>> {
-    let track_data: crate::models::TrackData = serde_json::from_str(&track_json)
+    let track_data: crate::models::TrackData = migration::parse_track(&track_json)
         .map_err(|e| anyhow::anyhow!("Invalid track JSON: {}", e))?;
 
-    // Calculate total duration from all steps
-    let total_duration: f64 = track_data.steps.iter()
-        .map(|s| s.duration)
-        .sum();
-
-    let total_samples = (total_duration * samples_per_second as f64) as usize;
+    // Place each step on the timeline; explicit starts may leave gaps
+    // (silent) or overlap earlier steps (louder of the two is shown)
+    let layout = track_data.layout(samples_per_second);
+    let total_samples = layout.total_samples();
 
     if total_samples == 0 {
         return Ok(vec![]);
     }
 
-    let mut waveform = Vec::with_capacity(total_samples);
-    let mut current_sample = 0usize;
+    let mut waveform = vec![0.0f32; total_samples];
 
-    for (step_idx, step) in track_data.steps.iter().enumerate() {
-        let step_samples = (step.duration * samples_per_second as f64) as usize;
+    // Repeat blocks are expanded, so each pass of a block is drawn again
+    for pos in 0..layout.len() {
+        let (step_start, step_samples) = layout.span(pos);
+        let step_idx = layout.occurrence(pos).step;
+        let step = &track_data.steps[step_idx];
         let voice_count = step.voices.len().max(1) as f32;
 
         for i in 0..step_samples {
             let local_t = i as f32 / samples_per_second as f32;
-            let global_t = (current_sample + i) as f32 / samples_per_second as f32;
+            let global_t = (step_start + i) as f32 / samples_per_second as f32;
 
             // Base amplitude varies by step (different presets = different patterns)
             let step_factor = 0.4 + ((step_idx as f32 * 0.7).sin().abs() * 0.4);
@@ -447,10 +459,9 @@ pub fn generate_track_waveform(track_json: String, samples_per_second: u32) -> a
             let variation = (global_t * 13.7).sin() * 0.1;
 
             let amplitude = ((wave.abs() * envelope * step_factor) + variation).clamp(0.1, 1.0);
-            waveform.push(amplitude);
+            let slot = &mut waveform[step_start + i];
+            *slot = slot.max(amplitude);
         }
-
-        current_sample += step_samples;
     }
 
     Ok(waveform)
@@ -480,6 +491,39 @@ pub fn get_current_step() -> Option<u64> {
     guard.as_ref().map(|state| state.current_step.load(Ordering::Relaxed))
 }
 
+/// Get the indices of every step currently audible (0-based)
+/// Usually a single step; includes the incoming step during a crossfade and
+/// all overlapping steps when steps are placed with explicit start times
+/// Returns None if no audio session is active
+pub fn get_active_steps() -> Option<Vec<u64>> {
+    let guard = ENGINE.lock();
+    guard.as_ref().map(|state| state.active_steps.lock().clone())
+}
+
+/// Get the current pass through a repeat block
+/// Returns None if no audio session is active or the current step is not
+/// inside a repeat block
+pub fn get_repeat_status() -> Option<RepeatStatus> {
+    let guard = ENGINE.lock();
+    let repeat = (*guard.as_ref()?.current_repeat.lock())?;
+    Some(RepeatStatus {
+        block: repeat.block as u32,
+        iteration: repeat.iteration,
+        count: repeat.count,
+    })
+}
+
+/// Position within a repeat block returned by get_repeat_status
+#[derive(Clone, Debug)]
+pub struct RepeatStatus {
+    /// Index of the repeat block in the track's `repeats`
+    pub block: u32,
+    /// Current pass through the block (0-based)
+    pub iteration: u32,
+    /// Total number of passes
+    pub count: u32,
+}
+
 /// Check if playback is currently paused
 /// Returns None if no audio session is active
 pub fn get_is_paused() -> Option<bool> {
@@ -505,10 +549,368 @@ pub fn get_playback_status() -> Option<PlaybackStatus> {
 pub struct PlaybackStatus {
     /// Current playback position in seconds
     pub position_seconds: f64,
-    /// Current step index (0-based)
+    /// Current step index (0-based). When steps overlap this is the most
+    /// recently started one; during a gap it is the next step to start.
     pub current_step: u64,
     /// Whether playback is paused
     pub is_paused: bool,
     /// Sample rate of the audio session
     pub sample_rate: u32,
 }
+
+/// Rewrite track JSON in the canonical, versioned schema. Older documents
+/// and alternate key spellings are migrated, so the app, the Kotlin port and
+/// the Python tool can all store the same format.
+pub fn normalize_track(track_json: String) -> anyhow::Result<String> {
+    migration::normalize_track_json(&track_json)
+        .map_err(|e| anyhow::anyhow!("Invalid track JSON: {}", e))
+}
+
+/// Instantiate a track template into concrete track JSON
+/// overrides_json is an optional JSON object replacing the values of
+/// variables declared in the template's `vars`, e.g. {"carrier": 180}
+pub fn instantiate_track_template(
+    template_json: String,
+    overrides_json: Option<String>,
+) -> anyhow::Result<String> {
+    let overrides = match overrides_json {
+        Some(json) => serde_json::from_str(&json)
+            .map_err(|e| anyhow::anyhow!("Invalid template overrides: {}", e))?,
+        None => serde_json::Map::new(),
+    };
+    let track_data = template::instantiate_template(&template_json, &overrides)
+        .map_err(|e| anyhow::anyhow!("Invalid track template: {}", e))?;
+    serde_json::to_string(&track_data)
+        .map_err(|e| anyhow::anyhow!("Failed to serialize track: {}", e))
+}
+
+/// Package a track and every file it references (clips, .noise files and the
+/// audio they read, voice audio such as subliminal audio_paths) into a single
+/// bundle file that can be shared between devices
+/// Relative paths in the track are resolved against base_dir when given
+pub fn export_session_bundle(
+    track_json: String,
+    base_dir: Option<String>,
+    out_path: String,
+) -> anyhow::Result<()> {
+    let track_data: TrackData = migration::parse_track(&track_json)
+        .map_err(|e| anyhow::anyhow!("Invalid track JSON: {}", e))?;
+
+    let output_path = if std::path::Path::new(&out_path).is_absolute() {
+        std::path::PathBuf::from(&out_path)
+    } else {
+        CONFIG.output_dir.join(&out_path)
+    };
+
+    bundle::export_bundle(
+        &track_data,
+        base_dir.as_deref().map(std::path::Path::new),
+        &output_path,
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to export bundle: {}", e))?;
+    Ok(())
+}
+
+/// Extract a session bundle into extract_dir and return its track JSON with
+/// file paths pointing at the extracted files, ready for start_audio_session
+/// or the render functions
+pub fn open_session_bundle(bundle_path: String, extract_dir: String) -> anyhow::Result<String> {
+    let track_data = bundle::open_bundle(
+        std::path::Path::new(&bundle_path),
+        std::path::Path::new(&extract_dir),
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to open bundle: {}", e))?;
+    serde_json::to_string(&track_data)
+        .map_err(|e| anyhow::anyhow!("Failed to serialize track: {}", e))
+}
+
+/// Check a track for problems before playback: unknown synth functions,
+/// params the voices don't read, out-of-range values, missing files and
+/// likely clipping. Returns an error only if the JSON itself is malformed.
+pub fn validate_track(track_json: String) -> anyhow::Result<Vec<TrackDiagnostic>> {
+    let diagnostics = validation::validate_track_json(&track_json)
+        .map_err(|e| anyhow::anyhow!("Invalid track JSON: {}", e))?;
+    Ok(diagnostics
+        .into_iter()
+        .map(|d| TrackDiagnostic {
+            path: d.path,
+            severity: match d.severity {
+                Severity::Error => DiagnosticSeverity::Error,
+                Severity::Warning => DiagnosticSeverity::Warning,
+                Severity::Info => DiagnosticSeverity::Info,
+            },
+            message: d.message,
+        })
+        .collect())
+}
+
+/// Severity of a TrackDiagnostic
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiagnosticSeverity {
+    /// The track will not play as written
+    Error,
+    /// The track plays, but probably not as intended
+    Warning,
+    /// Informational only
+    Info,
+}
+
+/// A single problem reported by validate_track
+#[derive(Clone, Debug)]
+pub struct TrackDiagnostic {
+    /// JSON path of the offending value, e.g. `$.steps[0].voices[1].params.beatFreq`
+    pub path: String,
+    /// How serious the problem is
+    pub severity: DiagnosticSeverity,
+    /// Human readable description
+    pub message: String,
+}
+
+/// Describe every synth function and the parameters its voice reads, so the
+/// step editor can build its forms and defaults from the engine itself.
+pub fn get_synth_functions() -> Vec<SynthFunctionInfo> {
+    voices::synth_registry()
+        .iter()
+        .map(|spec| SynthFunctionInfo {
+            name: spec.name.to_string(),
+            aliases: spec.aliases.iter().map(|a| a.to_string()).collect(),
+            is_transition: spec.is_transition,
+            params: spec
+                .params
+                .iter()
+                .map(|p| SynthParamInfo {
+                    name: p.name.to_string(),
+                    param_type: match p.param_type {
+                        ParamType::Float => SynthParamType::Float,
+                        ParamType::Int => SynthParamType::Int,
+                        ParamType::Bool => SynthParamType::Bool,
+                        ParamType::Choice => SynthParamType::Choice,
+                        ParamType::Text => SynthParamType::Text,
+                        ParamType::Json => SynthParamType::Json,
+                    },
+                    default_json: p.default.to_string(),
+                    unit: p.unit.to_string(),
+                    min: p.min,
+                    max: p.max,
+                    choices: p.choices.iter().map(|c| c.to_string()).collect(),
+                    start_key: p.start_key.clone(),
+                    end_key: p.end_key.clone(),
+                    end_default_json: p.end_default.as_ref().map(|v| v.to_string()),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Default params object (as JSON) for a new voice of the given synth function
+pub fn get_default_voice_params(synth_function_name: String) -> anyhow::Result<String> {
+    let spec = voices::synth_spec(&synth_function_name)
+        .ok_or_else(|| anyhow::anyhow!("Unknown synth function: {}", synth_function_name))?;
+    Ok(serde_json::Value::Object(spec.default_params()).to_string())
+}
+
+/// Value type of a SynthParamInfo
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SynthParamType {
+    Float,
+    Int,
+    Bool,
+    /// One of SynthParamInfo.choices
+    Choice,
+    /// Free text such as a file path
+    Text,
+    /// Structured JSON such as noise sweeps
+    Json,
+}
+
+/// One parameter of a synth function, returned by get_synth_functions
+#[derive(Clone, Debug)]
+pub struct SynthParamInfo {
+    /// Params key, e.g. "baseFreq"
+    pub name: String,
+    pub param_type: SynthParamType,
+    /// Default value as JSON ("null" when the voice derives it)
+    pub default_json: String,
+    /// Display unit such as "Hz", "s" or "rad" (empty when unitless)
+    pub unit: String,
+    /// Suggested editing range for numeric params
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// Allowed values for Choice params
+    pub choices: Vec<String>,
+    /// Keys for the start/end values in a transition voice
+    pub start_key: Option<String>,
+    pub end_key: Option<String>,
+    /// Default of end_key as JSON when it differs from default_json
+    pub end_default_json: Option<String>,
+}
+
+/// A synth function and its parameters, returned by get_synth_functions
+#[derive(Clone, Debug)]
+pub struct SynthFunctionInfo {
+    /// Name used as synth_function_name in track JSON
+    pub name: String,
+    /// Other accepted names
+    pub aliases: Vec<String>,
+    /// Whether this is a `_transition` voice with start/end params
+    pub is_transition: bool,
+    pub params: Vec<SynthParamInfo>,
+}
+
+fn with_presets<T>(f: impl FnOnce(&PresetLibrary) -> anyhow::Result<T>) -> anyhow::Result<T> {
+    let guard = PRESETS.lock();
+    let library = guard
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("Preset library not loaded; call load_preset_library first"))?;
+    f(library)
+}
+
+fn preset_info(preset: &presets::Preset) -> PresetInfo {
+    PresetInfo {
+        name: preset.name.clone(),
+        category: preset.category().into(),
+        is_user: preset.is_user(),
+        source_path: preset
+            .source
+            .as_ref()
+            .map(|p| p.to_string_lossy().into_owned()),
+        duration_seconds: preset.duration_seconds(),
+    }
+}
+
+/// Load the preset library from presets.json content (the app's
+/// assets/presets.json) plus user preset directories, which add to and
+/// override it. Replaces any previously loaded library.
+/// Returns the number of presets available
+pub fn load_preset_library(presets_json: String, user_dirs: Vec<String>) -> anyhow::Result<u32> {
+    let mut library = PresetLibrary::from_json_str(&presets_json)
+        .map_err(|e| anyhow::anyhow!("Invalid presets JSON: {}", e))?;
+    for dir in &user_dirs {
+        library
+            .load_user_dir(dir)
+            .map_err(|e| anyhow::anyhow!("Failed to read preset directory {}: {}", dir, e))?;
+    }
+    let count = library.list(None).len() as u32;
+    *PRESETS.lock() = Some(library);
+    Ok(count)
+}
+
+/// List presets sorted by name, optionally limited to one category
+pub fn list_presets(category: Option<PresetCategory>) -> anyhow::Result<Vec<PresetInfo>> {
+    with_presets(|library| {
+        Ok(library
+            .list(category.map(Into::into))
+            .into_iter()
+            .map(preset_info)
+            .collect())
+    })
+}
+
+/// Case-insensitive search over preset names, step descriptions, synth
+/// functions and noise colours
+pub fn search_presets(query: String, category: Option<PresetCategory>) -> anyhow::Result<Vec<PresetInfo>> {
+    with_presets(|library| {
+        Ok(library
+            .search(&query, category.map(Into::into))
+            .into_iter()
+            .map(preset_info)
+            .collect())
+    })
+}
+
+/// Get a preset as JSON: a canonical track for binaural presets, noise
+/// params for noise presets
+pub fn get_preset_json(category: PresetCategory, name: String) -> anyhow::Result<String> {
+    with_presets(|library| {
+        let preset = library
+            .get(category.into(), &name)
+            .ok_or_else(|| anyhow::anyhow!("Unknown preset: {}", name))?;
+        let json = match &preset.data {
+            PresetData::Binaural(track) => serde_json::to_string(track),
+            PresetData::Noise(noise) => serde_json::to_string(noise),
+        };
+        json.map_err(|e| anyhow::anyhow!("Failed to serialize preset: {}", e))
+    })
+}
+
+/// Set a noise preset as the track's background noise and return the updated
+/// track JSON. amp defaults to the amplitude of the noise being replaced
+pub fn apply_noise_preset(track_json: String, name: String, amp: Option<f32>) -> anyhow::Result<String> {
+    let mut track_data: TrackData = migration::parse_track(&track_json)
+        .map_err(|e| anyhow::anyhow!("Invalid track JSON: {}", e))?;
+    with_presets(|library| {
+        library
+            .apply_noise_preset(&mut track_data, &name, amp)
+            .map_err(|e| anyhow::anyhow!("{}", e))
+    })?;
+    serde_json::to_string(&track_data).map_err(|e| anyhow::anyhow!("Failed to serialize track: {}", e))
+}
+
+/// Insert a binaural preset's steps into the track at step index insert_at
+/// (appended when None) and return the updated track JSON
+pub fn merge_binaural_preset(
+    track_json: String,
+    name: String,
+    insert_at: Option<u32>,
+) -> anyhow::Result<String> {
+    let mut track_data: TrackData = migration::parse_track(&track_json)
+        .map_err(|e| anyhow::anyhow!("Invalid track JSON: {}", e))?;
+    with_presets(|library| {
+        library
+            .merge_binaural_preset(&mut track_data, &name, insert_at.map(|i| i as usize))
+            .map_err(|e| anyhow::anyhow!("{}", e))
+    })?;
+    serde_json::to_string(&track_data).map_err(|e| anyhow::anyhow!("Failed to serialize track: {}", e))
+}
+
+/// Compile an entrainment plan (target bands or frequencies, durations,
+/// carrier, technique and noise bed) into track JSON
+/// The noise bed preset is looked up in the preset library
+pub fn compile_entrainment_plan(plan_json: String) -> anyhow::Result<String> {
+    let plan: EntrainmentPlan = serde_json::from_str(&plan_json)
+        .map_err(|e| anyhow::anyhow!("Invalid plan JSON: {}", e))?;
+    let track_data = with_presets(|library| {
+        plan::compile_plan(&plan, library).map_err(|e| anyhow::anyhow!("Failed to compile plan: {}", e))
+    })?;
+    serde_json::to_string(&track_data).map_err(|e| anyhow::anyhow!("Failed to serialize track: {}", e))
+}
+
+/// Category of a preset
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PresetCategory {
+    /// A full track whose steps can be merged into another track
+    Binaural,
+    /// Background noise settings
+    Noise,
+}
+
+impl From<PresetCategory> for presets::PresetCategory {
+    fn from(category: PresetCategory) -> Self {
+        match category {
+            PresetCategory::Binaural => presets::PresetCategory::Binaural,
+            PresetCategory::Noise => presets::PresetCategory::Noise,
+        }
+    }
+}
+
+impl From<presets::PresetCategory> for PresetCategory {
+    fn from(category: presets::PresetCategory) -> Self {
+        match category {
+            presets::PresetCategory::Binaural => PresetCategory::Binaural,
+            presets::PresetCategory::Noise => PresetCategory::Noise,
+        }
+    }
+}
+
+/// A preset returned by list_presets and search_presets
+#[derive(Clone, Debug)]
+pub struct PresetInfo {
+    pub name: String,
+    pub category: PresetCategory,
+    /// Whether the preset came from a user preset directory
+    pub is_user: bool,
+    /// File the preset was loaded from, None for bundled presets
+    pub source_path: Option<String>,
+    /// Track length, or noise duration, in seconds
+    pub duration_seconds: f64,
+}
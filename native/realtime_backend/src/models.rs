@@ -1,7 +1,13 @@
-use serde::Deserialize;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize, Serializer};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 
+/// Version of the canonical track schema written by `Serialize`. Documents
+/// without a `format_version` are treated as version 0 and upgraded by
+/// [`crate::migration`].
+pub const TRACK_FORMAT_VERSION: u32 = 1;
+
 fn default_zero_f64() -> f64 {
     0.0
 }
@@ -13,7 +19,7 @@ fn default_amp() -> f32 {
 fn default_crossfade_duration() -> f64 {
     3.0
 }
-fn default_crossfade_curve() -> String {
+pub(crate) fn default_crossfade_curve() -> String {
     "linear".to_string()
 }
 
@@ -30,37 +36,187 @@ pub const BINAURAL_MIX_SCALING: f32 = 1.0;
 /// This matches the user's request for an "invisible" 25% boost.
 pub const NOISE_MIX_SCALING: f32 = 1.0;
 
-fn default_binaural_volume() -> f32 {
+pub(crate) fn default_binaural_volume() -> f32 {
     MAX_INDIVIDUAL_GAIN
 }
 
-fn default_noise_volume() -> f32 {
+pub(crate) fn default_noise_volume() -> f32 {
     MAX_INDIVIDUAL_GAIN
 }
 
-fn default_normalization() -> f32 {
+pub(crate) fn default_normalization() -> f32 {
     0.95
 }
 
-fn default_voice_type() -> String {
+pub(crate) fn default_voice_type() -> String {
     "binaural".to_string()
 }
 
-#[derive(Deserialize, Debug, Clone)]
+/// Serialize a map with its keys in sorted order so canonical documents are
+/// byte-for-byte stable.
+pub(crate) fn serialize_sorted<S, V>(
+    map: &HashMap<String, V>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    V: Serialize,
+{
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(try_from = "RawVolumeEnvelope")]
 pub struct VolumeEnvelope {
     #[serde(rename = "type")]
     pub envelope_type: String,
+    #[serde(default, serialize_with = "serialize_sorted")]
     pub params: HashMap<String, f64>,
+    /// Points of a `breakpoints` envelope, `value` being the gain.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub points: Vec<Breakpoint>,
+    /// Ramp shape of `linear_fade`, `fade_in`, `fade_out` and `fade_in_out`
+    /// envelopes: `linear` (the default) or `db`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub curve: Option<String>,
+    /// Direction of a `linear_fade`: `in` (the default), `out` or `in_out`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fade_type: Option<String>,
+    /// Unit of every time in the envelope: `seconds` (the default) or
+    /// `fraction` of the step's duration.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_unit: Option<String>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+/// A [`VolumeEnvelope`] as written, where older documents keep `fade_type`
+/// and `curve` among the params.
+#[derive(Deserialize)]
+struct RawVolumeEnvelope {
+    #[serde(rename = "type")]
+    envelope_type: String,
+    #[serde(default)]
+    params: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    points: Vec<Breakpoint>,
+    #[serde(default)]
+    curve: Option<String>,
+    #[serde(default)]
+    fade_type: Option<String>,
+    #[serde(default)]
+    time_unit: Option<String>,
+}
+
+impl TryFrom<RawVolumeEnvelope> for VolumeEnvelope {
+    type Error = String;
+
+    fn try_from(raw: RawVolumeEnvelope) -> Result<Self, Self::Error> {
+        let mut env = VolumeEnvelope {
+            envelope_type: raw.envelope_type,
+            params: HashMap::new(),
+            points: raw.points,
+            curve: raw.curve,
+            fade_type: raw.fade_type,
+            time_unit: raw.time_unit,
+        };
+        for (key, value) in raw.params {
+            match (key.as_str(), value) {
+                ("curve", serde_json::Value::String(s)) => {
+                    env.curve.get_or_insert(s);
+                }
+                ("fade_type", serde_json::Value::String(s)) => {
+                    env.fade_type.get_or_insert(s);
+                }
+                (_, value) => {
+                    let number = value
+                        .as_f64()
+                        .ok_or_else(|| format!("Envelope param '{key}' must be a number"))?;
+                    env.params.insert(key, number);
+                }
+            }
+        }
+        Ok(env)
+    }
+}
+
+/// Modulation sources of a voice and the params they drive.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ModulationData {
+    #[serde(default)]
+    pub sources: Vec<ModSourceData>,
+    #[serde(default)]
+    pub routes: Vec<ModRouteData>,
+}
+
+/// A named modulation source: `lfo`, `random`, `sample_hold` or `envelope`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ModSourceData {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub source_type: String,
+    #[serde(default, serialize_with = "serialize_sorted")]
+    pub params: HashMap<String, serde_json::Value>,
+}
+
+/// Routes a source to one numeric voice param. The source value, scaled by
+/// `depth` in the param's own units, is added to the param.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ModRouteData {
+    pub source: String,
+    /// A params key such as `beatFreq`. On `_transition` voices the plain
+    /// key offsets both the start and end values.
+    pub target: String,
+    pub depth: f64,
+    /// `bipolar` (-1..1) or `unipolar` (0..1); defaults to the source's own
+    /// range. A negative depth inverts the route.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub polarity: Option<String>,
+}
+
+/// Breakpoint automation of one param over a step.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct AutomationLane {
+    /// A voice params key such as `beatFreq`, or `binaural_volume` /
+    /// `noise_volume` on a step.
+    pub target: String,
+    pub points: Vec<Breakpoint>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Breakpoint {
+    /// Seconds from the start of the step (or, in a volume envelope, in its
+    /// `time_unit`).
+    pub time: f64,
+    pub value: f64,
+    /// Shape of the segment to the next point: `hold`, `linear`, `exp`,
+    /// `sigmoid` or `bezier`.
+    #[serde(default = "default_breakpoint_curve")]
+    pub curve: String,
+    /// Exponent of an `exp` segment (default 2) or steepness of a `sigmoid`
+    /// one (default 10).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tension: Option<f64>,
+    /// Control points `[x1, y1, x2, y2]` of a `bezier` segment, as in CSS
+    /// `cubic-bezier()`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub handles: Option<[f64; 4]>,
+}
+
+fn default_breakpoint_curve() -> String {
+    "linear".to_string()
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct VoiceData {
     #[serde(alias = "synthFunctionName", alias = "synth_function")]
     pub synth_function_name: String,
-    #[serde(alias = "parameters", default)]
+    #[serde(alias = "parameters", default, serialize_with = "serialize_sorted")]
     pub params: HashMap<String, serde_json::Value>,
-    #[serde(alias = "volumeEnvelope")]
+    #[serde(alias = "volumeEnvelope", skip_serializing_if = "Option::is_none")]
     pub volume_envelope: Option<VolumeEnvelope>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modulation: Option<ModulationData>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub automation: Vec<AutomationLane>,
     #[serde(default, alias = "isTransition")]
     pub is_transition: bool,
     #[serde(default)]
@@ -69,13 +225,13 @@ pub struct VoiceData {
     pub voice_type: String,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct StepData {
     #[serde(alias = "Duration", alias = "durationSeconds", alias = "stepDuration")]
     pub duration: f64,
     #[serde(default)]
     pub description: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub start: Option<f64>,
     pub voices: Vec<VoiceData>,
     #[serde(default = "default_binaural_volume", alias = "binaural_volume")]
@@ -84,9 +240,27 @@ pub struct StepData {
     pub noise_volume: f32,
     #[serde(default = "default_normalization", alias = "normalization_level")]
     pub normalization_level: f32,
+    /// Length of the crossfade into this step, overriding the global setting.
+    /// Zero gives a hard cut.
+    #[serde(
+        default,
+        alias = "crossfadeDuration",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub crossfade_duration: Option<f64>,
+    /// Curve of the crossfade into this step, overriding the global setting.
+    #[serde(
+        default,
+        alias = "crossfadeCurve",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub crossfade_curve: Option<String>,
+    /// Lanes for `binaural_volume` and `noise_volume`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub automation: Vec<AutomationLane>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct GlobalSettings {
     #[serde(alias = "sampleRate")]
     pub sample_rate: u32,
@@ -94,25 +268,76 @@ pub struct GlobalSettings {
     pub crossfade_duration: f64,
     #[serde(default = "default_crossfade_curve", alias = "crossfadeCurve")]
     pub crossfade_curve: String,
-    #[serde(default, alias = "outputFilename")]
+    #[serde(
+        default,
+        alias = "outputFilename",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub output_filename: Option<String>,
     #[serde(default = "default_normalization", alias = "normalization_level")]
     pub normalization_level: f32,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct TrackData {
+    /// Schema version; a document without one is version 0, as
+    /// [`crate::migration`] reads it.
+    #[serde(default, alias = "formatVersion")]
+    pub format_version: u32,
     #[serde(alias = "globalSettings", alias = "global")]
     pub global_settings: GlobalSettings,
     #[serde(alias = "progression")]
     pub steps: Vec<StepData>,
     #[serde(default, alias = "overlay_clips")]
     pub clips: Vec<ClipData>,
-    #[serde(default, alias = "noise")]
+    #[serde(default, alias = "noise", skip_serializing_if = "Option::is_none")]
     pub background_noise: Option<BackgroundNoiseData>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub repeats: Vec<RepeatBlock>,
+}
+
+/// A run of consecutive steps that plays `count` times in a row.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct RepeatBlock {
+    /// Index in `steps` of the first step of the group.
+    pub start_step: usize,
+    /// Number of consecutive steps in the group.
+    pub step_count: usize,
+    /// How many times the group plays; 1 plays it once.
+    pub count: u32,
+    /// Added to numeric voice params once per pass, so pass `n` (0-based)
+    /// plays `value + n * offset`. An offset for a transitioning param such
+    /// as `beatFreq` shifts both its start and end values.
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "serialize_sorted"
+    )]
+    pub param_offsets: HashMap<String, f64>,
+}
+
+/// Most passes a repeat block plays; larger counts are clamped to it.
+pub const MAX_REPEAT_COUNT: u32 = 10_000;
+
+impl RepeatBlock {
+    /// Passes the block plays: its `count`, clamped to [`MAX_REPEAT_COUNT`].
+    pub fn passes(&self) -> u32 {
+        self.count.min(MAX_REPEAT_COUNT)
+    }
+}
+
+/// One played instance of a step once repeat blocks are expanded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StepOccurrence {
+    /// Index into `TrackData::steps`.
+    pub step: usize,
+    /// Index into `TrackData::repeats` when the step belongs to a repeat block.
+    pub block: Option<usize>,
+    /// 0-based pass through the block; always 0 outside blocks.
+    pub iteration: u32,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct ClipData {
     #[serde(alias = "path", alias = "file")]
     pub file_path: String,
@@ -122,7 +347,7 @@ pub struct ClipData {
     pub amp: f32,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct BackgroundNoiseData {
     #[serde(
         default,
@@ -134,7 +359,7 @@ pub struct BackgroundNoiseData {
     pub file_path: String,
     #[serde(default = "default_amp", alias = "gain", alias = "amp")]
     pub amp: f32,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub params: Option<crate::noise_params::NoiseParams>,
     #[serde(default = "default_zero_f64", alias = "start_time_seconds")]
     pub start_time: f64,
@@ -147,6 +372,105 @@ pub struct BackgroundNoiseData {
 }
 
 impl TrackData {
+    /// Repeat blocks that take effect, ordered by their first step. Blocks
+    /// that are empty, run past the last step or overlap an earlier block are
+    /// ignored (the validator reports them).
+    pub fn active_repeat_blocks(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.repeats.len()).collect();
+        order.sort_by_key(|&i| self.repeats[i].start_step);
+        let mut next_free = 0;
+        order
+            .into_iter()
+            .filter(|&i| {
+                let block = &self.repeats[i];
+                let end = block.start_step.saturating_add(block.step_count);
+                let usable = block.step_count > 0
+                    && end <= self.steps.len()
+                    && block.start_step >= next_free;
+                if usable {
+                    next_free = end;
+                }
+                usable
+            })
+            .collect()
+    }
+
+    /// Param offsets and pass number applied to `occ`, if any.
+    pub fn occurrence_offsets(&self, occ: StepOccurrence) -> Option<(&HashMap<String, f64>, u32)> {
+        let block = &self.repeats[occ.block?];
+        (occ.iteration > 0 && !block.param_offsets.is_empty())
+            .then_some((&block.param_offsets, occ.iteration))
+    }
+
+    /// The step played at `occ`, with its repeat block's param offsets applied.
+    pub fn occurrence_step(&self, occ: StepOccurrence) -> Cow<'_, StepData> {
+        let step = &self.steps[occ.step];
+        let Some((offsets, iteration)) = self.occurrence_offsets(occ) else {
+            return Cow::Borrowed(step);
+        };
+        let mut step = step.clone();
+        for voice in &mut step.voices {
+            let spec = crate::voices::synth_spec(&voice.synth_function_name);
+            for (key, offset) in offsets {
+                let delta = offset * iteration as f64;
+                let param = spec.and_then(|s| s.param(key));
+                let keys = std::iter::once(key.as_str()).chain(
+                    param
+                        .into_iter()
+                        .flat_map(|p| [p.start_key.as_deref(), p.end_key.as_deref()])
+                        .flatten(),
+                );
+                let mut shifted = false;
+                for k in keys {
+                    if let Some(v) = voice.params.get_mut(k).filter(|v| v.is_number()) {
+                        *v = (v.as_f64().unwrap_or(0.0) + delta).into();
+                        shifted = true;
+                    }
+                }
+                // Shift the default when the voice leaves the param unset. A
+                // transition voice reads its start and end keys, and its end
+                // only follows the start when the spec has no end default.
+                let Some(param) = param else { continue };
+                let Some(default) = param.default.as_f64() else {
+                    continue;
+                };
+                let set = |k: &str| voice.params.get(k).is_some_and(|v| v.is_number());
+                match (param.start_key.as_deref(), param.end_key.as_deref()) {
+                    (Some(start), Some(end)) => {
+                        let end_default = param.end_default.as_ref().and_then(|v| v.as_f64());
+                        let fill_start = !set(start) && !set(key);
+                        let fill_end = end_default.filter(|_| !set(end));
+                        if fill_start {
+                            voice
+                                .params
+                                .insert(start.to_string(), (default + delta).into());
+                        }
+                        if let Some(end_default) = fill_end {
+                            voice
+                                .params
+                                .insert(end.to_string(), (end_default + delta).into());
+                        }
+                    }
+                    _ if !shifted => {
+                        voice.params.insert(key.clone(), (default + delta).into());
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Cow::Owned(step)
+    }
+
+    /// Where every step plays at `sample_rate`.
+    pub fn layout(&self, sample_rate: u32) -> TrackLayout {
+        TrackLayout::new(self, sample_rate)
+    }
+
+    /// Length of the track in samples: the end of the last step to finish.
+    pub fn total_samples(&self, sample_rate: u32) -> usize {
+        self.layout(sample_rate).total_samples()
+    }
+
     /// Resolve clip and noise file paths relative to the provided base directory.
     pub fn resolve_relative_paths<P: AsRef<Path>>(&mut self, base: P) {
         let base = base.as_ref();
@@ -157,6 +481,23 @@ impl TrackData {
                     noise.file_path = base.join(p).to_string_lossy().into_owned();
                 }
             }
+            if let Some(params) = &mut noise.params {
+                let p = Path::new(&params.input_audio_path);
+                if !params.input_audio_path.is_empty() && p.is_relative() {
+                    params.input_audio_path = base.join(p).to_string_lossy().into_owned();
+                }
+            }
+        }
+        for step in &mut self.steps {
+            for voice in &mut step.voices {
+                map_voice_file_paths(&mut voice.params, |path| {
+                    if Path::new(path).is_relative() {
+                        base.join(path).to_string_lossy().into_owned()
+                    } else {
+                        path.to_string()
+                    }
+                });
+            }
         }
         for clip in &mut self.clips {
             if !clip.file_path.is_empty() {
@@ -169,6 +510,256 @@ impl TrackData {
     }
 }
 
+/// Where every step of a track plays at one sample rate.
+///
+/// Positions number the steps in playing order with repeat blocks expanded.
+/// Steps play back-to-back in runs: a step whose explicit `start` places it
+/// anywhere other than directly after the previous step begins a new run,
+/// leaving a gap before it or overlapping the runs already playing.
+///
+/// A repeat block is stored once however many passes it plays, and its
+/// positions are resolved from the pass length when asked for.
+#[derive(Debug, Clone, Default)]
+pub struct TrackLayout {
+    /// Ordered by first position.
+    segments: Vec<Segment>,
+    /// Ordered by start sample.
+    runs: Vec<StepRun>,
+}
+
+/// A single step, or every pass of a repeat block, played back-to-back.
+#[derive(Debug, Clone)]
+struct Segment {
+    /// First position of the segment.
+    first: usize,
+    /// Start sample of the first position.
+    start: usize,
+    /// Index into `TrackData::steps` of the first step.
+    step: usize,
+    block: Option<usize>,
+    /// Passes through the segment; 1 outside repeat blocks.
+    passes: u32,
+    /// Start sample of each step within one pass, then the pass length.
+    offsets: Vec<usize>,
+}
+
+impl Segment {
+    fn steps(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    fn pass_length(&self) -> usize {
+        self.offsets[self.steps()]
+    }
+
+    fn positions(&self) -> usize {
+        self.steps() * self.passes as usize
+    }
+
+    fn length(&self) -> usize {
+        self.pass_length().saturating_mul(self.passes as usize)
+    }
+}
+
+/// Positions that play back-to-back from one place on the timeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepRun {
+    /// First position of the run.
+    pub first: usize,
+    /// One past the last position of the run.
+    pub end: usize,
+    /// Start sample of the first step.
+    pub start: usize,
+    /// Length in samples of all the run's steps.
+    pub length: usize,
+}
+
+impl TrackLayout {
+    /// Lay out `track` at `sample_rate`.
+    ///
+    /// Starts of steps inside repeat blocks are ignored, as every pass shifts
+    /// them. A `start` within one sample of the end of the previous step is
+    /// treated as back-to-back so rounding in saved tracks does not split a
+    /// run.
+    pub fn new(track: &TrackData, sample_rate: u32) -> Self {
+        let rate = sample_rate as f64;
+        let samples = |step: &StepData| (step.duration.max(0.0) * rate) as usize;
+        let mut layout = TrackLayout::default();
+        let mut blocks = track.active_repeat_blocks().into_iter().peekable();
+        let mut step = 0;
+        while step < track.steps.len() {
+            if let Some(b) = blocks.next_if(|&b| track.repeats[b].start_step == step) {
+                let block = &track.repeats[b];
+                let steps = &track.steps[step..step + block.step_count];
+                let offsets = std::iter::once(0)
+                    .chain(steps.iter().scan(0, |end, s| {
+                        *end += samples(s);
+                        Some(*end)
+                    }))
+                    .collect();
+                layout.push(step, Some(b), block.passes(), offsets, None);
+                step += block.step_count;
+            } else {
+                let data = &track.steps[step];
+                let start = data.start.map(|s| (s.max(0.0) * rate) as usize);
+                layout.push(step, None, 1, vec![0, samples(data)], start);
+                step += 1;
+            }
+        }
+        layout.runs.sort_by_key(|run| run.start);
+        layout
+    }
+
+    fn push(
+        &mut self,
+        step: usize,
+        block: Option<usize>,
+        passes: u32,
+        offsets: Vec<usize>,
+        start: Option<usize>,
+    ) {
+        let (first, cursor) = self.segments.last().map_or((0, 0), |seg| {
+            (seg.first + seg.positions(), seg.start + seg.length())
+        });
+        let segment = Segment {
+            first,
+            start: start.filter(|s| s.abs_diff(cursor) > 1).unwrap_or(cursor),
+            step,
+            block,
+            passes,
+            offsets,
+        };
+        if segment.positions() == 0 {
+            return;
+        }
+        let (end, length) = (first + segment.positions(), segment.length());
+        match self.runs.last_mut() {
+            Some(run) if segment.start == cursor => {
+                run.end = end;
+                run.length += length;
+            }
+            _ => self.runs.push(StepRun {
+                first,
+                end,
+                start: segment.start,
+                length,
+            }),
+        }
+        self.segments.push(segment);
+    }
+
+    /// Number of positions.
+    pub fn len(&self) -> usize {
+        self.segments
+            .last()
+            .map_or(0, |seg| seg.first + seg.positions())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// The segment holding `pos`, with the pass and the step within the pass.
+    fn locate(&self, pos: usize) -> (&Segment, usize, usize) {
+        let i = self.segments.partition_point(|seg| seg.first <= pos) - 1;
+        let seg = &self.segments[i];
+        let k = pos - seg.first;
+        (seg, k / seg.steps(), k % seg.steps())
+    }
+
+    /// The step played at `pos`.
+    pub fn occurrence(&self, pos: usize) -> StepOccurrence {
+        let (seg, pass, i) = self.locate(pos);
+        StepOccurrence {
+            step: seg.step + i,
+            block: seg.block,
+            iteration: pass as u32,
+        }
+    }
+
+    /// Start sample and length in samples of the step at `pos`.
+    pub fn span(&self, pos: usize) -> (usize, usize) {
+        let (seg, pass, i) = self.locate(pos);
+        let start = seg.start + pass * seg.pass_length() + seg.offsets[i];
+        (start, seg.offsets[i + 1] - seg.offsets[i])
+    }
+
+    /// The position of `run` playing at `sample` and how many samples into
+    /// it `sample` lies, or `None` outside the run.
+    pub fn position_at(&self, run: &StepRun, sample: usize) -> Option<(usize, usize)> {
+        if sample < run.start || sample - run.start >= run.length {
+            return None;
+        }
+        let segments = &self.segments[self.segments.partition_point(|seg| seg.first < run.first)
+            ..self.segments.partition_point(|seg| seg.first < run.end)];
+        let seg = &segments[segments.partition_point(|seg| seg.start + seg.length() <= sample)];
+        let (pass, into_pass) = (
+            (sample - seg.start) / seg.pass_length(),
+            (sample - seg.start) % seg.pass_length(),
+        );
+        let i = seg.offsets.partition_point(|&o| o <= into_pass) - 1;
+        let pos = seg.first + pass * seg.steps() + i;
+        Some((pos, into_pass - seg.offsets[i]))
+    }
+
+    /// Runs of back-to-back steps, ordered by start sample.
+    pub fn runs(&self) -> &[StepRun] {
+        &self.runs
+    }
+
+    /// Length of the track in samples: the end of the last run to finish.
+    pub fn total_samples(&self) -> usize {
+        self.runs
+            .iter()
+            .map(|run| run.start + run.length)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Voice params that name audio files: the noise voices' `input_audio_path`,
+/// the subliminal voice's `audio_path` / `audio_paths` and the tonal voices'
+/// `carrierWavetable` (which may instead hold the samples themselves).
+pub const VOICE_FILE_PARAMS: &[&str] = &[
+    "input_audio_path",
+    "audio_path",
+    "audio_paths",
+    "carrierWavetable",
+];
+
+/// Rewrite every non-empty file path held in a voice's params.
+/// `audio_paths` may be a `;`-separated string or an array; its shape is kept.
+pub fn map_voice_file_paths<F>(params: &mut HashMap<String, serde_json::Value>, mut f: F)
+where
+    F: FnMut(&str) -> String,
+{
+    use serde_json::Value;
+    for key in VOICE_FILE_PARAMS {
+        match params.get_mut(*key) {
+            Some(Value::String(s)) if *key == "audio_paths" => {
+                *s = s
+                    .split(';')
+                    .map(str::trim)
+                    .filter(|p| !p.is_empty())
+                    .map(&mut f)
+                    .collect::<Vec<_>>()
+                    .join(";");
+            }
+            Some(Value::String(s)) if !s.is_empty() => *s = f(s),
+            Some(Value::Array(items)) => {
+                for item in items {
+                    if let Value::String(s) = item {
+                        if !s.is_empty() {
+                            *s = f(s);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::TrackData;
@@ -0,0 +1,527 @@
+//! Modulation sources and routing for voice parameters.
+//!
+//! A voice's `modulation` block declares named sources (LFOs, smoothed
+//! random, sample-and-hold and envelopes) and routes each one to a numeric
+//! param with a depth in that param's own units. [`ModMatrix`] evaluates the
+//! sources once every [`MOD_BLOCK_FRAMES`] frames and hands the modulated
+//! values to the wrapped voice (see `voices::ModulatedVoice`), so new
+//! modulation ideas need no new voice fields.
+
+use crate::dsp::trig::sin_lut;
+use crate::models::{ModSourceData, ModulationData};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Frames between modulation updates (0.7 ms at 44.1 kHz).
+pub const MOD_BLOCK_FRAMES: usize = 32;
+
+/// Source types understood by [`ModSource::from_data`] and the params each reads.
+pub const SOURCE_PARAM_KEYS: &[(&str, &[&str])] = &[
+    ("lfo", &["rate", "shape", "phase"]),
+    ("random", &["rate", "seed"]),
+    ("sample_hold", &["rate", "seed"]),
+    (
+        "envelope",
+        &["delay", "attack", "decay", "sustain", "release"],
+    ),
+];
+
+pub const LFO_SHAPES: &[&str] = &["sine", "triangle", "square", "saw_up", "saw_down"];
+pub const POLARITIES: &[&str] = &["bipolar", "unipolar"];
+
+fn num(params: &HashMap<String, Value>, key: &str, default: f32) -> f32 {
+    params
+        .get(key)
+        .and_then(|v| v.as_f64())
+        .map(|v| v as f32)
+        .unwrap_or(default)
+}
+
+/// Seed from the `seed` param, or a stable hash of the source id so renders
+/// are repeatable.
+fn seed(data: &ModSourceData) -> u64 {
+    data.params
+        .get("seed")
+        .and_then(|v| v.as_u64())
+        .unwrap_or_else(|| {
+            data.id.bytes().fold(0xcbf2_9ce4_8422_2325, |h, b| {
+                (h ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+            })
+        })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum LfoShape {
+    Sine,
+    Triangle,
+    Square,
+    SawUp,
+    SawDown,
+}
+
+impl LfoShape {
+    fn from_name(name: &str) -> Self {
+        match name.to_lowercase().as_str() {
+            "triangle" => LfoShape::Triangle,
+            "square" => LfoShape::Square,
+            "saw_up" | "saw" => LfoShape::SawUp,
+            "saw_down" => LfoShape::SawDown,
+            _ => LfoShape::Sine,
+        }
+    }
+
+    /// Value at `phase` cycles; every shape starts at zero and rises, except
+    /// the square and saws which start at their cycle's beginning.
+    fn value(self, phase: f32) -> f32 {
+        match self {
+            LfoShape::Sine => sin_lut(phase * std::f32::consts::TAU),
+            LfoShape::Triangle => {
+                if phase < 0.25 {
+                    4.0 * phase
+                } else if phase < 0.75 {
+                    2.0 - 4.0 * phase
+                } else {
+                    4.0 * phase - 4.0
+                }
+            }
+            LfoShape::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            LfoShape::SawUp => 2.0 * phase - 1.0,
+            LfoShape::SawDown => 1.0 - 2.0 * phase,
+        }
+    }
+}
+
+enum SourceKind {
+    Lfo {
+        shape: LfoShape,
+        rate: f32,
+        /// Phase at the start of the voice, in cycles.
+        phase: f32,
+    },
+    /// Cosine-smoothed glide to a new random target every cycle.
+    Random {
+        rate: f32,
+        /// Cycles whose targets have been drawn.
+        cycle: u64,
+        from: f32,
+        to: f32,
+        rng: StdRng,
+    },
+    SampleHold {
+        rate: f32,
+        cycle: u64,
+        value: f32,
+        rng: StdRng,
+    },
+    /// Delay-attack-decay-sustain, with the release ending at the voice's end.
+    Envelope {
+        delay: f32,
+        attack: f32,
+        decay: f32,
+        sustain: f32,
+        release: f32,
+        duration: f32,
+    },
+}
+
+/// One running modulation source. LFOs and random sources are bipolar
+/// (-1..1); envelopes are unipolar (0..1).
+///
+/// The source counts whole frames and derives its time from them, so hours
+/// of small steps land where one long step would.
+pub struct ModSource {
+    kind: SourceKind,
+    frames: u64,
+    sample_rate: f32,
+}
+
+impl ModSource {
+    /// Build a source for a voice lasting `duration` seconds. Unknown source
+    /// types return `None`.
+    pub fn from_data(data: &ModSourceData, duration: f32) -> Option<Self> {
+        let p = &data.params;
+        let rate = num(p, "rate", 1.0).max(0.0);
+        let kind = match data.source_type.to_lowercase().as_str() {
+            "lfo" => SourceKind::Lfo {
+                shape: LfoShape::from_name(p.get("shape").and_then(|v| v.as_str()).unwrap_or("")),
+                rate,
+                phase: (num(p, "phase", 0.0) / std::f32::consts::TAU).rem_euclid(1.0),
+            },
+            "random" => {
+                let mut rng = StdRng::seed_from_u64(seed(data));
+                SourceKind::Random {
+                    rate,
+                    cycle: 0,
+                    from: rng.gen_range(-1.0..=1.0),
+                    to: rng.gen_range(-1.0..=1.0),
+                    rng,
+                }
+            }
+            "sample_hold" => {
+                let mut rng = StdRng::seed_from_u64(seed(data));
+                SourceKind::SampleHold {
+                    rate,
+                    cycle: 0,
+                    value: rng.gen_range(-1.0..=1.0),
+                    rng,
+                }
+            }
+            "envelope" => SourceKind::Envelope {
+                delay: num(p, "delay", 0.0).max(0.0),
+                attack: num(p, "attack", 0.0).max(0.0),
+                decay: num(p, "decay", 0.0).max(0.0),
+                sustain: num(p, "sustain", 1.0).clamp(0.0, 1.0),
+                release: num(p, "release", 0.0).max(0.0),
+                duration,
+            },
+            _ => return None,
+        };
+        Some(Self {
+            kind,
+            frames: 0,
+            sample_rate: 0.0,
+        })
+    }
+
+    pub fn is_bipolar(&self) -> bool {
+        !matches!(self.kind, SourceKind::Envelope { .. })
+    }
+
+    /// Current value, then advance by `frames` frames at `sample_rate`.
+    pub fn next(&mut self, frames: usize, sample_rate: f32) -> f32 {
+        self.sample_rate = sample_rate;
+        let value = self.value();
+        self.frames += frames as u64;
+        let time = self.time();
+        match &mut self.kind {
+            SourceKind::Lfo { .. } | SourceKind::Envelope { .. } => {}
+            SourceKind::Random {
+                rate,
+                cycle,
+                from,
+                to,
+                rng,
+            } => {
+                // Draw every target passed, so the sequence does not depend
+                // on how playback was split into steps.
+                let now = (*rate as f64 * time) as u64;
+                while *cycle < now {
+                    *cycle += 1;
+                    *from = *to;
+                    *to = rng.gen_range(-1.0..=1.0);
+                }
+            }
+            SourceKind::SampleHold {
+                rate,
+                cycle,
+                value,
+                rng,
+            } => {
+                let now = (*rate as f64 * time) as u64;
+                while *cycle < now {
+                    *cycle += 1;
+                    *value = rng.gen_range(-1.0..=1.0);
+                }
+            }
+        }
+        value
+    }
+
+    /// Seconds since the start of the voice.
+    fn time(&self) -> f64 {
+        if self.frames == 0 {
+            0.0
+        } else {
+            self.frames as f64 / self.sample_rate as f64
+        }
+    }
+
+    fn value(&self) -> f32 {
+        // Position within the current cycle of a source running at `rate`.
+        let cycle_pos = |rate: f32| (rate as f64 * self.time()).fract() as f32;
+        match &self.kind {
+            SourceKind::Lfo { shape, rate, phase } => {
+                shape.value((*phase + cycle_pos(*rate)).fract())
+            }
+            SourceKind::Random { rate, from, to, .. } => {
+                let blend = 0.5 - 0.5 * (std::f32::consts::PI * cycle_pos(*rate)).cos();
+                from + (to - from) * blend
+            }
+            SourceKind::SampleHold { value, .. } => *value,
+            SourceKind::Envelope {
+                delay,
+                attack,
+                decay,
+                sustain,
+                release,
+                duration,
+            } => {
+                let time = self.time() as f32;
+                let t = time - delay;
+                let level = if t < 0.0 {
+                    0.0
+                } else if t < *attack {
+                    t / attack
+                } else if t - attack < *decay {
+                    1.0 - (1.0 - sustain) * (t - attack) / decay
+                } else {
+                    *sustain
+                };
+                let remaining = duration - time;
+                if *release > 0.0 && remaining < *release {
+                    level * (remaining / release).max(0.0)
+                } else {
+                    level
+                }
+            }
+        }
+    }
+}
+
+/// How a route reads its source.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Polarity {
+    /// The source's own range.
+    Source,
+    Bipolar,
+    Unipolar,
+}
+
+impl Polarity {
+    /// `None` and unknown names read the source's own range.
+    pub fn from_name(name: Option<&str>) -> Self {
+        match name.map(str::to_lowercase).as_deref() {
+            Some("bipolar") => Polarity::Bipolar,
+            Some("unipolar") => Polarity::Unipolar,
+            _ => Polarity::Source,
+        }
+    }
+
+    fn apply(self, value: f32, bipolar_source: bool) -> f32 {
+        match (self, bipolar_source) {
+            (Polarity::Bipolar, false) => 2.0 * value - 1.0,
+            (Polarity::Unipolar, true) => 0.5 * (value + 1.0),
+            _ => value,
+        }
+    }
+}
+
+/// A modulated field: its unmodulated value and the routes summed onto it.
+struct ModTarget {
+    field: usize,
+    base: f32,
+    terms: Vec<(usize, f32, Polarity)>,
+}
+
+/// The sources and resolved routes of one voice.
+pub struct ModMatrix {
+    sources: Vec<ModSource>,
+    values: Vec<f32>,
+    targets: Vec<ModTarget>,
+}
+
+impl ModMatrix {
+    /// `resolve` maps a route's params key to the indices of the voice
+    /// fields it drives, each with its current value. Routes whose source is
+    /// undeclared or whose target resolves to nothing are dropped.
+    pub fn new<F>(data: &ModulationData, duration: f32, mut resolve: F) -> Self
+    where
+        F: FnMut(&str) -> Vec<(usize, f32)>,
+    {
+        let mut ids = Vec::new();
+        let mut sources = Vec::new();
+        for source in &data.sources {
+            if let Some(s) = ModSource::from_data(source, duration) {
+                ids.push(source.id.as_str());
+                sources.push(s);
+            }
+        }
+        let mut targets: Vec<ModTarget> = Vec::new();
+        for route in &data.routes {
+            let Some(source) = ids.iter().position(|id| *id == route.source) else {
+                continue;
+            };
+            let term = (
+                source,
+                route.depth as f32,
+                Polarity::from_name(route.polarity.as_deref()),
+            );
+            for (field, base) in resolve(&route.target) {
+                match targets.iter_mut().find(|t| t.field == field) {
+                    Some(target) => target.terms.push(term),
+                    None => targets.push(ModTarget {
+                        field,
+                        base,
+                        terms: vec![term],
+                    }),
+                }
+            }
+        }
+        Self {
+            values: vec![0.0; sources.len()],
+            sources,
+            targets,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Move the value `field`'s routes are summed onto. Returns `false`
+    /// when no route drives the field.
+    pub fn set_base(&mut self, field: usize, value: f32) -> bool {
+        match self.targets.iter_mut().find(|t| t.field == field) {
+            Some(target) => {
+                target.base = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move every source `frames` frames on without evaluating the routes.
+    pub fn seek(&mut self, frames: usize, sample_rate: f32) {
+        for source in &mut self.sources {
+            source.next(frames, sample_rate);
+        }
+    }
+
+    /// Evaluate every source for the next `frames` frames and pass each
+    /// modulated field's new value to `set`.
+    pub fn step<F>(&mut self, frames: usize, sample_rate: f32, mut set: F)
+    where
+        F: FnMut(usize, f32),
+    {
+        for (value, source) in self.values.iter_mut().zip(&mut self.sources) {
+            *value = source.next(frames, sample_rate);
+        }
+        for target in &self.targets {
+            let offset: f32 = target
+                .terms
+                .iter()
+                .map(|&(source, depth, polarity)| {
+                    depth * polarity.apply(self.values[source], self.sources[source].is_bipolar())
+                })
+                .sum();
+            set(target.field, target.base + offset);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ModRouteData;
+    use crate::voices::synth_registry;
+
+    fn source(id: &str, source_type: &str, params: Value) -> ModSourceData {
+        ModSourceData {
+            id: id.into(),
+            source_type: source_type.into(),
+            params: serde_json::from_value(params).unwrap(),
+        }
+    }
+
+    #[test]
+    fn routes_sum_onto_the_base_value() {
+        let data = ModulationData {
+            sources: vec![
+                source(
+                    "lfo",
+                    "lfo",
+                    serde_json::json!({"rate": 1.0, "shape": "square"}),
+                ),
+                source(
+                    "env",
+                    "envelope",
+                    serde_json::json!({"attack": 1.0, "release": 1.0}),
+                ),
+            ],
+            routes: vec![
+                ModRouteData {
+                    source: "lfo".into(),
+                    target: "beatFreq".into(),
+                    depth: 2.0,
+                    polarity: Some("unipolar".into()),
+                },
+                ModRouteData {
+                    source: "env".into(),
+                    target: "beatFreq".into(),
+                    depth: -1.0,
+                    polarity: None,
+                },
+                ModRouteData {
+                    source: "missing".into(),
+                    target: "beatFreq".into(),
+                    depth: 5.0,
+                    polarity: None,
+                },
+            ],
+        };
+        let mut matrix = ModMatrix::new(&data, 4.0, |key| match key {
+            "beatFreq" => vec![(0, 10.0)],
+            _ => Vec::new(),
+        });
+        let mut seen = Vec::new();
+        // Blocks of a quarter second at 4 Hz.
+        for _ in 0..16 {
+            matrix.step(1, 4.0, |field, value| {
+                assert_eq!(field, 0);
+                seen.push(value);
+            });
+        }
+        // The square adds 2 on whole seconds and nothing on half seconds; the
+        // envelope subtracts its level, ramping in over the first second and
+        // out over the last.
+        assert_eq!(seen[0], 12.0);
+        assert_eq!(seen[2], 9.5);
+        assert_eq!(seen[4], 11.0);
+        assert_eq!(seen[6], 9.0);
+        assert_eq!(seen[14], 9.5);
+    }
+
+    #[test]
+    fn sources_do_not_drift_over_long_runs() {
+        // An hour at 1 kHz in one-frame steps lands where one seek does.
+        for (source_type, params) in [
+            ("lfo", serde_json::json!({"rate": 0.37, "shape": "sine"})),
+            ("random", serde_json::json!({"rate": 3.1, "seed": 7})),
+            ("sample_hold", serde_json::json!({"rate": 5.3, "seed": 7})),
+        ] {
+            let data = source("s", source_type, params);
+            let mut stepped = ModSource::from_data(&data, 7200.0).unwrap();
+            let mut seeked = ModSource::from_data(&data, 7200.0).unwrap();
+            for _ in 0..3_600_000 {
+                stepped.next(1, 1000.0);
+            }
+            seeked.next(3_600_000, 1000.0);
+            assert_eq!(stepped.value(), seeked.value(), "{source_type}");
+        }
+        // Whole cycles later, the LFO is back where it started.
+        let data = source("lfo", "lfo", serde_json::json!({"rate": 0.25}));
+        let mut lfo = ModSource::from_data(&data, 7200.0).unwrap();
+        let start = lfo.next(4_000_000, 1000.0);
+        assert!((lfo.value() - start).abs() < 1e-6);
+    }
+
+    #[test]
+    fn registry_fields_are_reachable_from_param_keys() {
+        for spec in synth_registry() {
+            for (index, field) in spec.modulatable.iter().enumerate() {
+                let reachable = spec
+                    .param_keys()
+                    .any(|key| spec.modulation_fields(key).contains(&index));
+                assert!(reachable, "{}: no param drives '{field}'", spec.name);
+            }
+        }
+    }
+}
@@ -40,7 +40,11 @@ pub struct NoiseParams {
     pub end_lfo_freq: f32,
     #[serde(default)]
     pub sweeps: Vec<NoiseSweep>,
-    #[serde(default, alias = "color_params")]
+    #[serde(
+        default,
+        alias = "color_params",
+        serialize_with = "crate::models::serialize_sorted"
+    )]
     pub noise_parameters: HashMap<String, Value>,
     #[serde(default)]
     pub start_lfo_phase_offset_deg: f32,
@@ -82,9 +86,79 @@ pub struct NoiseParams {
     pub static_notches: Vec<Value>,
 }
 
+/// Deepest cascade a static notch may ask for. Each stage is a biquad per
+/// channel run on every sample, so an absurd count would stall generation.
+pub const MAX_STATIC_NOTCH_CASCADE: usize = 32;
+
+/// Fixed-frequency notch described by one `static_notches` entry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StaticNotch {
+    pub freq: f32,
+    pub q: f32,
+    pub casc: usize,
+}
+
+impl StaticNotch {
+    /// Accepts `{"freq": 4000, "q": 25, "casc": 10}` (also `frequency` /
+    /// `center_freq` and `cascade` / `cascade_count`), `[freq, q, casc]` or a
+    /// bare frequency. Q and cascade default to the swept-notch defaults;
+    /// the cascade is capped at [`MAX_STATIC_NOTCH_CASCADE`].
+    pub fn from_value(value: &Value) -> Option<Self> {
+        let num = |v: Option<&Value>| v.and_then(|v| v.as_f64());
+        let (freq, q, casc) = match value {
+            Value::Number(n) => (n.as_f64(), None, None),
+            Value::Array(items) => (num(items.first()), num(items.get(1)), num(items.get(2))),
+            Value::Object(map) => (
+                num(map
+                    .get("freq")
+                    .or_else(|| map.get("frequency"))
+                    .or_else(|| map.get("center_freq"))),
+                num(map.get("q")),
+                num(map
+                    .get("casc")
+                    .or_else(|| map.get("cascade"))
+                    .or_else(|| map.get("cascade_count"))),
+            ),
+            _ => return None,
+        };
+        let freq = freq? as f32;
+        if !freq.is_finite() || freq <= 0.0 {
+            return None;
+        }
+        Some(Self {
+            freq,
+            q: q.map(|q| q as f32).filter(|q| *q > 0.0).unwrap_or(25.0),
+            casc: casc
+                .map(|c| c as usize)
+                .filter(|c| *c > 0)
+                .unwrap_or(10)
+                .min(MAX_STATIC_NOTCH_CASCADE),
+        })
+    }
+}
+
+impl NoiseParams {
+    /// The usable entries of `static_notches`; malformed entries are skipped.
+    pub fn parsed_static_notches(&self) -> Vec<StaticNotch> {
+        self.static_notches
+            .iter()
+            .filter_map(StaticNotch::from_value)
+            .collect()
+    }
+}
+
 pub fn load_noise_params(path: &str) -> Result<NoiseParams, Box<dyn std::error::Error>> {
     let file = std::fs::File::open(path)?;
-    let params: NoiseParams = serde_json::from_reader(file)?;
+    let mut params: NoiseParams = serde_json::from_reader(file)?;
+    // A relative input_audio_path is relative to the .noise file itself.
+    if !params.input_audio_path.is_empty() {
+        let audio = std::path::Path::new(&params.input_audio_path);
+        if audio.is_relative() {
+            if let Some(dir) = std::path::Path::new(path).parent() {
+                params.input_audio_path = dir.join(audio).to_string_lossy().into_owned();
+            }
+        }
+    }
     Ok(apply_color_params(params))
 }
 
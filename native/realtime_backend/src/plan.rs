@@ -0,0 +1,373 @@
+//! Compiling high-level entrainment plans into tracks.
+//!
+//! A plan describes a session the way users think about it: "start at 14 Hz
+//! beta, descend to 6 Hz theta over 20 min, hold 30 min, return to alpha".
+//! [`compile_plan`] turns it into a [`TrackData`] with one step per segment:
+//! ramps use the technique's `*_transition` synth with matching start/end
+//! params, holds use the steady synth.
+//!
+//! ```json
+//! {
+//!   "technique": "binaural",
+//!   "carrier": 200,
+//!   "start": 14,
+//!   "segments": [
+//!     {"target": "theta", "duration": 1200},
+//!     {"duration": 1800},
+//!     {"target": "alpha", "duration": 600, "carrier": 180}
+//!   ],
+//!   "noise": {"preset": "Pink Noise", "amp": 0.3}
+//! }
+//! ```
+
+use crate::models::{
+    default_binaural_volume, default_crossfade_curve, default_noise_volume, default_normalization,
+    default_voice_type, GlobalSettings, StepData, TrackData, VoiceData, TRACK_FORMAT_VERSION,
+};
+use crate::presets::PresetLibrary;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::error::Error;
+
+/// Named bands and the beat frequency, in Hz, used for each.
+pub const BANDS: &[(&str, f64)] = &[
+    ("delta", 2.0),
+    ("theta", 6.0),
+    ("alpha", 10.0),
+    ("smr", 13.0),
+    ("beta", 18.0),
+    ("gamma", 40.0),
+];
+
+/// How the beat frequency is presented.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Technique {
+    #[default]
+    Binaural,
+    Isochronic,
+    Qam,
+}
+
+impl Technique {
+    /// Synth functions for steady and transitioning segments.
+    fn synths(self) -> (&'static str, &'static str) {
+        match self {
+            Technique::Binaural => ("binaural_beat", "binaural_beat_transition"),
+            Technique::Isochronic => ("isochronic_tone", "isochronic_tone_transition"),
+            Technique::Qam => ("qam_beat", "qam_beat_transition"),
+        }
+    }
+
+    /// Params placing a beat of `beat` Hz on a `carrier` Hz tone.
+    fn params(self, carrier: f64, beat: f64, amp: f64) -> Vec<(&'static str, f64)> {
+        match self {
+            Technique::Binaural | Technique::Isochronic => vec![
+                ("ampL", amp),
+                ("ampR", amp),
+                ("baseFreq", carrier),
+                ("beatFreq", beat),
+            ],
+            // QAM carries the beat both as the left/right frequency offset
+            // and as the amplitude modulation rate.
+            Technique::Qam => vec![
+                ("ampL", amp),
+                ("ampR", amp),
+                ("baseFreqL", carrier),
+                ("baseFreqR", carrier + beat),
+                ("qamAmFreqL", beat),
+                ("qamAmFreqR", beat),
+            ],
+        }
+    }
+}
+
+/// A beat frequency given either in Hz or as a band name from [`BANDS`].
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum FrequencyTarget {
+    Hz(f64),
+    Band(String),
+}
+
+impl FrequencyTarget {
+    pub fn hz(&self) -> Result<f64, Box<dyn Error>> {
+        match self {
+            FrequencyTarget::Hz(hz) => Ok(*hz),
+            FrequencyTarget::Band(name) => BANDS
+                .iter()
+                .find(|(band, _)| band.eq_ignore_ascii_case(name.trim()))
+                .map(|&(_, hz)| hz)
+                .ok_or_else(|| {
+                    let known: Vec<&str> = BANDS.iter().map(|(band, _)| *band).collect();
+                    format!("Unknown band '{name}' (expected {})", known.join(", ")).into()
+                }),
+        }
+    }
+}
+
+/// One stretch of the plan. Without a `target` the current frequency is held.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PlanSegment {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<FrequencyTarget>,
+    /// Length in seconds.
+    pub duration: f64,
+    /// Carrier to glide to over the segment; the previous carrier when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub carrier: Option<f64>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+}
+
+/// Background noise taken from a noise preset.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct NoiseBed {
+    pub preset: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amp: Option<f32>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct EntrainmentPlan {
+    #[serde(default)]
+    pub technique: Technique,
+    /// Carrier tone in Hz.
+    #[serde(default = "default_carrier")]
+    pub carrier: f64,
+    /// Beat frequency at the start of the session.
+    pub start: FrequencyTarget,
+    pub segments: Vec<PlanSegment>,
+    /// Amplitude of each ear's tone.
+    #[serde(default = "default_amp")]
+    pub amp: f64,
+    /// Curve of frequency ramps: `linear`, `logarithmic` or `exponential`.
+    #[serde(default = "default_transition_curve", alias = "transitionCurve")]
+    pub transition_curve: String,
+    #[serde(default, alias = "noiseBed", skip_serializing_if = "Option::is_none")]
+    pub noise: Option<NoiseBed>,
+    #[serde(default = "default_sample_rate", alias = "sampleRate")]
+    pub sample_rate: u32,
+    #[serde(default = "default_crossfade", alias = "crossfadeDuration")]
+    pub crossfade_duration: f64,
+}
+
+fn default_carrier() -> f64 {
+    200.0
+}
+
+fn default_amp() -> f64 {
+    0.5
+}
+
+fn default_transition_curve() -> String {
+    "linear".to_string()
+}
+
+fn default_sample_rate() -> u32 {
+    44100
+}
+
+fn default_crossfade() -> f64 {
+    3.0
+}
+
+/// Compile a plan into a track. The noise bed preset is looked up in
+/// `presets`.
+pub fn compile_plan(
+    plan: &EntrainmentPlan,
+    presets: &PresetLibrary,
+) -> Result<TrackData, Box<dyn Error>> {
+    if plan.segments.is_empty() {
+        return Err("Plan has no segments".into());
+    }
+    let (steady_synth, transition_synth) = plan.technique.synths();
+    let mut beat = plan.start.hz()?;
+    let mut carrier = plan.carrier;
+    check_frequencies(carrier, beat, "start")?;
+
+    let mut steps = Vec::with_capacity(plan.segments.len());
+    for (i, segment) in plan.segments.iter().enumerate() {
+        if segment.duration.is_nan() || segment.duration <= 0.0 {
+            return Err(
+                format!("Segment {i} has non-positive duration {}", segment.duration).into(),
+            );
+        }
+        let end_beat = match &segment.target {
+            Some(target) => target.hz()?,
+            None => beat,
+        };
+        let end_carrier = segment.carrier.unwrap_or(carrier);
+        check_frequencies(end_carrier, end_beat, &format!("segment {i}"))?;
+
+        let mut params: HashMap<String, Value> = HashMap::new();
+        let start = plan.technique.params(carrier, beat, plan.amp);
+        let end = plan.technique.params(end_carrier, end_beat, plan.amp);
+        let ramps = start != end;
+        if ramps {
+            for ((key, from), (_, to)) in start.into_iter().zip(end) {
+                params.insert(transition_key("start", key), from.into());
+                params.insert(transition_key("end", key), to.into());
+            }
+            params.insert(
+                "transition_curve".to_string(),
+                plan.transition_curve.clone().into(),
+            );
+        } else {
+            params.extend(
+                start
+                    .into_iter()
+                    .map(|(key, v)| (key.to_string(), v.into())),
+            );
+        }
+
+        let description = if segment.description.is_empty() {
+            describe(beat, end_beat)
+        } else {
+            segment.description.clone()
+        };
+        steps.push(StepData {
+            duration: segment.duration,
+            description,
+            start: None,
+            voices: vec![VoiceData {
+                synth_function_name: if ramps {
+                    transition_synth
+                } else {
+                    steady_synth
+                }
+                .to_string(),
+                params,
+                volume_envelope: None,
+                modulation: None,
+                automation: Vec::new(),
+                is_transition: ramps,
+                description: String::new(),
+                voice_type: default_voice_type(),
+            }],
+            binaural_volume: default_binaural_volume(),
+            noise_volume: default_noise_volume(),
+            normalization_level: default_normalization(),
+            crossfade_duration: None,
+            crossfade_curve: None,
+            automation: Vec::new(),
+        });
+        beat = end_beat;
+        carrier = end_carrier;
+    }
+
+    let mut track = TrackData {
+        format_version: TRACK_FORMAT_VERSION,
+        global_settings: GlobalSettings {
+            sample_rate: plan.sample_rate,
+            crossfade_duration: plan.crossfade_duration,
+            crossfade_curve: default_crossfade_curve(),
+            output_filename: None,
+            normalization_level: default_normalization(),
+        },
+        steps,
+        clips: Vec::new(),
+        background_noise: None,
+        repeats: Vec::new(),
+    };
+    if let Some(noise) = &plan.noise {
+        presets.apply_noise_preset(&mut track, &noise.preset, noise.amp)?;
+    }
+    Ok(track)
+}
+
+fn check_frequencies(carrier: f64, beat: f64, at: &str) -> Result<(), Box<dyn Error>> {
+    if beat.is_nan() || beat < 0.0 {
+        return Err(format!("Beat frequency {beat} Hz at {at} must not be negative").into());
+    }
+    if carrier.is_nan() || carrier <= beat {
+        return Err(format!(
+            "Carrier {carrier} Hz at {at} must be above the beat frequency {beat} Hz"
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// `baseFreq` -> `startBaseFreq` / `endBaseFreq`.
+fn transition_key(prefix: &str, key: &str) -> String {
+    let mut chars = key.chars();
+    let first = chars.next().map(|c| c.to_ascii_uppercase());
+    format!("{prefix}{}{}", first.unwrap_or_default(), chars.as_str())
+}
+
+fn describe(from: f64, to: f64) -> String {
+    let name = |hz: f64| {
+        let band = BANDS
+            .iter()
+            .find(|&&(_, band_hz)| (band_hz - hz).abs() < 1e-9)
+            .map(|(band, _)| format!(" {band}"))
+            .unwrap_or_default();
+        format!("{hz} Hz{band}")
+    };
+    if (from - to).abs() < 1e-9 {
+        format!("Hold {}", name(from))
+    } else {
+        format!("{} -> {}", name(from), name(to))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::{validate_track_value, Severity};
+
+    #[test]
+    fn plans_compile_to_valid_tracks() {
+        let plan: EntrainmentPlan = serde_json::from_value(serde_json::json!({
+            "technique": "isochronic",
+            "carrier": 200,
+            "start": 14,
+            "segments": [
+                {"target": "theta", "duration": 1200},
+                {"duration": 1800},
+                {"target": "alpha", "duration": 600}
+            ],
+            "noise": {"preset": "Pink Noise", "amp": 0.3}
+        }))
+        .unwrap();
+        let presets = PresetLibrary::from_json_str(
+            r#"{"noise": {"Pink Noise": {"noise_parameters": {"name": "pink"}}}}"#,
+        )
+        .unwrap();
+        let track = compile_plan(&plan, &presets).expect("plan compiles");
+
+        assert_eq!(track.steps.len(), 3);
+        let descent = &track.steps[0].voices[0];
+        assert_eq!(descent.synth_function_name, "isochronic_tone_transition");
+        assert_eq!(descent.params["startBeatFreq"], 14.0);
+        assert_eq!(descent.params["endBeatFreq"], 6.0);
+        let hold = &track.steps[1].voices[0];
+        assert_eq!(hold.synth_function_name, "isochronic_tone");
+        assert_eq!(hold.params["beatFreq"], 6.0);
+        assert_eq!(track.steps[2].voices[0].params["endBeatFreq"], 10.0);
+        assert_eq!(track.background_noise.as_ref().map(|n| n.amp), Some(0.3));
+
+        let value = serde_json::to_value(&track).unwrap();
+        let errors: Vec<_> = validate_track_value(&value)
+            .into_iter()
+            .filter(|d| d.severity == Severity::Error)
+            .collect();
+        assert!(errors.is_empty(), "{errors:?}");
+    }
+
+    #[test]
+    fn plans_reject_unknown_bands_and_beats_above_the_carrier() {
+        let mut plan: EntrainmentPlan = serde_json::from_value(serde_json::json!({
+            "start": "beta",
+            "segments": [{"target": "thetta", "duration": 60}]
+        }))
+        .unwrap();
+        assert!(compile_plan(&plan, &PresetLibrary::default()).is_err());
+
+        plan.segments[0].target = Some(FrequencyTarget::Hz(6.0));
+        plan.carrier = 4.0;
+        assert!(compile_plan(&plan, &PresetLibrary::default()).is_err());
+    }
+}
@@ -0,0 +1,466 @@
+//! Preset library shared by every frontend.
+//!
+//! The app's `assets/presets.json` groups full tracks under `"binaural"` and
+//! standalone noise settings under `"noise"`; frontends read it and hand its
+//! content to [`PresetLibrary::from_json_str`]. User preset directories add
+//! to (and override) the built-in set: `*.noise` files are noise presets, and
+//! `*.json` files are either a single track or a document in the same grouped
+//! layout.
+
+use crate::migration;
+use crate::models::{BackgroundNoiseData, TrackData};
+use crate::noise_params::{apply_color_params, NoiseParams};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PresetCategory {
+    Binaural,
+    Noise,
+}
+
+impl PresetCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PresetCategory::Binaural => "binaural",
+            PresetCategory::Noise => "noise",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "binaural" => Some(PresetCategory::Binaural),
+            "noise" => Some(PresetCategory::Noise),
+            _ => None,
+        }
+    }
+}
+
+/// A noise preset: generator settings plus the placement fields that travel
+/// with them in `presets.json`.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct NoisePreset {
+    #[serde(flatten)]
+    pub params: NoiseParams,
+    #[serde(default)]
+    pub start_time: f64,
+    #[serde(default)]
+    pub fade_in: f64,
+    #[serde(default)]
+    pub fade_out: f64,
+    #[serde(default)]
+    pub amp_envelope: Vec<[f32; 2]>,
+}
+
+#[derive(Clone, Debug)]
+pub enum PresetData {
+    Binaural(TrackData),
+    Noise(NoisePreset),
+}
+
+#[derive(Clone, Debug)]
+pub struct Preset {
+    pub name: String,
+    /// File the preset was loaded from, `None` for bundled presets.
+    pub source: Option<PathBuf>,
+    pub data: PresetData,
+}
+
+impl Preset {
+    pub fn category(&self) -> PresetCategory {
+        match self.data {
+            PresetData::Binaural(_) => PresetCategory::Binaural,
+            PresetData::Noise(_) => PresetCategory::Noise,
+        }
+    }
+
+    pub fn is_user(&self) -> bool {
+        self.source.is_some()
+    }
+
+    /// Playing time in seconds: the track length, or the noise duration.
+    pub fn duration_seconds(&self) -> f64 {
+        match &self.data {
+            PresetData::Binaural(track) => {
+                let rate = track.global_settings.sample_rate.max(1);
+                track.total_samples(rate) as f64 / rate as f64
+            }
+            PresetData::Noise(noise) => noise.params.duration_seconds as f64,
+        }
+    }
+
+    fn matches(&self, query: &str) -> bool {
+        if self.name.to_lowercase().contains(query) {
+            return true;
+        }
+        match &self.data {
+            PresetData::Binaural(track) => track.steps.iter().any(|step| {
+                step.description.to_lowercase().contains(query)
+                    || step
+                        .voices
+                        .iter()
+                        .any(|v| v.synth_function_name.to_lowercase().contains(query))
+            }),
+            PresetData::Noise(noise) => noise
+                .params
+                .noise_parameters
+                .get("name")
+                .and_then(Value::as_str)
+                .is_some_and(|n| n.to_lowercase().contains(query)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PresetLibrary {
+    presets: BTreeMap<(PresetCategory, String), Preset>,
+}
+
+impl PresetLibrary {
+    /// Parse a document in the `presets.json` layout.
+    pub fn from_json_str(json: &str) -> Result<Self, Box<dyn Error>> {
+        let mut library = Self::default();
+        library.add_grouped(&serde_json::from_str(json)?, None);
+        Ok(library)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let mut library = Self::default();
+        library.add_grouped(
+            &serde_json::from_str(&fs::read_to_string(path)?)?,
+            Some(path),
+        );
+        Ok(library)
+    }
+
+    /// Add every preset found in `dir`, replacing presets of the same name.
+    /// Files that fail to parse are skipped with a warning. Returns the
+    /// number of presets added.
+    pub fn load_user_dir<P: AsRef<Path>>(&mut self, dir: P) -> Result<usize, Box<dyn Error>> {
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir.as_ref())?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.is_file())
+            .collect();
+        paths.sort();
+        let before = self.presets.len();
+        let mut added = 0;
+        for path in paths {
+            let Some(ext) = path.extension().and_then(|e| e.to_str()) else {
+                continue;
+            };
+            let ext = ext.to_ascii_lowercase();
+            if ext != "json" && ext != "noise" {
+                continue;
+            }
+            let name = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let value: Value = match fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|s| serde_json::from_str(&s).map_err(|e| e.to_string()))
+            {
+                Ok(v) => v,
+                Err(e) => {
+                    log::warn!("Skipping preset file {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            if ext == "noise" {
+                added += usize::from(self.add_noise(name, &value, Some(&path)));
+            } else if value.get("binaural").is_some() || value.get("noise").is_some() {
+                added += self.add_grouped(&value, Some(&path));
+            } else {
+                added += usize::from(self.add_binaural(name, &value, Some(&path)));
+            }
+        }
+        log::info!(
+            "Loaded {} presets from {} ({} new)",
+            added,
+            dir.as_ref().display(),
+            self.presets.len() - before
+        );
+        Ok(added)
+    }
+
+    /// Presets sorted by category then name, optionally limited to one category.
+    pub fn list(&self, category: Option<PresetCategory>) -> Vec<&Preset> {
+        self.presets
+            .values()
+            .filter(|p| category.is_none_or(|c| p.category() == c))
+            .collect()
+    }
+
+    /// Case-insensitive search over preset names, step descriptions, synth
+    /// functions and noise colour names.
+    pub fn search(&self, query: &str, category: Option<PresetCategory>) -> Vec<&Preset> {
+        let query = query.trim().to_lowercase();
+        self.list(category)
+            .into_iter()
+            .filter(|p| query.is_empty() || p.matches(&query))
+            .collect()
+    }
+
+    /// Look up a preset by name. Matching falls back to ignoring case and,
+    /// for noise, to the `"<name> Noise"` spelling used by the built-ins.
+    pub fn get(&self, category: PresetCategory, name: &str) -> Option<&Preset> {
+        if let Some(p) = self.presets.get(&(category, name.to_string())) {
+            return Some(p);
+        }
+        let lower = name.to_lowercase();
+        let noise_name = format!("{lower} noise");
+        self.list(Some(category)).into_iter().find(|p| {
+            let candidate = p.name.to_lowercase();
+            candidate == lower || (category == PresetCategory::Noise && candidate == noise_name)
+        })
+    }
+
+    /// Make the named noise preset the track's background noise. `amp`
+    /// defaults to the amplitude of the noise it replaces, or 1.0.
+    pub fn apply_noise_preset(
+        &self,
+        track: &mut TrackData,
+        name: &str,
+        amp: Option<f32>,
+    ) -> Result<(), Box<dyn Error>> {
+        let Some(PresetData::Noise(preset)) =
+            self.get(PresetCategory::Noise, name).map(|p| &p.data)
+        else {
+            return Err(format!("Unknown noise preset '{name}'").into());
+        };
+        let amp = amp
+            .or_else(|| track.background_noise.as_ref().map(|n| n.amp))
+            .unwrap_or(1.0);
+        track.background_noise = Some(BackgroundNoiseData {
+            file_path: String::new(),
+            amp,
+            params: Some(preset.params.clone()),
+            start_time: preset.start_time,
+            fade_in: preset.fade_in,
+            fade_out: preset.fade_out,
+            amp_envelope: preset.amp_envelope.clone(),
+        });
+        Ok(())
+    }
+
+    /// Insert the named binaural preset's steps into `track` at step index
+    /// `at` (the end when `None`). Preset steps that start where the step
+    /// before them ends lose their explicit start, so they crossfade with
+    /// their neighbours like any sequential step. The remaining preset starts
+    /// are shifted to the insertion point, and explicit starts of later track
+    /// steps are pushed back by the preset's length. Repeat blocks are carried
+    /// over from the preset, and a track block that `at` falls inside grows to
+    /// include the inserted steps. The preset's clips, background noise and
+    /// global settings are not merged. Returns the index of the first inserted
+    /// step.
+    pub fn merge_binaural_preset(
+        &self,
+        track: &mut TrackData,
+        name: &str,
+        at: Option<usize>,
+    ) -> Result<usize, Box<dyn Error>> {
+        let Some(PresetData::Binaural(preset)) =
+            self.get(PresetCategory::Binaural, name).map(|p| &p.data)
+        else {
+            return Err(format!("Unknown binaural preset '{name}'").into());
+        };
+        let at = at.unwrap_or(track.steps.len()).min(track.steps.len());
+
+        let rate = track.global_settings.sample_rate.max(1);
+        let layout = track.layout(rate);
+        let first_after = (0..layout.len())
+            .find(|&pos| layout.occurrence(pos).step >= at)
+            .unwrap_or(layout.len());
+        let offset = if first_after == 0 {
+            0.0
+        } else {
+            let (start, len) = layout.span(first_after - 1);
+            (start + len) as f64 / rate as f64
+        };
+        let preset_rate = preset.global_settings.sample_rate.max(1);
+        let preset_layout = preset.layout(preset_rate);
+        let preset_length = preset_layout.total_samples() as f64 / preset_rate as f64;
+        // Steps that begin a run other than at 0 are pinned by their start.
+        let mut pinned = vec![false; preset.steps.len()];
+        for run in preset_layout.runs() {
+            if run.start > 1 {
+                pinned[preset_layout.occurrence(run.first).step] = true;
+            }
+        }
+
+        for step in &mut track.steps[at..] {
+            if let Some(start) = &mut step.start {
+                *start += preset_length;
+            }
+        }
+        let inserted = preset.steps.iter().zip(pinned).map(|(step, pinned)| {
+            let mut step = step.clone();
+            step.start = step.start.filter(|_| pinned).map(|start| start + offset);
+            step
+        });
+        track.steps.splice(at..at, inserted);
+
+        let added = preset.steps.len();
+        for block in &mut track.repeats {
+            if block.start_step >= at {
+                block.start_step += added;
+            } else if block.start_step + block.step_count > at {
+                block.step_count += added;
+            }
+        }
+        track
+            .repeats
+            .extend(preset.repeats.iter().cloned().map(|mut block| {
+                block.start_step += at;
+                block
+            }));
+        Ok(at)
+    }
+
+    /// Add the presets of a grouped document; returns how many were added.
+    fn add_grouped(&mut self, root: &Value, source: Option<&Path>) -> usize {
+        let mut added = 0;
+        if let Some(group) = root.get("binaural").and_then(Value::as_object) {
+            for (name, value) in group {
+                added += usize::from(self.add_binaural(name.clone(), value, source));
+            }
+        }
+        if let Some(group) = root.get("noise").and_then(Value::as_object) {
+            for (name, value) in group {
+                added += usize::from(self.add_noise(name.clone(), value, source));
+            }
+        }
+        added
+    }
+
+    fn add_binaural(&mut self, name: String, value: &Value, source: Option<&Path>) -> bool {
+        let track = migration::migrate_track_value(value.clone())
+            .and_then(|v| Ok(serde_json::from_value::<TrackData>(v)?));
+        match track {
+            Ok(track) => {
+                self.insert(name, source, PresetData::Binaural(track));
+                true
+            }
+            Err(e) => {
+                log::warn!("Skipping binaural preset '{}': {}", name, e);
+                false
+            }
+        }
+    }
+
+    fn add_noise(&mut self, name: String, value: &Value, source: Option<&Path>) -> bool {
+        match serde_json::from_value::<NoisePreset>(value.clone()) {
+            Ok(mut preset) => {
+                preset.params = apply_color_params(preset.params);
+                self.insert(name, source, PresetData::Noise(preset));
+                true
+            }
+            Err(e) => {
+                log::warn!("Skipping noise preset '{}': {}", name, e);
+                false
+            }
+        }
+    }
+
+    fn insert(&mut self, name: String, source: Option<&Path>, data: PresetData) {
+        let preset = Preset {
+            name: name.clone(),
+            source: source.map(Path::to_path_buf),
+            data,
+        };
+        self.presets.insert((preset.category(), name), preset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::TrackScheduler;
+
+    const PRESETS: &str = r#"{
+        "binaural": {
+            "Sweep": {
+                "global_settings": {"sample_rate": 100, "crossfade_duration": 1.0},
+                "steps": [
+                    {"start": 0.0, "duration": 4.0, "description": "settle",
+                     "voices": [{"synth_function_name": "binaural_beat",
+                                 "params": {"baseFreq": 200, "beatFreq": 10}}]},
+                    {"start": 4.0, "duration": 4.0, "description": "drift",
+                     "voices": [{"synth_function_name": "binaural_beat",
+                                 "params": {"baseFreq": 200, "beatFreq": 6}}]},
+                    {"start": 20.0, "duration": 2.0, "voices": []}
+                ]
+            },
+            "Broken": {"steps": "not a list"}
+        },
+        "noise": {
+            "Pink Noise": {"noise_parameters": {"name": "pink"}, "amp": 1.0},
+            "Rolling Brown Noise": {"noise_parameters": {"name": "brown"}, "amp": 1.0}
+        }
+    }"#;
+
+    #[test]
+    fn presets_load_search_and_apply() {
+        let library = PresetLibrary::from_json_str(PRESETS).expect("presets parse");
+        assert_eq!(library.list(Some(PresetCategory::Binaural)).len(), 1);
+        assert!(library.get(PresetCategory::Noise, "pink").is_some());
+        let rolling = library.search("rolling", Some(PresetCategory::Noise));
+        assert_eq!(rolling.len(), 1);
+        assert!(library.search("drift", None)[0].name == "Sweep");
+
+        let mut track: TrackData = serde_json::from_str(
+            r#"{"global_settings": {"sample_rate": 100, "crossfade_duration": 1.0},
+                "steps": [{"duration": 3.0, "voices": []}, {"duration": 3.0, "voices": []}]}"#,
+        )
+        .unwrap();
+        library
+            .apply_noise_preset(&mut track, "Pink", Some(0.4))
+            .expect("noise preset applies");
+        let noise = track.background_noise.as_ref().unwrap();
+        assert!((noise.amp - 0.4).abs() < f32::EPSILON);
+        assert!(noise.params.is_some());
+    }
+
+    #[test]
+    fn merged_presets_keep_sequential_steps_unpinned() {
+        let library = PresetLibrary::from_json_str(PRESETS).unwrap();
+        let mut track: TrackData = serde_json::from_str(
+            r#"{"global_settings": {"sample_rate": 100, "crossfade_duration": 1.0},
+                "steps": [{"duration": 3.0, "voices": []}, {"duration": 3.0, "voices": []}]}"#,
+        )
+        .unwrap();
+        let at = library
+            .merge_binaural_preset(&mut track, "Sweep", Some(1))
+            .expect("binaural preset merges");
+        assert_eq!(at, 1);
+        assert_eq!(track.steps.len(), 5);
+        // Steps that followed on in the preset follow on in the track; the
+        // step pinned after a gap is rebased onto the insertion point.
+        assert_eq!(track.steps[1].start, None);
+        assert_eq!(track.steps[2].start, None);
+        assert_eq!(track.steps[3].start, Some(3.0 + 20.0));
+        assert_eq!(track.steps[4].start, None);
+
+        // The inserted steps crossfade with the track step before them and
+        // with each other, so they share one run.
+        let layout = track.layout(100);
+        assert_eq!(layout.runs().len(), 2);
+        assert_eq!(layout.runs()[0].end - layout.runs()[0].first, 3);
+
+        let mut scheduler = TrackScheduler::new(track, 100);
+        let mut buffer = vec![0.0; 2 * 10];
+        let mut play = |frames: usize| {
+            for _ in 0..frames / 10 {
+                scheduler.process_block(&mut buffer);
+            }
+            scheduler.active_steps().collect::<Vec<_>>()
+        };
+        assert_eq!(play(280), [0, 1]);
+        assert_eq!(play(300), [1, 2]);
+    }
+}
@@ -1,6 +1,9 @@
+use crate::automation::{lane_for, Lane};
 use crate::config::CONFIG;
 use crate::gpu::GpuMixer;
-use crate::models::{BackgroundNoiseData, StepData, TrackData, MAX_INDIVIDUAL_GAIN};
+use crate::models::{
+    BackgroundNoiseData, StepData, StepOccurrence, TrackData, TrackLayout, MAX_INDIVIDUAL_GAIN,
+};
 use crate::noise_params::NoiseParams;
 use crate::streaming_noise::StreamingNoise;
 use crate::voice_loader::{LoadRequest, LoadResponse};
@@ -37,6 +40,14 @@ pub enum CrossfadeCurve {
 }
 
 impl CrossfadeCurve {
+    /// Parse a curve name as used in track JSON; unknown names fall back to linear.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "equal_power" => CrossfadeCurve::EqualPower,
+            _ => CrossfadeCurve::Linear,
+        }
+    }
+
     fn gains(self, ratio: f32) -> (f32, f32) {
         match self {
             CrossfadeCurve::Linear => (1.0 - ratio, ratio),
@@ -66,6 +77,13 @@ fn steps_have_continuous_voices(a: &StepData, b: &StepData) -> bool {
         if va.is_transition != vb.is_transition {
             return false;
         }
+        if va.modulation != vb.modulation {
+            return false;
+        }
+        // Lanes run on the step's clock, so each step restarts its voices.
+        if !va.automation.is_empty() || !vb.automation.is_empty() {
+            return false;
+        }
         if va.voice_type.to_lowercase() != vb.voice_type.to_lowercase() {
             return false;
         }
@@ -76,19 +94,15 @@ fn steps_have_continuous_voices(a: &StepData, b: &StepData) -> bool {
 
 pub struct TrackScheduler {
     pub track: TrackData,
-    pub current_sample: usize,
+    /// Position in the step sequence of the step playing in the most
+    /// recently started chain, which differs from the step index once repeat
+    /// blocks are expanded (see `current_step_index`).
     pub current_step: usize,
-    pub active_voices: Vec<StepVoice>,
-    pub next_voices: Vec<StepVoice>,
     pub sample_rate: f32,
     pub crossfade_samples: usize,
-    pub current_crossfade_samples: usize,
     pub crossfade_curve: CrossfadeCurve,
-    pub crossfade_envelope: Vec<f32>,
     crossfade_prev: Vec<f32>,
     crossfade_next: Vec<f32>,
-    pub next_step_sample: usize,
-    pub crossfade_active: bool,
     pub absolute_sample: u64,
     /// Whether playback is paused
     pub paused: bool,
@@ -112,16 +126,182 @@ pub struct TrackScheduler {
     voice_temp: Vec<f32>,
     /// Temporary buffer for accumulating noise voices separately
     noise_scratch: Vec<f32>,
-    /// Accumulated phases (phase_l, phase_r) carried over from previous voices.
-    /// Used to maintain phase continuity and prevent clicking when transitioning between steps.
-    accumulated_phases: Vec<(f32, f32)>,
+    /// Output of one chain before it is mixed into the block
+    chain_scratch: Vec<f32>,
+    /// Background noise of the block, rendered before the steps
+    bed_scratch: Vec<f32>,
+    /// RMS of `bed_scratch` before the voice gain, which masked subliminals
+    /// follow along with the step's noise voices
+    background_rms: f32,
 
     // Async voice loading
     loader_tx: Option<Sender<LoadRequest>>,
     loader_rx: Option<Receiver<LoadResponse>>,
     cached_next_voices: HashMap<usize, Vec<StepVoice>>,
     pending_requests: Vec<usize>,
-    pending_track_update: Option<TrackData>,
+    pending_track_update: Option<Box<PreparedTrack>>,
+
+    /// Where every step plays, resolved when the track was loaded or edited.
+    /// Chains and cached voices index into its positions.
+    layout: TrackLayout,
+    /// Chains sounding, in the order they started.
+    chains: Vec<StepChain>,
+    /// Index in `layout.runs()` of the next run to start.
+    next_run: usize,
+    /// Compiled `binaural_volume` / `noise_volume` lanes, indexed by step.
+    volume_lanes: Vec<StepVolumeLanes>,
+}
+
+/// A track with its layout and volume lanes resolved for one sample rate,
+/// so loading or editing a track does no layout work on the audio thread.
+#[derive(Debug)]
+pub struct PreparedTrack {
+    pub track: TrackData,
+    sample_rate: u32,
+    layout: TrackLayout,
+    volume_lanes: Vec<StepVolumeLanes>,
+}
+
+impl PreparedTrack {
+    pub fn new(track: TrackData, sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            layout: track.layout(sample_rate),
+            volume_lanes: step_volume_lanes(&track),
+            track,
+        }
+    }
+
+    /// Lay the track out again if it was prepared for another rate.
+    fn for_rate(self, sample_rate: u32) -> Self {
+        if self.sample_rate == sample_rate {
+            self
+        } else {
+            Self::new(self.track, sample_rate)
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+struct StepVolumeLanes {
+    binaural: Option<Lane>,
+    noise: Option<Lane>,
+}
+
+fn step_volume_lanes(track: &TrackData) -> Vec<StepVolumeLanes> {
+    track
+        .steps
+        .iter()
+        .map(|step| StepVolumeLanes {
+            binaural: lane_for(&step.automation, "binaural_volume"),
+            noise: lane_for(&step.automation, "noise_volume"),
+        })
+        .collect()
+}
+
+/// Volume of one mix bus over a block: fixed, or read from a step lane
+/// every frame.
+#[derive(Clone, Copy)]
+enum BusVolume<'a> {
+    Fixed(f32),
+    Lane {
+        lane: &'a Lane,
+        /// Samples of the step played before the block.
+        step_sample: usize,
+        sample_rate: f32,
+    },
+}
+
+impl<'a> BusVolume<'a> {
+    /// A gain override wins over the lane, which wins over the step volume.
+    fn new(
+        gain_override: Option<f32>,
+        lane: Option<&'a Lane>,
+        volume: f32,
+        step_sample: usize,
+        sample_rate: f32,
+    ) -> Self {
+        match (gain_override, lane) {
+            (Some(gain), _) => BusVolume::Fixed(gain),
+            (None, Some(lane)) => BusVolume::Lane {
+                lane,
+                step_sample,
+                sample_rate,
+            },
+            (None, None) => BusVolume::Fixed(volume),
+        }
+    }
+
+    fn at(&self, frame: usize) -> f32 {
+        match *self {
+            BusVolume::Fixed(volume) => volume,
+            BusVolume::Lane {
+                lane,
+                step_sample,
+                sample_rate,
+            } => lane.value_at((step_sample + frame) as f64 / sample_rate as f64) as f32,
+        }
+    }
+}
+
+/// A run of back-to-back steps playing in order, crossfading between
+/// neighbours. An ordinary track plays as a single chain; a step whose
+/// explicit `start` leaves a gap or overlap starts another, mixed with any
+/// chain still sounding.
+struct StepChain {
+    /// Position of the step playing.
+    position: usize,
+    /// One past the last position of the run.
+    end: usize,
+    /// Sample on the timeline where the chain starts (or was seeked to).
+    start_sample: usize,
+    /// Samples of the current step played.
+    sample: usize,
+    voices: Vec<StepVoice>,
+    /// Voices of the incoming step during a crossfade.
+    next_voices: Vec<StepVoice>,
+    crossfade_active: bool,
+    next_step_sample: usize,
+    /// Length and curve of the crossfade in progress, which may come from a
+    /// step override.
+    crossfade_samples: usize,
+    crossfade_curve: CrossfadeCurve,
+    /// Phases (phase_l, phase_r) carried over from the previous step's voices,
+    /// so moving between steps does not click.
+    phases: Vec<(f32, f32)>,
+}
+
+impl StepChain {
+    fn new(position: usize, end: usize, start_sample: usize, sample: usize) -> Self {
+        Self {
+            position,
+            end,
+            start_sample,
+            sample,
+            voices: Vec::new(),
+            next_voices: Vec::new(),
+            crossfade_active: false,
+            next_step_sample: 0,
+            crossfade_samples: 0,
+            crossfade_curve: CrossfadeCurve::Linear,
+            phases: Vec::new(),
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.position >= self.end
+    }
+
+    /// Phases of whatever the chain is playing.
+    fn current_phases(&self) -> Vec<(f32, f32)> {
+        if !self.voices.is_empty() {
+            TrackScheduler::extract_phases_from_voices(&self.voices)
+        } else if !self.next_voices.is_empty() {
+            TrackScheduler::extract_phases_from_voices(&self.next_voices)
+        } else {
+            self.phases.clone()
+        }
+    }
 }
 
 pub enum ClipSamples {
@@ -402,8 +582,8 @@ fn noise_params_realtime_safe(old: &NoiseParams, new: &NoiseParams) -> bool {
 /// Check if realtime-safe parameters changed between two track configurations.
 /// Allows volume and noise sweep/LFO tweaks without rebuilding voices or clips.
 fn is_realtime_safe_change(old: &TrackData, new: &TrackData) -> bool {
-    // Must have same number of steps
-    if old.steps.len() != new.steps.len() {
+    // Must have same number of steps, repeated the same way
+    if old.steps.len() != new.steps.len() || old.repeats != new.repeats {
         return false;
     }
 
@@ -412,19 +592,19 @@ fn is_realtime_safe_change(old: &TrackData, new: &TrackData) -> bool {
         return false;
     }
 
-    // Global settings must match except for normalization_level
+    // Global settings must match except for normalization_level and crossfades,
+    // which are only read when the next crossfade begins
     if old.global_settings.sample_rate != new.global_settings.sample_rate
-        || old.global_settings.crossfade_duration != new.global_settings.crossfade_duration
-        || old.global_settings.crossfade_curve != new.global_settings.crossfade_curve
         || old.global_settings.output_filename != new.global_settings.output_filename
     {
         return false;
     }
 
-    // Compare each step - everything must match except volume-related fields
+    // Compare each step - everything must match except volume and crossfade fields
     for (old_step, new_step) in old.steps.iter().zip(new.steps.iter()) {
-        // Duration must match
-        if (old_step.duration - new_step.duration).abs() > 1e-9 {
+        // Duration and timeline placement must match
+        if (old_step.duration - new_step.duration).abs() > 1e-9 || old_step.start != new_step.start
+        {
             return false;
         }
 
@@ -437,6 +617,8 @@ fn is_realtime_safe_change(old: &TrackData, new: &TrackData) -> bool {
                 || old_voice.params != new_voice.params
                 || old_voice.is_transition != new_voice.is_transition
                 || old_voice.voice_type != new_voice.voice_type
+                || old_voice.modulation != new_voice.modulation
+                || old_voice.automation != new_voice.automation
             {
                 return false;
             }
@@ -491,11 +673,12 @@ fn is_realtime_safe_change(old: &TrackData, new: &TrackData) -> bool {
 }
 
 /// Check if only volume-related parameters changed between two track configurations.
-/// Volume-related parameters (binaural_volume, noise_volume, normalization_level)
-/// can be updated without rebuilding voices or seeking, preserving phase continuity.
+/// Volume-related parameters (binaural_volume, noise_volume, normalization_level
+/// and step automation lanes) can be updated without rebuilding voices or seeking,
+/// preserving phase continuity.
 fn is_volume_only_change(old: &TrackData, new: &TrackData) -> bool {
-    // Must have same number of steps
-    if old.steps.len() != new.steps.len() {
+    // Must have same number of steps, repeated the same way
+    if old.steps.len() != new.steps.len() || old.repeats != new.repeats {
         return false;
     }
 
@@ -515,8 +698,9 @@ fn is_volume_only_change(old: &TrackData, new: &TrackData) -> bool {
 
     // Compare each step - everything must match except volume-related fields
     for (old_step, new_step) in old.steps.iter().zip(new.steps.iter()) {
-        // Duration must match
-        if (old_step.duration - new_step.duration).abs() > 1e-9 {
+        // Duration and timeline placement must match
+        if (old_step.duration - new_step.duration).abs() > 1e-9 || old_step.start != new_step.start
+        {
             return false;
         }
 
@@ -529,6 +713,8 @@ fn is_volume_only_change(old: &TrackData, new: &TrackData) -> bool {
                 || old_voice.params != new_voice.params
                 || old_voice.is_transition != new_voice.is_transition
                 || old_voice.voice_type != new_voice.voice_type
+                || old_voice.modulation != new_voice.modulation
+                || old_voice.automation != new_voice.automation
             {
                 return false;
             }
@@ -566,6 +752,17 @@ pub struct StepVoice {
     pub normalization_peak: f32,
 }
 
+/// Where playback is within a repeat block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RepeatPosition {
+    /// Index into `TrackData::repeats`.
+    pub block: usize,
+    /// 0-based pass through the block.
+    pub iteration: u32,
+    /// Total number of passes.
+    pub count: u32,
+}
+
 impl StepVoice {
     fn process(&mut self, output: &mut [f32]) {
         self.kind.process(output);
@@ -746,13 +943,16 @@ impl TrackScheduler {
         loader_tx: Option<Sender<LoadRequest>>,
         loader_rx: Option<Receiver<LoadResponse>>,
     ) -> Self {
+        let PreparedTrack {
+            track,
+            layout,
+            volume_lanes,
+            ..
+        } = PreparedTrack::new(track, device_rate);
         let sample_rate = device_rate as f32;
         let crossfade_samples =
             (track.global_settings.crossfade_duration * sample_rate as f64) as usize;
-        let crossfade_curve = match track.global_settings.crossfade_curve.as_str() {
-            "equal_power" => CrossfadeCurve::EqualPower,
-            _ => CrossfadeCurve::Linear,
-        };
+        let crossfade_curve = CrossfadeCurve::from_name(&track.global_settings.crossfade_curve);
         let mut clips = Vec::new();
         let cfg = &CONFIG;
         for c in &track.clips {
@@ -809,20 +1009,13 @@ impl TrackScheduler {
 
         let mut sched = Self {
             track,
-            current_sample: 0,
             current_step: 0,
-            active_voices: Vec::new(),
-            next_voices: Vec::new(),
             sample_rate,
             crossfade_samples,
-            current_crossfade_samples: 0,
             crossfade_curve,
-            crossfade_envelope: Vec::with_capacity(crossfade_samples),
             // Pre-allocate crossfade buffers to avoid allocations in audio callback
             crossfade_prev: vec![0.0; preallocated_crossfade],
             crossfade_next: vec![0.0; preallocated_crossfade],
-            next_step_sample: 0,
-            crossfade_active: false,
             absolute_sample: 0,
             paused: false,
             clips,
@@ -843,12 +1036,18 @@ impl TrackScheduler {
             gpu: GpuMixer::new(),
             voice_temp: vec![0.0; PREALLOCATED_BUFFER_SIZE],
             noise_scratch: vec![0.0; PREALLOCATED_BUFFER_SIZE],
-            accumulated_phases: Vec::new(),
+            chain_scratch: vec![0.0; PREALLOCATED_BUFFER_SIZE],
+            bed_scratch: vec![0.0; PREALLOCATED_BUFFER_SIZE],
+            background_rms: 0.0,
             loader_tx,
             loader_rx,
             cached_next_voices: HashMap::new(),
             pending_requests: Vec::new(),
             pending_track_update: None,
+            chains: Vec::with_capacity(layout.runs().len()),
+            layout,
+            next_run: 0,
+            volume_lanes,
         };
 
         let start_samples = (start_time * sample_rate as f64) as usize;
@@ -868,27 +1067,24 @@ impl TrackScheduler {
             };
         }
 
-        let mut remaining = abs_samples;
-        self.current_step = 0;
-        self.current_sample = 0;
-        for (idx, step) in self.track.steps.iter().enumerate() {
-            let step_samples = (step.duration * self.sample_rate as f64) as usize;
-            if remaining < step_samples {
-                self.current_step = idx;
-                self.current_sample = remaining;
+        // Every run spanning the seek position picks up at its own offset;
+        // later runs start when playback reaches them.
+        self.chains.clear();
+        self.next_run = self.layout.runs().len();
+        for (i, run) in self.layout.runs().iter().enumerate() {
+            if run.start > abs_samples {
+                self.next_run = i;
                 break;
             }
-            remaining = remaining.saturating_sub(step_samples);
+            if let Some((pos, sample)) = self.layout.position_at(run, abs_samples) {
+                self.chains
+                    .push(StepChain::new(pos, run.end, abs_samples, sample));
+            }
         }
+        self.current_step = self.current_position();
 
-        self.active_voices.clear();
-        self.next_voices.clear();
-        self.crossfade_active = false;
-        self.current_crossfade_samples = 0;
-        self.next_step_sample = 0;
         self.crossfade_prev.clear();
         self.crossfade_next.clear();
-        self.accumulated_phases.clear();
         if let Some(noise) = &mut self.background_noise {
             noise.playback_sample = 0;
             noise.started = false;
@@ -906,8 +1102,84 @@ impl TrackScheduler {
         }
     }
 
+    /// Length in samples and curve of the crossfade into the step at `pos`:
+    /// the step's own overrides where set, the global settings otherwise.
+    fn incoming_crossfade(&self, pos: usize) -> (usize, CrossfadeCurve) {
+        let step = &self.track.steps[self.layout.occurrence(pos).step];
+        let samples = step
+            .crossfade_duration
+            .map(|d| (d.max(0.0) * self.sample_rate as f64) as usize)
+            .unwrap_or(self.crossfade_samples);
+        let curve = step
+            .crossfade_curve
+            .as_deref()
+            .map(CrossfadeCurve::from_name)
+            .unwrap_or(self.crossfade_curve);
+        (samples, curve)
+    }
+
+    /// Position reported as current: the step playing in the most recently
+    /// started chain, otherwise the first step of the next run (during a
+    /// gap), or the sequence length once playback has ended.
+    fn current_position(&self) -> usize {
+        self.chains
+            .last()
+            .map(|chain| chain.position)
+            .or_else(|| self.layout.runs().get(self.next_run).map(|run| run.first))
+            .unwrap_or(self.layout.len())
+    }
+
+    /// Phases of the first chain sounding, handed to the chain that replaces
+    /// it after an edit or seek so the carrier does not restart at phase 0.
+    fn carried_phases(&self) -> Vec<(f32, f32)> {
+        self.chains
+            .first()
+            .map(StepChain::current_phases)
+            .unwrap_or_default()
+    }
+
+    fn restore_phases(&mut self, phases: Vec<(f32, f32)>) {
+        if let Some(chain) = self.chains.first_mut() {
+            chain.phases = phases;
+        }
+    }
+
+    /// Build the voices for the step at `pos`, applying its repeat offsets.
+    fn voices_for_position(&self, pos: usize) -> Vec<StepVoice> {
+        voices_for_step(
+            &self.track.occurrence_step(self.layout.occurrence(pos)),
+            self.sample_rate,
+        )
+    }
+
+    /// Whether the steps at positions `a` and `b` play identical voices, so
+    /// moving between them needs no crossfade. Passes with different param
+    /// offsets always crossfade.
+    fn positions_have_continuous_voices(&self, a: usize, b: usize) -> bool {
+        let (occ_a, occ_b) = (self.layout.occurrence(a), self.layout.occurrence(b));
+        let offsets_a = self
+            .track
+            .occurrence_offsets(occ_a)
+            .map(|(_, n)| (occ_a.block, n));
+        let offsets_b = self
+            .track
+            .occurrence_offsets(occ_b)
+            .map(|(_, n)| (occ_b.block, n));
+        offsets_a == offsets_b
+            && steps_have_continuous_voices(
+                &self.track.steps[occ_a.step],
+                &self.track.steps[occ_b.step],
+            )
+    }
+
     /// Replace the current track data while preserving playback progress.
-    pub fn update_track(&mut self, track: TrackData) {
+    pub fn update_track(&mut self, track: PreparedTrack) {
+        let PreparedTrack {
+            track,
+            layout,
+            volume_lanes,
+            ..
+        } = track.for_rate(self.sample_rate as u32);
         // Fast path: if only volume-related parameters changed, we can update
         // the track data in place without rebuilding voices or seeking.
         // This preserves perfect phase continuity for binaural_volume, noise_volume,
@@ -917,6 +1189,7 @@ impl TrackScheduler {
             // in render_step_audio via apply_gain_stage, so existing voices
             // will automatically use the new volume values.
             self.track = track.clone();
+            self.volume_lanes = volume_lanes;
 
             // Update noise gain if noise is active (noise config is compatible)
             if let (Some(ref mut noise), Some(noise_cfg)) =
@@ -935,20 +1208,11 @@ impl TrackScheduler {
         // this, `seek_samples` would clear the cached phases and newly
         // constructed voices would restart at phase 0, producing an audible
         // reset whenever parameters change mid-stream.
-        let preserved_phases = if !self.active_voices.is_empty() {
-            Self::extract_phases_from_voices(&self.active_voices)
-        } else if !self.next_voices.is_empty() {
-            Self::extract_phases_from_voices(&self.next_voices)
-        } else {
-            self.accumulated_phases.clone()
-        };
+        let preserved_phases = self.carried_phases();
 
         self.crossfade_samples =
             (track.global_settings.crossfade_duration * self.sample_rate as f64) as usize;
-        self.crossfade_curve = match track.global_settings.crossfade_curve.as_str() {
-            "equal_power" => CrossfadeCurve::EqualPower,
-            _ => CrossfadeCurve::Linear,
-        };
+        self.crossfade_curve = CrossfadeCurve::from_name(&track.global_settings.crossfade_curve);
 
         // Check if we can reuse the existing noise generator (only gain changed).
         // This preserves the noise phase/LFO state and prevents audible resets.
@@ -1012,11 +1276,14 @@ impl TrackScheduler {
             };
         }
 
+        self.chains.reserve(layout.runs().len());
+        self.layout = layout;
+        self.volume_lanes = volume_lanes;
         self.seek_samples(abs_samples);
 
         // Restore the captured phases so the next render reuses the current
         // oscillator states and remains continuous across the update.
-        self.accumulated_phases = preserved_phases;
+        self.restore_phases(preserved_phases);
         self.crossfade_prev.clear();
         self.crossfade_next.clear();
         #[cfg(feature = "gpu")]
@@ -1025,26 +1292,37 @@ impl TrackScheduler {
         }
     }
 
-    fn update_realtime(&mut self, track: TrackData) -> bool {
-        if !is_realtime_safe_change(&self.track, &track) {
-            return false;
+    /// Apply an edit that keeps every voice and the layout, handing the track
+    /// back when it needs a full update.
+    fn update_realtime(&mut self, prepared: Box<PreparedTrack>) -> Result<(), Box<PreparedTrack>> {
+        if !is_realtime_safe_change(&self.track, &prepared.track) {
+            return Err(prepared);
         }
 
         if let (Some(ref mut noise), Some(noise_cfg)) =
-            (&mut self.background_noise, &track.background_noise)
+            (&mut self.background_noise, &prepared.track.background_noise)
         {
             noise.set_gain(noise_cfg.amp * self.noise_gain);
             if let Some(params) = &noise_cfg.params {
                 let mut params = params.clone();
                 apply_background_noise_overrides(noise_cfg, &mut params);
                 if !noise.update_realtime_params(&params) {
-                    return false;
+                    return Err(prepared);
                 }
             }
         }
 
+        let PreparedTrack {
+            track,
+            volume_lanes,
+            ..
+        } = *prepared;
+        self.crossfade_samples =
+            (track.global_settings.crossfade_duration * self.sample_rate as f64) as usize;
+        self.crossfade_curve = CrossfadeCurve::from_name(&track.global_settings.crossfade_curve);
         self.track = track;
-        true
+        self.volume_lanes = volume_lanes;
+        Ok(())
     }
 
     pub fn handle_command(&mut self, cmd: Command) {
@@ -1052,21 +1330,19 @@ impl TrackScheduler {
             Command::UpdateTrack(t) => {
                 if self.paused {
                     self.pending_track_update = None;
-                    self.update_track(t);
+                    self.update_track(*t);
                 } else {
                     self.pending_track_update = Some(t);
                 }
             }
-            Command::UpdateRealtime(t) => {
-                if self.update_realtime(t.clone()) {
-                    self.pending_track_update = None;
-                } else if self.paused {
+            Command::UpdateRealtime(t) => match self.update_realtime(t) {
+                Ok(()) => self.pending_track_update = None,
+                Err(t) if self.paused => {
                     self.pending_track_update = None;
-                    self.update_track(t);
-                } else {
-                    self.pending_track_update = Some(t);
+                    self.update_track(*t);
                 }
-            }
+                Err(t) => self.pending_track_update = Some(t),
+            },
             Command::EnableGpu(enable) => {
                 self.gpu_enabled = enable;
             }
@@ -1081,17 +1357,11 @@ impl TrackScheduler {
                 let samples = (time * self.sample_rate as f64) as usize;
                 // Preserve current phases before seeking to prevent discontinuities
                 // when the user scrubs the audio timeline.
-                let preserved_phases = if !self.active_voices.is_empty() {
-                    Self::extract_phases_from_voices(&self.active_voices)
-                } else if !self.next_voices.is_empty() {
-                    Self::extract_phases_from_voices(&self.next_voices)
-                } else {
-                    self.accumulated_phases.clone()
-                };
+                let preserved_phases = self.carried_phases();
                 self.seek_samples(samples);
                 // Restore the captured phases so the next render reuses the current
                 // oscillator states and remains continuous across the seek.
-                self.accumulated_phases = preserved_phases;
+                self.restore_phases(preserved_phases);
             }
             Command::SetMasterGain(gain) => {
                 self.master_gain = gain.clamp(0.0, 1.0);
@@ -1129,7 +1399,8 @@ impl TrackScheduler {
     fn apply_gain_stage(
         buffer: &mut [f32],
         norm_target: f32,
-        volume: f32,
+        volume: BusVolume<'_>,
+        mix_scaling: f32,
         has_content: bool,
         normalization_peak: f32,
     ) {
@@ -1145,7 +1416,16 @@ impl TrackScheduler {
         };
 
         // Clamp volume to MAX_INDIVIDUAL_GAIN to prevent clipping when sources combine
-        let clamped_volume = volume.clamp(0.0, MAX_INDIVIDUAL_GAIN);
+        let BusVolume::Fixed(volume) = volume else {
+            for (i, frame) in buffer.chunks_exact_mut(2).enumerate() {
+                let gain = normalization_gain
+                    * (volume.at(i) * mix_scaling).clamp(0.0, MAX_INDIVIDUAL_GAIN);
+                frame[0] *= gain;
+                frame[1] *= gain;
+            }
+            return;
+        };
+        let clamped_volume = (volume * mix_scaling).clamp(0.0, MAX_INDIVIDUAL_GAIN);
         let total_gain = normalization_gain * clamped_volume;
 
         if (total_gain - 1.0).abs() > f32::EPSILON {
@@ -1155,14 +1435,54 @@ impl TrackScheduler {
         }
     }
 
+    /// Render the step at sequence position `pos`, `step_sample` samples
+    /// into the step, with its gain overrides and volume lanes applied.
+    fn render_position(
+        &mut self,
+        pos: usize,
+        voices: &mut [StepVoice],
+        step_sample: usize,
+        out: &mut [f32],
+    ) {
+        let step_index = self.layout.occurrence(pos).step;
+        let step = &self.track.steps[step_index];
+        let norm = self
+            .normalization_level_override
+            .unwrap_or(step.normalization_level);
+        let (binaural_volume, noise_volume) = (step.binaural_volume, step.noise_volume);
+        let lanes = self
+            .volume_lanes
+            .get_mut(step_index)
+            .map(std::mem::take)
+            .unwrap_or_default();
+        let binaural = BusVolume::new(
+            self.binaural_gain_override,
+            lanes.binaural.as_ref(),
+            binaural_volume,
+            step_sample,
+            self.sample_rate,
+        );
+        let noise = BusVolume::new(
+            self.noise_gain_override,
+            lanes.noise.as_ref(),
+            noise_volume,
+            step_sample,
+            self.sample_rate,
+        );
+        self.render_step_audio(voices, norm, binaural, noise, out);
+        if let Some(slot) = self.volume_lanes.get_mut(step_index) {
+            *slot = lanes;
+        }
+    }
+
     /// Render audio for a step's voices into the output buffer.
     /// Takes gain parameters directly to avoid cloning StepData in the audio callback.
     fn render_step_audio(
         &mut self,
         voices: &mut [StepVoice],
         normalization_level: f32,
-        binaural_volume: f32,
-        noise_volume: f32,
+        binaural_volume: BusVolume<'_>,
+        noise_volume: BusVolume<'_>,
         out: &mut [f32],
     ) {
         let len = out.len();
@@ -1178,8 +1498,9 @@ impl TrackScheduler {
             self.voice_temp.resize(len, 0.0);
         }
 
-        let binaural_buf = &mut self.scratch;
-        let noise_buf = &mut self.noise_scratch;
+        // Voices render exactly `len` samples: the buffers may be longer.
+        let binaural_buf = &mut self.scratch[..len];
+        let noise_buf = &mut self.noise_scratch[..len];
         binaural_buf.fill(0.0);
         noise_buf.fill(0.0);
         let mut binaural_count = 0usize;
@@ -1188,8 +1509,12 @@ impl TrackScheduler {
         let mut noise_peak = 0.0f32;
 
         for voice in voices.iter_mut() {
-            self.voice_temp.fill(0.0);
-            voice.process(&mut self.voice_temp);
+            if voice.kind.is_masked() {
+                continue;
+            }
+            let voice_temp = &mut self.voice_temp[..len];
+            voice_temp.fill(0.0);
+            voice.process(voice_temp);
             match voice.voice_type {
                 VoiceType::Noise => {
                     noise_count += 1;
@@ -1211,14 +1536,16 @@ impl TrackScheduler {
         Self::apply_gain_stage(
             binaural_buf,
             normalization_level,
-            binaural_volume * crate::models::BINAURAL_MIX_SCALING,
+            binaural_volume,
+            crate::models::BINAURAL_MIX_SCALING,
             binaural_count > 0,
             binaural_peak,
         );
         Self::apply_gain_stage(
             noise_buf,
             normalization_level,
-            noise_volume * crate::models::NOISE_MIX_SCALING,
+            noise_volume,
+            crate::models::NOISE_MIX_SCALING,
             noise_count > 0,
             noise_peak,
         );
@@ -1227,12 +1554,31 @@ impl TrackScheduler {
         for i in 0..len {
             out[i] = binaural_buf[i] + noise_buf[i];
         }
+
+        // Masked subliminals sit at a fixed SNR under the finished noise bed,
+        // the step's noise voices plus the background noise, so they skip
+        // both gain stages.
+        let step_rms = if len > 0 {
+            (noise_buf.iter().map(|v| v * v).sum::<f32>() / len as f32).sqrt()
+        } else {
+            0.0
+        };
+        let bed_rms = step_rms.hypot(self.background_rms);
+        for voice in voices.iter_mut().filter(|v| v.kind.is_masked()) {
+            voice.kind.follow_noise_bed(bed_rms);
+            let voice_temp = &mut self.voice_temp[..len];
+            voice_temp.fill(0.0);
+            voice.process(voice_temp);
+            for i in 0..len {
+                out[i] += voice_temp[i];
+            }
+        }
     }
 
     pub fn pause(&mut self) {
         self.paused = true;
         if let Some(track) = self.pending_track_update.take() {
-            self.update_track(track);
+            self.update_track(*track);
         }
     }
 
@@ -1244,8 +1590,39 @@ impl TrackScheduler {
         self.paused
     }
 
+    /// Index into `track.steps` of the current step, or `steps.len()` once
+    /// playback has finished.
     pub fn current_step_index(&self) -> usize {
-        self.current_step
+        self.current_occurrence()
+            .map_or(self.track.steps.len(), |occ| occ.step)
+    }
+
+    /// The current step together with its repeat block and pass.
+    pub fn current_occurrence(&self) -> Option<StepOccurrence> {
+        (self.current_step < self.layout.len()).then(|| self.layout.occurrence(self.current_step))
+    }
+
+    /// Repeat block and pass of the current step, if it is inside a block.
+    pub fn current_repeat(&self) -> Option<RepeatPosition> {
+        let occ = self.current_occurrence()?;
+        let block = occ.block?;
+        Some(RepeatPosition {
+            block,
+            iteration: occ.iteration,
+            count: self.track.repeats[block].passes(),
+        })
+    }
+
+    /// Indices of every step currently audible: the step playing in each
+    /// chain plus the incoming one while it crossfades.
+    pub fn active_steps(&self) -> impl Iterator<Item = usize> + '_ {
+        self.chains
+            .iter()
+            .flat_map(|chain| {
+                let incoming = chain.crossfade_active.then_some(chain.position + 1);
+                std::iter::once(chain.position).chain(incoming)
+            })
+            .map(|pos| self.layout.occurrence(pos).step)
     }
 
     pub fn elapsed_samples(&self) -> u64 {
@@ -1253,132 +1630,85 @@ impl TrackScheduler {
     }
 
     /// Extracts accumulated phases from all voices that track phase.
-    /// Returns a vector of (phase_l, phase_r) tuples for each voice that has phases.
+    /// Returns a vector of (phase_l, phase_r) tuples for each voice that has phases,
+    /// one per partial for chord voices.
     fn extract_phases_from_voices(voices: &[StepVoice]) -> Vec<(f32, f32)> {
-        voices.iter().filter_map(|v| v.kind.get_phases()).collect()
+        voices
+            .iter()
+            .flat_map(|v| v.kind.partial_phases())
+            .collect()
     }
 
     /// Applies accumulated phases to newly created voices.
     /// This maintains phase continuity between voice instances to prevent clicking.
     fn apply_phases_to_voices(phases: &[(f32, f32)], voices: &mut [StepVoice]) {
-        // Apply phases to voices that support them, matching by index
-        let mut phase_iter = phases.iter();
+        // Apply phases to voices that support them, matching by index. Chord
+        // voices take one pair per partial.
+        let mut remaining = phases;
         for voice in voices.iter_mut() {
-            // Only apply to voices that have phase tracking
-            if voice.kind.get_phases().is_some() {
-                if let Some(&(phase_l, phase_r)) = phase_iter.next() {
-                    voice.kind.set_phases(phase_l, phase_r);
-                }
-            }
+            let used = voice.kind.set_partial_phases(remaining);
+            remaining = &remaining[used..];
         }
     }
 
-    pub fn process_block(&mut self, buffer: &mut [f32]) {
+    /// Render the next block of `chain` into `buffer`, crossfading into the
+    /// next step of its run as the current one nears its end.
+    fn render_chain(&mut self, chain: &mut StepChain, buffer: &mut [f32]) {
         let frame_count = buffer.len() / 2;
-        buffer.fill(0.0);
-
-        if self.paused {
-            return;
-        }
-
-        if self.current_step >= self.track.steps.len() {
-            return;
-        }
-
-        // POLL FOR COMPLETED VOICE LOADS
-        if let Some(rx) = &self.loader_rx {
-            while let Ok(response) = rx.try_recv() {
-                self.cached_next_voices
-                    .insert(response.step_index, response.voices);
-                self.pending_requests.retain(|&x| x != response.step_index);
-            }
-        }
-
-        // TRIGGER PRELOAD FOR NEXT 2 STEPS (if not already cached or pending)
-        // Preloading 2 steps ahead gives more time for async loading to complete,
-        // reducing the chance of synchronous fallback which causes audio glitches.
-        if let Some(tx) = &self.loader_tx {
-            for lookahead in 1..=2 {
-                let next_step_idx = self.current_step + lookahead;
-                if next_step_idx < self.track.steps.len() {
-                    let already_cached = self.cached_next_voices.contains_key(&next_step_idx);
-                    let already_pending = self.pending_requests.contains(&next_step_idx);
-
-                    if !already_cached && !already_pending {
-                        // Send request
-                        let req = LoadRequest {
-                            step_index: next_step_idx,
-                            step_data: self.track.steps[next_step_idx].clone(),
-                            sample_rate: self.sample_rate,
-                            track_data: self.track.clone(),
-                        };
-                        if tx.send(req).is_ok() {
-                            self.pending_requests.push(next_step_idx);
-                        }
-                    }
-                }
-            }
-        }
 
-        if self.active_voices.is_empty() && !self.crossfade_active {
-            let step = &self.track.steps[self.current_step];
+        if chain.voices.is_empty() && !chain.crossfade_active {
             // Try to get cached voices first, otherwise load synchronously
             let mut new_voices =
-                if let Some(voices) = self.cached_next_voices.remove(&self.current_step) {
+                if let Some(voices) = self.cached_next_voices.remove(&chain.position) {
                     voices
                 } else {
-                    voices_for_step(step, self.sample_rate)
+                    self.voices_for_position(chain.position)
                 };
 
             // Apply accumulated phases from previous voices to maintain phase continuity
-            Self::apply_phases_to_voices(&self.accumulated_phases, &mut new_voices);
-            self.active_voices = new_voices;
+            Self::apply_phases_to_voices(&chain.phases, &mut new_voices);
+            // After a seek the step is already under way.
+            for voice in &mut new_voices {
+                voice.kind.seek(chain.sample);
+            }
+            chain.voices = new_voices;
         }
 
         // Check if we need to start crossfade into the next step
-        if !self.crossfade_active
-            && self.crossfade_samples > 0
-            && self.current_step + 1 < self.track.steps.len()
-        {
-            let step = &self.track.steps[self.current_step];
-            let next_step = &self.track.steps[self.current_step + 1];
-            if !steps_have_continuous_voices(step, next_step) {
-                let step_samples = (step.duration * self.sample_rate as f64) as usize;
-                let fade_len = self.crossfade_samples.min(step_samples);
-                if self.current_sample >= step_samples.saturating_sub(fade_len) {
+        if !chain.crossfade_active && chain.position + 1 < chain.end {
+            let next_step_idx = chain.position + 1;
+            let (crossfade_samples, crossfade_curve) = self.incoming_crossfade(next_step_idx);
+            if crossfade_samples > 0
+                && !self.positions_have_continuous_voices(chain.position, next_step_idx)
+            {
+                let step_samples = self.layout.span(chain.position).1;
+                let fade_len = crossfade_samples.min(step_samples);
+                if chain.sample >= step_samples.saturating_sub(fade_len) {
                     // Extract phases from current voices before transitioning
-                    self.accumulated_phases = Self::extract_phases_from_voices(&self.active_voices);
+                    chain.phases = Self::extract_phases_from_voices(&chain.voices);
 
                     // TRY TO USE PRELOADED VOICES
-                    let next_step_idx = self.current_step + 1;
                     let mut new_next_voices =
                         if let Some(voices) = self.cached_next_voices.remove(&next_step_idx) {
                             voices
                         } else {
                             // FALLBACK: Synchronous load (might block/glitch, but better than crashing)
-                            voices_for_step(next_step, self.sample_rate)
+                            self.voices_for_position(next_step_idx)
                         };
 
                     // Apply accumulated phases to the new voices for continuity
-                    Self::apply_phases_to_voices(&self.accumulated_phases, &mut new_next_voices);
-                    self.next_voices = new_next_voices;
-                    self.crossfade_active = true;
-                    self.next_step_sample = 0;
-                    let next_samples = (next_step.duration * self.sample_rate as f64) as usize;
-                    self.current_crossfade_samples =
-                        self.crossfade_samples.min(step_samples).min(next_samples);
-                    self.crossfade_envelope = if self.current_crossfade_samples <= 1 {
-                        vec![0.0; self.current_crossfade_samples]
-                    } else {
-                        (0..self.current_crossfade_samples)
-                            .map(|i| i as f32 / (self.current_crossfade_samples - 1) as f32)
-                            .collect()
-                    };
+                    Self::apply_phases_to_voices(&chain.phases, &mut new_next_voices);
+                    chain.next_voices = new_next_voices;
+                    chain.crossfade_active = true;
+                    chain.next_step_sample = 0;
+                    let next_samples = self.layout.span(next_step_idx).1;
+                    chain.crossfade_samples = crossfade_samples.min(step_samples).min(next_samples);
+                    chain.crossfade_curve = crossfade_curve;
                 }
             }
         }
 
-        if self.crossfade_active {
+        if chain.crossfade_active {
             let len = buffer.len();
             let frames = len / 2;
             // Ensure buffers are large enough but never shrink to avoid allocations.
@@ -1395,54 +1725,29 @@ impl TrackScheduler {
             prev_buf[..len].fill(0.0);
             next_buf[..len].fill(0.0);
 
-            // Extract only the values needed for rendering (avoid cloning entire StepData)
-            let step = &self.track.steps[self.current_step];
-            let step_norm = self
-                .normalization_level_override
-                .unwrap_or(step.normalization_level);
-            let step_binaural = self
-                .binaural_gain_override
-                .unwrap_or(step.binaural_volume);
-            let step_noise = self.noise_gain_override.unwrap_or(step.noise_volume);
-            let mut voices = std::mem::take(&mut self.active_voices);
-            self.render_step_audio(
-                &mut voices,
-                step_norm,
-                step_binaural,
-                step_noise,
+            self.render_position(
+                chain.position,
+                &mut chain.voices,
+                chain.sample,
                 &mut prev_buf[..len],
             );
-            self.active_voices = voices;
-
-            let next_step_idx = (self.current_step + 1).min(self.track.steps.len() - 1);
-            let next_step = &self.track.steps[next_step_idx];
-            let next_norm = self
-                .normalization_level_override
-                .unwrap_or(next_step.normalization_level);
-            let next_binaural = self
-                .binaural_gain_override
-                .unwrap_or(next_step.binaural_volume);
-            let next_noise = self.noise_gain_override.unwrap_or(next_step.noise_volume);
-            let mut next_voices = std::mem::take(&mut self.next_voices);
-            self.render_step_audio(
-                &mut next_voices,
-                next_norm,
-                next_binaural,
-                next_noise,
+            self.render_position(
+                chain.position + 1,
+                &mut chain.next_voices,
+                chain.next_step_sample,
                 &mut next_buf[..len],
             );
-            self.next_voices = next_voices;
 
             for i in 0..frames {
                 let idx = i * 2;
-                let progress = self.next_step_sample + i;
-                if progress < self.current_crossfade_samples {
-                    let ratio = if progress < self.crossfade_envelope.len() {
-                        self.crossfade_envelope[progress]
+                let progress = chain.next_step_sample + i;
+                if progress < chain.crossfade_samples {
+                    let ratio = if chain.crossfade_samples > 1 {
+                        progress as f32 / (chain.crossfade_samples - 1) as f32
                     } else {
-                        progress as f32 / (self.current_crossfade_samples - 1) as f32
+                        0.0
                     };
-                    let (g_out, g_in) = self.crossfade_curve.gains(ratio);
+                    let (g_out, g_in) = chain.crossfade_curve.gains(ratio);
                     buffer[idx] = prev_buf[idx] * g_out + next_buf[idx] * g_in;
                     buffer[idx + 1] = prev_buf[idx + 1] * g_out + next_buf[idx + 1] * g_in;
                 } else {
@@ -1451,69 +1756,180 @@ impl TrackScheduler {
                 }
             }
 
-            self.current_sample += frames;
-            self.next_step_sample += frames;
+            chain.sample += frames;
+            chain.next_step_sample += frames;
 
-            self.active_voices.retain(|v| !v.is_finished());
-            self.next_voices.retain(|v| !v.is_finished());
+            chain.voices.retain(|v| !v.is_finished());
+            chain.next_voices.retain(|v| !v.is_finished());
 
-            if self.next_step_sample >= self.current_crossfade_samples {
+            if chain.next_step_sample >= chain.crossfade_samples {
                 // Update accumulated phases from the next_voices that are becoming active
-                self.accumulated_phases = Self::extract_phases_from_voices(&self.next_voices);
-                self.current_step += 1;
-                self.current_sample = self.next_step_sample;
-                self.next_step_sample = 0;
-                self.active_voices = std::mem::take(&mut self.next_voices);
-                self.crossfade_active = false;
-                self.crossfade_envelope.clear();
-                self.current_crossfade_samples = 0;
+                chain.phases = Self::extract_phases_from_voices(&chain.next_voices);
+                chain.position += 1;
+                chain.sample = chain.next_step_sample;
+                chain.next_step_sample = 0;
+                chain.voices = std::mem::take(&mut chain.next_voices);
+                chain.crossfade_active = false;
+                chain.crossfade_samples = 0;
             }
 
             self.crossfade_prev = prev_buf;
             self.crossfade_next = next_buf;
         } else {
-            if !self.active_voices.is_empty() {
-                // Extract only the values needed for rendering (avoid cloning entire StepData)
-                let step = &self.track.steps[self.current_step];
-                let norm = self
-                    .normalization_level_override
-                    .unwrap_or(step.normalization_level);
-                let binaural = self
-                    .binaural_gain_override
-                    .unwrap_or(step.binaural_volume);
-                let noise = self.noise_gain_override.unwrap_or(step.noise_volume);
-                let mut voices = std::mem::take(&mut self.active_voices);
-                self.render_step_audio(&mut voices, norm, binaural, noise, buffer);
-                self.active_voices = voices;
-            }
-
-            self.active_voices.retain(|v| !v.is_finished());
-            self.current_sample += frame_count;
-            let step = &self.track.steps[self.current_step];
-            let step_samples = (step.duration * self.sample_rate as f64) as usize;
-            if self.current_sample >= step_samples {
+            if chain.voices.is_empty() {
+                buffer.fill(0.0);
+            } else {
+                self.render_position(chain.position, &mut chain.voices, chain.sample, buffer);
+            }
+
+            chain.voices.retain(|v| !v.is_finished());
+            chain.sample += frame_count;
+            let step_samples = self.layout.span(chain.position).1;
+            if chain.sample >= step_samples {
                 // Extract phases from current voices before clearing to maintain phase continuity
-                self.accumulated_phases = Self::extract_phases_from_voices(&self.active_voices);
-                self.current_step += 1;
-                self.current_sample = 0;
-                self.active_voices.clear();
+                chain.phases = Self::extract_phases_from_voices(&chain.voices);
+                chain.position += 1;
+                // Carry the overshoot so the next step starts where the
+                // layout places it rather than at the block boundary.
+                chain.sample -= step_samples;
+                chain.voices.clear();
             }
         }
+    }
 
-        for v in &mut buffer[..] {
-            *v *= self.voice_gain;
+    /// Start every run reaching this block and mix the chains sounding.
+    /// Overlapping chains are mixed, each step through its own gain stage,
+    /// and gaps between runs are left silent. Explicit placement replaces the
+    /// automatic crossfade between runs; volume envelopes shape their edges
+    /// instead.
+    fn render_chains(&mut self, buffer: &mut [f32]) {
+        let frames = buffer.len() / 2;
+        let block_start = self.absolute_sample as usize;
+
+        while let Some(run) = self.layout.runs().get(self.next_run) {
+            if run.start >= block_start + frames {
+                break;
+            }
+            self.next_run += 1;
+            self.chains
+                .push(StepChain::new(run.first, run.end, run.start, 0));
+        }
+
+        if self.chain_scratch.len() < buffer.len() {
+            self.chain_scratch.resize(buffer.len(), 0.0);
+        }
+        let mut chain_buf = std::mem::take(&mut self.chain_scratch);
+        let mut chains = std::mem::take(&mut self.chains);
+        for chain in chains.iter_mut() {
+            let offset = chain.start_sample.saturating_sub(block_start);
+            let out = &mut chain_buf[..(frames - offset) * 2];
+            self.render_chain(chain, out);
+            for (d, s) in buffer[offset * 2..].iter_mut().zip(out.iter()) {
+                *d += *s;
+            }
+        }
+        chains.retain(|chain| !chain.is_finished());
+        self.chains = chains;
+        self.chain_scratch = chain_buf;
+
+        self.current_step = self.current_position();
+    }
+
+    pub fn process_block(&mut self, buffer: &mut [f32]) {
+        let frame_count = buffer.len() / 2;
+        buffer.fill(0.0);
+
+        if self.paused {
+            return;
+        }
+
+        if self.current_step >= self.layout.len() {
+            return;
+        }
+
+        // POLL FOR COMPLETED VOICE LOADS
+        if let Some(rx) = &self.loader_rx {
+            while let Ok(response) = rx.try_recv() {
+                self.cached_next_voices
+                    .insert(response.step_index, response.voices);
+                self.pending_requests.retain(|&x| x != response.step_index);
+            }
+        }
+
+        // TRIGGER PRELOAD FOR NEXT 2 STEPS (if not already cached or pending)
+        // Preloading 2 steps ahead gives more time for async loading to complete,
+        // reducing the chance of synchronous fallback which causes audio glitches.
+        if let Some(tx) = &self.loader_tx {
+            // The next steps of every chain and the first steps of the runs
+            // still to start.
+            let upcoming = self
+                .chains
+                .iter()
+                .flat_map(|chain| (chain.position + 1..chain.end).take(2))
+                .chain(
+                    self.layout.runs()[self.next_run..]
+                        .iter()
+                        .take(2)
+                        .map(|run| run.first),
+                );
+            for next_step_idx in upcoming {
+                let already_cached = self.cached_next_voices.contains_key(&next_step_idx);
+                let already_pending = self.pending_requests.contains(&next_step_idx);
+
+                if !already_cached && !already_pending {
+                    // Send request
+                    let req = LoadRequest {
+                        step_index: next_step_idx,
+                        step_data: self
+                            .track
+                            .occurrence_step(self.layout.occurrence(next_step_idx))
+                            .into_owned(),
+                        sample_rate: self.sample_rate,
+                        track_data: self.track.clone(),
+                    };
+                    if tx.send(req).is_ok() {
+                        self.pending_requests.push(next_step_idx);
+                    }
+                }
+            }
         }
 
         let frames = frame_count;
 
         let start_sample = self.absolute_sample as usize;
 
+        // The background noise is rendered before the steps so that their
+        // masked subliminals can follow it.
+        let mut bed = std::mem::take(&mut self.bed_scratch);
+        if bed.len() < buffer.len() {
+            bed.resize(buffer.len(), 0.0);
+        }
+        let bed_buf = &mut bed[..buffer.len()];
+        self.background_rms = 0.0;
         if let Some(noise) = &mut self.background_noise {
             if self.scratch.len() != buffer.len() {
                 self.scratch.resize(buffer.len(), 0.0);
             }
-            noise.mix_into(buffer, &mut self.scratch, start_sample);
+            bed_buf.fill(0.0);
+            noise.mix_into(bed_buf, &mut self.scratch, start_sample);
+            if self.voice_gain > 0.0 && !bed_buf.is_empty() {
+                let power = bed_buf.iter().map(|v| v * v).sum::<f32>() / bed_buf.len() as f32;
+                self.background_rms = power.sqrt() / self.voice_gain;
+            }
+        }
+
+        self.render_chains(buffer);
+
+        for v in &mut buffer[..] {
+            *v *= self.voice_gain;
+        }
+
+        if self.background_noise.is_some() {
+            for (d, s) in buffer.iter_mut().zip(bed_buf.iter()) {
+                *d += *s;
+            }
         }
+        self.bed_scratch = bed;
 
         if self.startup_fade_enabled && self.startup_fade_samples > 0 {
             if start_sample >= self.startup_fade_samples {
@@ -1598,9 +2014,10 @@ impl TrackScheduler {
 
 #[cfg(test)]
 mod tests {
-    use super::{
-        BackgroundNoiseData, CrossfadeCurve, GlobalSettings, StepData, TrackData,
-        BINAURAL_MIX_SCALING, MAX_INDIVIDUAL_GAIN, NOISE_MIX_SCALING,
+    use super::CrossfadeCurve;
+    use crate::models::{
+        BackgroundNoiseData, GlobalSettings, StepData, TrackData, MAX_INDIVIDUAL_GAIN,
+        MAX_REPEAT_COUNT,
     };
     use crate::noise_params::NoiseParams;
 
@@ -1613,6 +2030,9 @@ mod tests {
             binaural_volume: MAX_INDIVIDUAL_GAIN,
             noise_volume: MAX_INDIVIDUAL_GAIN,
             normalization_level: 0.95,
+            crossfade_duration: None,
+            crossfade_curve: None,
+            automation: Vec::new(),
         }
     }
 
@@ -1649,6 +2069,7 @@ mod tests {
         params.sweeps = Vec::new();
 
         let track = TrackData {
+            format_version: crate::models::TRACK_FORMAT_VERSION,
             global_settings: GlobalSettings {
                 sample_rate,
                 crossfade_duration: 0.0,
@@ -1667,6 +2088,7 @@ mod tests {
                 fade_out: 0.0,
                 amp_envelope: vec![[0.0, 1.0], [0.6, 0.0]],
             }),
+            repeats: Vec::new(),
         };
 
         let mut scheduler = super::TrackScheduler::new(track, sample_rate);
@@ -1688,4 +2110,530 @@ mod tests {
         assert!(head_energy > 0.0);
         assert!(tail_energy < 1e-5);
     }
+
+    fn timeline_track(starts: &[(f64, f64)]) -> TrackData {
+        let steps: Vec<serde_json::Value> = starts
+            .iter()
+            .map(|&(start, duration)| {
+                serde_json::json!({
+                    "start": start,
+                    "duration": duration,
+                    "voices": [{
+                        "synth_function_name": "binaural_beat",
+                        "params": {"baseFreq": 10.0, "beatFreq": 2.0}
+                    }]
+                })
+            })
+            .collect();
+        serde_json::from_value(serde_json::json!({
+            "global_settings": {"sample_rate": 100, "crossfade_duration": 0.0},
+            "steps": steps
+        }))
+        .expect("valid track data")
+    }
+
+    fn render_seconds(scheduler: &mut super::TrackScheduler, seconds: usize) -> Vec<f32> {
+        let mut out = Vec::new();
+        for _ in 0..seconds * 10 {
+            let mut block = vec![0.0f32; 10 * 2];
+            scheduler.process_block(&mut block);
+            out.extend_from_slice(&block);
+        }
+        out
+    }
+
+    #[test]
+    fn chord_partials_keep_their_phases_across_steps() {
+        let step: StepData = serde_json::from_value(serde_json::json!({
+            "duration": 1.0,
+            "voices": [
+                {
+                    "synth_function_name": "binaural_chord",
+                    "params": {"harmonicCount": 3, "baseFreq": 110.0}
+                },
+                {"synth_function_name": "binaural_beat", "params": {}}
+            ]
+        }))
+        .expect("valid step data");
+        let mut voices = crate::voices::voices_for_step(&step, 1000.0);
+        let mut block = vec![0.0f32; 2 * 37];
+        for voice in &mut voices {
+            voice.process(&mut block);
+        }
+        let phases = super::TrackScheduler::extract_phases_from_voices(&voices);
+        assert_eq!(phases.len(), 4, "one pair per partial and per carrier");
+        assert!(phases[0] != phases[1] && phases[1] != phases[2]);
+
+        let mut next = crate::voices::voices_for_step(&step, 1000.0);
+        super::TrackScheduler::apply_phases_to_voices(&phases, &mut next);
+        assert_eq!(
+            super::TrackScheduler::extract_phases_from_voices(&next),
+            phases
+        );
+    }
+
+    #[test]
+    fn masked_subliminals_sit_under_the_noise_bed() {
+        let path =
+            std::env::temp_dir().join(format!("masked_subliminal_test_{}.wav", std::process::id()));
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..8000 {
+            let x = (2.0 * std::f32::consts::PI * 440.0 * i as f32 / 8000.0).sin();
+            writer
+                .write_sample((x * 0.5 * i16::MAX as f32) as i16)
+                .unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let step = serde_json::json!({
+            "duration": 2.0,
+            "voices": [{
+                "synth_function_name": "subliminal_encode",
+                "params": {
+                    "mode": "masked",
+                    "layout": "stack",
+                    "snrDb": -10.0,
+                    "audio_path": path.to_string_lossy()
+                }
+            }]
+        });
+        let data: StepData = serde_json::from_value(step.clone()).expect("valid step data");
+        let mut voices = crate::voices::voices_for_step(&data, 8000.0);
+        assert!(voices[0].kind.is_masked());
+        voices[0].kind.follow_noise_bed(0.2);
+        let mut block = vec![0.0f32; 2 * 16_000];
+        voices[0].process(&mut block);
+        // Settled onto the bed by the second half.
+        let tail = &block[24_000..];
+        let rms = (tail.iter().map(|v| v * v).sum::<f32>() / tail.len() as f32).sqrt();
+        let expected = 0.2 * 10f32.powf(-10.0 / 20.0);
+        assert!((rms / expected - 1.0).abs() < 0.02, "{rms} vs {expected}");
+
+        // With no noise voice in the step there is nothing to hide under.
+        let track: TrackData = serde_json::from_value(serde_json::json!({
+            "global_settings": {"sample_rate": 8000, "crossfade_duration": 0.0},
+            "steps": [step]
+        }))
+        .expect("valid track data");
+        let mut scheduler = super::TrackScheduler::new(track.clone(), 8000);
+        let out = render_seconds(&mut scheduler, 1);
+        assert!(out.iter().all(|v| *v == 0.0));
+
+        // The background noise is a bed too: the subliminal is the difference
+        // from the same track without it.
+        let render = |track: TrackData| {
+            let mut scheduler = super::TrackScheduler::new(track, 8000);
+            let mut out = Vec::new();
+            for _ in 0..16_000 / 160 {
+                let mut block = vec![0.0f32; 160 * 2];
+                scheduler.process_block(&mut block);
+                out.extend_from_slice(&block);
+            }
+            out
+        };
+        let mut masked = track;
+        masked.background_noise = Some(BackgroundNoiseData {
+            file_path: String::new(),
+            amp: 0.5,
+            params: Some(NoiseParams {
+                duration_seconds: 2.0,
+                ..Default::default()
+            }),
+            start_time: 0.0,
+            fade_in: 0.0,
+            fade_out: 0.0,
+            amp_envelope: Vec::new(),
+        });
+        let mut bed_only = masked.clone();
+        bed_only.steps[0].voices.clear();
+        let (masked, bed_only) = (render(masked), render(bed_only));
+        std::fs::remove_file(&path).ok();
+        let rms = |samples: &[f32]| {
+            (samples.iter().map(|v| v * v).sum::<f32>() / samples.len() as f32).sqrt()
+        };
+        let subliminal: Vec<f32> = masked[24_000..]
+            .iter()
+            .zip(&bed_only[24_000..])
+            .map(|(a, b)| a - b)
+            .collect();
+        let bed = rms(&bed_only[24_000..]);
+        assert!(bed > 0.0);
+        let ratio = rms(&subliminal) / (bed * 10f32.powf(-10.0 / 20.0));
+        assert!((ratio - 1.0).abs() < 0.1, "{ratio}");
+    }
+
+    #[test]
+    fn step_and_voice_lanes_follow_their_breakpoints() {
+        // Silent for the first second of each step, then audible: the first
+        // step through its binaural_volume lane, the second through lanes on
+        // the voice's amplitudes.
+        let gate = |value: f64| {
+            serde_json::json!([
+                {"time": 0.0, "value": 0.0, "curve": "hold"},
+                {"time": 1.0, "value": value}
+            ])
+        };
+        let track: TrackData = serde_json::from_value(serde_json::json!({
+            "global_settings": {"sample_rate": 100, "crossfade_duration": 0.0},
+            "steps": [
+                {
+                    "duration": 2.0,
+                    "voices": [{
+                        "synth_function_name": "binaural_beat",
+                        "params": {"baseFreq": 10.0, "beatFreq": 2.0}
+                    }],
+                    "automation": [{"target": "binaural_volume", "points": gate(1.0)}]
+                },
+                {
+                    "duration": 2.0,
+                    "voices": [{
+                        "synth_function_name": "binaural_beat",
+                        "params": {"baseFreq": 10.0, "beatFreq": 2.0},
+                        "automation": [
+                            {"target": "ampL", "points": gate(0.5)},
+                            {"target": "ampR", "points": gate(0.5)}
+                        ]
+                    }]
+                }
+            ]
+        }))
+        .expect("valid track data");
+        let mut scheduler = super::TrackScheduler::new(track, 100);
+        let out = render_seconds(&mut scheduler, 4);
+        for step in 0..2 {
+            let start = step * 400;
+            let held = &out[start..start + 200];
+            let open = &out[start + 200..start + 400];
+            assert!(held.iter().all(|s| *s == 0.0), "step {step} leaks");
+            assert!(
+                open.iter().any(|s| s.abs() > 0.1),
+                "step {step} stays silent"
+            );
+        }
+    }
+
+    #[test]
+    fn explicit_starts_leave_gaps_silent() {
+        let track = timeline_track(&[(0.0, 1.0), (2.0, 1.0)]);
+        assert_eq!(track.layout(100).runs().len(), 2);
+        assert_eq!(track.total_samples(100), 300);
+
+        let mut scheduler = super::TrackScheduler::new(track, 100);
+        let first = render_seconds(&mut scheduler, 1);
+        assert!(first.iter().any(|v| v.abs() > 1e-6));
+        assert_eq!(scheduler.current_step_index(), 1);
+
+        let gap = render_seconds(&mut scheduler, 1);
+        assert!(gap.iter().all(|v| v.abs() < 1e-6));
+        assert_eq!(scheduler.active_steps().count(), 0);
+
+        let second = render_seconds(&mut scheduler, 1);
+        assert!(second.iter().any(|v| v.abs() > 1e-6));
+        assert_eq!(scheduler.current_step_index(), 2);
+    }
+
+    #[test]
+    fn overlapping_steps_mix_and_survive_seeking() {
+        let track = timeline_track(&[(0.0, 2.0), (1.0, 2.0)]);
+        let mut scheduler = super::TrackScheduler::new(track.clone(), 100);
+        render_seconds(&mut scheduler, 1);
+        let mut block = vec![0.0f32; 10 * 2];
+        scheduler.process_block(&mut block);
+        assert_eq!(scheduler.active_steps().collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(scheduler.current_step_index(), 1);
+
+        let mut seeked = super::TrackScheduler::new_with_start(track, 100, 1.5, None, None);
+        assert_eq!(seeked.current_step_index(), 1);
+        seeked.process_block(&mut block);
+        assert_eq!(seeked.active_steps().collect::<Vec<_>>(), vec![0, 1]);
+        render_seconds(&mut seeked, 1);
+        assert_eq!(seeked.active_steps().collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn seeking_picks_up_volume_envelopes_mid_step() {
+        let track: TrackData = serde_json::from_value(serde_json::json!({
+            "global_settings": {"sample_rate": 100, "crossfade_duration": 0.0},
+            "steps": [{
+                "duration": 8.0,
+                "voices": [{
+                    "synth_function_name": "binaural_beat",
+                    "params": {"baseFreq": 10.0, "beatFreq": 0.0},
+                    "volume_envelope": {
+                        "type": "linear_fade",
+                        "params": {"fade_duration": 8.0, "start_amp": 0.0, "end_amp": 1.0}
+                    }
+                }]
+            }]
+        }))
+        .expect("valid track data");
+        let peak = |samples: &[f32]| samples.iter().fold(0.0f32, |m, v| m.max(v.abs()));
+        let mut scheduler = super::TrackScheduler::new(track.clone(), 100);
+        let from_start = render_seconds(&mut scheduler, 5);
+        let mut seeked = super::TrackScheduler::new_with_start(track, 100, 4.0, None, None);
+        let from_seek = render_seconds(&mut seeked, 1);
+        // Halfway through the fade-in (and past the startup fade), not back
+        // at its start.
+        let expected = peak(&from_start[800..840]);
+        assert!(peak(&from_start[..40]) < 0.2 * expected);
+        assert!((peak(&from_seek[..40]) / expected - 1.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn seeking_picks_up_transition_sweeps_mid_step() {
+        let track: TrackData = serde_json::from_value(serde_json::json!({
+            "global_settings": {"sample_rate": 100, "crossfade_duration": 0.0},
+            "steps": [{
+                "duration": 8.0,
+                "voices": [{
+                    "synth_function_name": "binaural_beat_transition",
+                    "params": {
+                        "startBaseFreq": 10.0, "endBaseFreq": 10.0,
+                        "startBeatFreq": 0.0, "endBeatFreq": 0.0,
+                        "startAmpL": 0.0, "endAmpL": 1.0,
+                        "startAmpR": 0.0, "endAmpR": 1.0
+                    }
+                }]
+            }]
+        }))
+        .expect("valid track data");
+        let peak = |samples: &[f32]| samples.iter().fold(0.0f32, |m, v| m.max(v.abs()));
+        let mut scheduler = super::TrackScheduler::new(track.clone(), 100);
+        let from_start = render_seconds(&mut scheduler, 5);
+        let mut seeked = super::TrackScheduler::new_with_start(track, 100, 4.0, None, None);
+        let from_seek = render_seconds(&mut seeked, 1);
+        // Halfway through the sweep, not back at its start.
+        let expected = peak(&from_start[800..840]);
+        assert!(peak(&from_start[..40]) < 0.2 * expected);
+        assert!((peak(&from_seek[..40]) / expected - 1.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn repeat_blocks_resolve_passes_on_demand_and_report_iterations() {
+        let mut track = timeline_track(&[(0.0, 1.0), (1.0, 1.0), (2.0, 1.0)]);
+        for step in &mut track.steps {
+            step.start = None;
+        }
+        track.repeats = vec![serde_json::from_value(serde_json::json!({
+            "start_step": 1,
+            "step_count": 1,
+            "count": 3,
+            "param_offsets": {"beatFreq": 1.0}
+        }))
+        .unwrap()];
+        let layout = track.layout(100);
+        assert_eq!(layout.len(), 5);
+        assert_eq!(layout.total_samples(), 500);
+        let third_pass = track.occurrence_step(layout.occurrence(3));
+        assert_eq!(third_pass.voices[0].params["beatFreq"], 4.0);
+
+        // The block is stored once, so however many passes it plays costs
+        // nothing up front; the count is clamped all the same.
+        let mut long = track.clone();
+        long.repeats[0].count = u32::MAX;
+        let layout = long.layout(100);
+        let passes = MAX_REPEAT_COUNT as usize;
+        assert_eq!(layout.len(), passes + 2);
+        let last_pass = layout.occurrence(passes);
+        assert_eq!(
+            (last_pass.step, last_pass.iteration),
+            (1, MAX_REPEAT_COUNT - 1)
+        );
+        assert_eq!(layout.span(passes), (100 * passes, 100));
+        assert_eq!(layout.span(passes + 1), (100 * passes + 100, 100));
+        assert_eq!(
+            layout.position_at(&layout.runs()[0], 500_050),
+            Some((5000, 50))
+        );
+
+        let mut scheduler = super::TrackScheduler::new(track.clone(), 100);
+        render_seconds(&mut scheduler, 2);
+        assert_eq!(scheduler.current_step_index(), 1);
+        let repeat = scheduler.current_repeat().expect("inside the block");
+        assert_eq!((repeat.block, repeat.iteration, repeat.count), (0, 1, 3));
+
+        let mut seeked = super::TrackScheduler::new_with_start(track, 100, 3.5, None, None);
+        assert_eq!(seeked.current_step_index(), 1);
+        assert_eq!(seeked.current_repeat().map(|r| r.iteration), Some(2));
+        render_seconds(&mut seeked, 1);
+        assert_eq!(seeked.current_step_index(), 2);
+        assert_eq!(seeked.current_repeat(), None);
+        render_seconds(&mut seeked, 1);
+        assert_eq!(seeked.current_step_index(), 3);
+    }
+
+    #[test]
+    fn repeat_offsets_shift_unset_transition_params_through_their_start_and_end_keys() {
+        let mut track: TrackData = serde_json::from_value(serde_json::json!({
+            "global_settings": {"sample_rate": 100, "crossfade_duration": 0.0},
+            "steps": [{
+                "duration": 1.0,
+                "voices": [
+                    {"synth_function_name": "isochronic_tone_transition", "params": {}},
+                    {"synth_function_name": "stereo_am_independent_transition", "params": {}}
+                ]
+            }]
+        }))
+        .expect("valid track data");
+        track.repeats = vec![serde_json::from_value(serde_json::json!({
+            "start_step": 0,
+            "step_count": 1,
+            "count": 3,
+            "param_offsets": {"beatFreq": 1.0, "carrierFreq": 10.0}
+        }))
+        .unwrap()];
+        let layout = track.layout(100);
+        let third_pass = track.occurrence_step(layout.occurrence(2));
+        // The end beat follows the start; the carrier has its own end default.
+        let isochronic = &third_pass.voices[0].params;
+        assert_eq!(isochronic["startBeatFreq"], 6.0);
+        assert!(!isochronic.contains_key("beatFreq"));
+        assert!(!isochronic.contains_key("endBeatFreq"));
+        let stereo_am = &third_pass.voices[1].params;
+        assert_eq!(stereo_am["startCarrierFreq"], 220.0);
+        assert_eq!(stereo_am["endCarrierFreq"], 270.0);
+
+        // The voice reads the shifted start, so the offset reaches its output.
+        let render = |step: &StepData| {
+            let mut voice = crate::voices::voices_for_step(step, 1000.0).remove(0);
+            let mut out = vec![0.0f32; 2 * 1000];
+            voice.process(&mut out);
+            out
+        };
+        assert_ne!(render(&third_pass), render(&track.steps[0]));
+    }
+
+    #[test]
+    fn chained_steps_keep_to_the_layout_across_block_boundaries() {
+        let mut track = timeline_track(&[(0.0, 0.25), (0.0, 0.37), (0.0, 0.18), (0.0, 0.5)]);
+        for step in &mut track.steps {
+            step.start = None;
+        }
+        let layout = track.layout(100);
+        let run = layout.runs()[0];
+        let mut scheduler = super::TrackScheduler::new(track, 100);
+        let mut block = vec![0.0f32; 10 * 2];
+        for _ in 0..12 {
+            scheduler.process_block(&mut block);
+            let sample = scheduler.elapsed_samples() as usize;
+            let Some(chain) = scheduler.chains.first() else {
+                break;
+            };
+            assert_eq!(
+                layout.position_at(&run, sample),
+                Some((chain.position, chain.sample)),
+                "at sample {sample}"
+            );
+            assert_eq!(layout.span(chain.position).0 + chain.sample, sample);
+        }
+    }
+
+    #[test]
+    fn back_to_back_starts_keep_sequential_playback() {
+        let track = timeline_track(&[(0.0, 1.0), (1.0, 1.0)]);
+        assert_eq!(track.layout(100).runs().len(), 1);
+    }
+
+    #[test]
+    fn pinned_steps_leave_their_neighbours_crossfading() {
+        let step = |start: Option<f64>, beat: f64| {
+            let mut step = serde_json::json!({
+                "duration": 1.0,
+                "voices": [{
+                    "synth_function_name": "binaural_beat",
+                    "params": {"baseFreq": 10.0, "beatFreq": beat}
+                }]
+            });
+            if let Some(start) = start {
+                step["start"] = start.into();
+            }
+            step
+        };
+        let track: TrackData = serde_json::from_value(serde_json::json!({
+            "global_settings": {"sample_rate": 100, "crossfade_duration": 0.5},
+            "steps": [
+                step(Some(0.0), 1.0),
+                step(None, 2.0),
+                step(Some(3.0), 3.0),
+                step(Some(4.0), 4.0)
+            ]
+        }))
+        .expect("valid track data");
+        let runs: Vec<_> = track
+            .layout(100)
+            .runs()
+            .iter()
+            .map(|run| (run.first, run.end, run.start))
+            .collect();
+        assert_eq!(runs, vec![(0, 2, 0), (2, 4, 300)]);
+
+        let mut scheduler = super::TrackScheduler::new(track, 100);
+        let mut out = Vec::new();
+        let mut fades = Vec::new();
+        for _ in 0..50 {
+            let mut block = vec![0.0f32; 10 * 2];
+            scheduler.process_block(&mut block);
+            out.extend_from_slice(&block);
+            fades.extend(
+                scheduler
+                    .chains
+                    .iter()
+                    .filter(|chain| chain.crossfade_active)
+                    .map(|chain| chain.position),
+            );
+        }
+        fades.dedup();
+        assert_eq!(fades, vec![0, 2], "each run crossfades between its steps");
+        // The first run ends half a second early, having crossfaded; the
+        // pinned run waits for its start.
+        assert!(out[2 * 150..2 * 300].iter().all(|v| *v == 0.0));
+        assert!(out[2 * 300..2 * 310].iter().any(|v| *v != 0.0));
+    }
+
+    #[test]
+    fn step_crossfade_overrides_global_settings() {
+        let step = |beat: f64, fade: Option<f64>| {
+            let mut step = serde_json::json!({
+                "duration": 1.0,
+                "voices": [{
+                    "synth_function_name": "binaural_beat",
+                    "params": {"baseFreq": 10.0, "beatFreq": beat}
+                }]
+            });
+            if let Some(fade) = fade {
+                step["crossfadeDuration"] = fade.into();
+                step["crossfadeCurve"] = "equal_power".into();
+            }
+            step
+        };
+        let track: TrackData = serde_json::from_value(serde_json::json!({
+            "global_settings": {"sample_rate": 100, "crossfade_duration": 0.5},
+            "steps": [step(2.0, None), step(3.0, Some(0.0)), step(4.0, Some(0.2))]
+        }))
+        .expect("valid track data");
+
+        let mut scheduler = super::TrackScheduler::new(track, 100);
+        let mut block = vec![0.0f32; 10 * 2];
+        let mut fades = Vec::new();
+        for _ in 0..30 {
+            scheduler.process_block(&mut block);
+            if let Some(chain) = scheduler.chains.first().filter(|c| c.crossfade_active) {
+                fades.push((
+                    chain.position,
+                    chain.crossfade_samples,
+                    chain.crossfade_curve,
+                ));
+            }
+        }
+        // Step 1 is a hard cut; step 2 fades in over its own 0.2 s.
+        assert!(fades.iter().all(|&(step, _, _)| step == 1));
+        assert_eq!(fades.first().map(|f| f.1), Some(20));
+        assert!(matches!(fades[0].2, CrossfadeCurve::EqualPower));
+    }
 }
@@ -204,6 +204,20 @@ fn biquad_time_varying_block(
     }
 }
 
+/// Apply a fixed-coefficient biquad cascade to an f32 block, filtering in f64.
+fn biquad_cascade_block(block: &mut [f32], coeffs: &Coeffs, stages: &mut [BiquadState64]) {
+    for sample in block.iter_mut() {
+        let mut x = *sample as f64;
+        for st in stages.iter_mut() {
+            let out = x * coeffs.b0 + st.z1;
+            st.z1 = x * coeffs.b1 - out * coeffs.a1 + st.z2;
+            st.z2 = x * coeffs.b2 - out * coeffs.a2;
+            x = out;
+        }
+        *sample = x as f32;
+    }
+}
+
 // --- FFT Based Noise Generator (Matches Python's ColoredNoiseGenerator) ---
 
 struct NoiseGenRequest {
@@ -568,7 +582,10 @@ impl FftNoiseGenerator {
     }
 
     fn crossfade_len(&self) -> usize {
-        self.buffer.len().min(CROSSFADE_SAMPLES)
+        // Never crossfade over more than half of a buffer: the swap skips the
+        // samples already consumed during the fade, so a full-length fade on a
+        // very short buffer would leave the cursor past the end.
+        (self.buffer.len() / 2).min(CROSSFADE_SAMPLES)
     }
 
     fn next(&mut self) -> f32 {
@@ -764,6 +781,81 @@ impl FftNoiseGenerator {
 
 // --- Precomputed Hann window (matching np.hanning) ---
 
+// --- Decoded audio file as the base signal ---
+
+/// Length of the tail-into-head crossfade that makes an input file loop seamlessly.
+const LOOP_CROSSFADE_SECONDS: f32 = 0.5;
+
+/// Decode `input_audio_path` (mono, at `sample_rate`) and fold its tail into
+/// its head with a linear crossfade so the result can be looped without a
+/// seam. The tail and head of a recording are usually correlated, and equal
+/// gains keep such material at its level where equal power would lift it by
+/// up to 3 dB. Returns `None` when no path is set or the file can't be decoded.
+fn load_input_audio(params: &NoiseParams, sample_rate: u32) -> Option<Arc<Vec<f32>>> {
+    if params.input_audio_path.is_empty() {
+        return None;
+    }
+    let (mut data, src_rate) = match crate::voices::load_audio_file(&params.input_audio_path) {
+        Ok(decoded) => decoded,
+        Err(e) => {
+            log::warn!(
+                "Could not load noise input audio {}: {e}; using generated noise",
+                params.input_audio_path
+            );
+            return None;
+        }
+    };
+    if src_rate != sample_rate {
+        data = crate::voices::resample_linear(&data, src_rate, sample_rate);
+    }
+    if data.is_empty() {
+        return None;
+    }
+
+    let fade = ((LOOP_CROSSFADE_SECONDS * sample_rate as f32) as usize).min(data.len() / 4);
+    if fade > 0 {
+        let loop_len = data.len() - fade;
+        for i in 0..fade {
+            let x = (i as f32 + 0.5) / fade as f32;
+            data[i] = data[i] * x + data[loop_len + i] * (1.0 - x);
+        }
+        data.truncate(loop_len);
+    }
+    Some(Arc::new(data))
+}
+
+/// Endless playback of a decoded (pre-crossfaded) input file.
+struct AudioLoop {
+    samples: Arc<Vec<f32>>,
+    pos: usize,
+}
+
+impl AudioLoop {
+    fn next(&mut self) -> f32 {
+        let sample = self.samples[self.pos];
+        self.pos += 1;
+        if self.pos >= self.samples.len() {
+            self.pos = 0;
+        }
+        sample
+    }
+}
+
+/// Signal fed into the swept-notch chain.
+enum NoiseSource {
+    Generated(Box<FftNoiseGenerator>),
+    Audio(AudioLoop),
+}
+
+impl NoiseSource {
+    fn next(&mut self) -> f32 {
+        match self {
+            NoiseSource::Generated(gen) => gen.next(),
+            NoiseSource::Audio(audio) => audio.next(),
+        }
+    }
+}
+
 fn hann_window(size: usize) -> Vec<f32> {
     // np.hanning(N) = 0.5 - 0.5 * cos(2*pi*n/(N-1)), n = 0..N-1
     (0..size)
@@ -901,6 +993,28 @@ impl SweepRuntime {
     }
 }
 
+/// A fixed notch with its own per-channel cascade state.
+struct StaticNotchRuntime {
+    coeffs: Coeffs,
+    l: Vec<BiquadState64>,
+    r: Vec<BiquadState64>,
+}
+
+impl StaticNotchRuntime {
+    fn build(params: &NoiseParams, sample_rate: f32) -> Vec<Self> {
+        params
+            .parsed_static_notches()
+            .into_iter()
+            .filter(|n| n.freq < sample_rate * 0.49)
+            .map(|n| Self {
+                coeffs: notch_coeffs_f64(n.freq as f64, n.q as f64, sample_rate as f64),
+                l: vec![BiquadState64::new(); n.casc],
+                r: vec![BiquadState64::new(); n.casc],
+            })
+            .collect()
+    }
+}
+
 impl SweepParams {
     fn interpolate_at(&self, t: f32) -> (f32, f32, f32, usize) {
         let t = t.clamp(0.0, 1.0);
@@ -934,11 +1048,14 @@ pub struct StreamingNoise {
     // Persistent biquad states per sweep (per channel + per pass + per cascade stage)
     sweep_runtime: Vec<SweepRuntime>,
 
+    // Fixed-frequency notches applied after the sweeps
+    static_notches: Vec<StaticNotchRuntime>,
+
     // Mode flags
     transition: bool,
 
-    // FFT Generator for all noise modes
-    fft_gen: FftNoiseGenerator,
+    // Base signal: FFT generated noise, or a looping input file
+    source: NoiseSource,
 
     // OLA state for Python-compat mode
     ola: OlaState,
@@ -996,6 +1113,15 @@ impl StreamingNoise {
     }
 
     pub fn new(params: &NoiseParams, sample_rate: u32) -> Self {
+        let input_audio = load_input_audio(params, sample_rate);
+        Self::with_input_audio(params, sample_rate, input_audio)
+    }
+
+    fn with_input_audio(
+        params: &NoiseParams,
+        sample_rate: u32,
+        input_audio: Option<Arc<Vec<f32>>>,
+    ) -> Self {
         let sample_rate_f = sample_rate as f32;
         let duration_samples = (params.duration_seconds * sample_rate_f) as usize;
 
@@ -1039,8 +1165,14 @@ impl StreamingNoise {
             initial_offset: params.initial_offset,
             sweep_params,
             sweep_runtime,
+            static_notches: StaticNotchRuntime::build(params, sample_rate_f),
             transition: params.transition,
-            fft_gen: FftNoiseGenerator::new(params, sample_rate_f),
+            source: match input_audio {
+                Some(samples) => NoiseSource::Audio(AudioLoop { samples, pos: 0 }),
+                None => {
+                    NoiseSource::Generated(Box::new(FftNoiseGenerator::new(params, sample_rate_f)))
+                }
+            },
             ola: OlaState::new(),
             total_samples_output: 0,
         };
@@ -1052,11 +1184,13 @@ impl StreamingNoise {
         // *before* we start outputting real audio. This prevents a "quiet start"
         // or fade-in artifact.
         if params.sweeps.is_empty() {
-            // Run exactly one window's worth of samples to trigger the first calc
-            // RENORM_WINDOW is currently 8192
-            for _ in 0..RENORM_WINDOW {
-                // discard output, just warming up state
-                gen.fft_gen.next();
+            if let NoiseSource::Generated(fft_gen) = &mut gen.source {
+                // Run exactly one window's worth of samples to trigger the first calc
+                // RENORM_WINDOW is currently 8192
+                for _ in 0..RENORM_WINDOW {
+                    // discard output, just warming up state
+                    fft_gen.next();
+                }
             }
             // Reset state that shouldn't persist (optional, but good practice)
             // Actually, we WANT to keep the renorm_gain, so we don't reset that.
@@ -1117,7 +1251,10 @@ impl StreamingNoise {
     ) -> (Self, f32) {
         let frames = calibration_frames.max(1);
 
-        let mut calibration_gen = StreamingNoise::new(params, sample_rate);
+        // Decode the input file once and share it between both instances.
+        let input_audio = load_input_audio(params, sample_rate);
+        let mut calibration_gen =
+            StreamingNoise::with_input_audio(params, sample_rate, input_audio.clone());
         let mut scratch = vec![0.0f32; frames * 2];
         calibration_gen.generate(&mut scratch);
 
@@ -1131,7 +1268,7 @@ impl StreamingNoise {
         let idx = idx.min(abs_vals.len().saturating_sub(1));
         let peak = abs_vals.get(idx).copied().unwrap_or(0.0).max(1e-9);
 
-        let generator = StreamingNoise::new(params, sample_rate);
+        let generator = StreamingNoise::with_input_audio(params, sample_rate, input_audio);
 
         (generator, peak)
     }
@@ -1141,8 +1278,15 @@ impl StreamingNoise {
         self.generate(&mut scratch);
     }
 
+    /// Move the sweep clock `n` samples on without generating them. The noise
+    /// itself is random, so only the sweep position needs to follow a seek.
+    pub fn skip_clock(&mut self, n: usize) {
+        self.ola.absolute_block_start += n;
+        self.total_samples_output += n;
+    }
+
     fn next_base(&mut self) -> f32 {
-        self.fft_gen.next()
+        self.source.next()
     }
 
     /// Compute the transition fraction at a given absolute sample index
@@ -1304,6 +1448,11 @@ impl StreamingNoise {
             }
         }
 
+        for notch in self.static_notches.iter_mut() {
+            biquad_cascade_block(&mut self.ola.block_l, &notch.coeffs, &mut notch.l);
+            biquad_cascade_block(&mut self.ola.block_r, &notch.coeffs, &mut notch.r);
+        }
+
         // RMS compensation: restore original loudness after notch filtering
         // This matches Python's behavior where it computes rms_in before filtering
         // and then scales output by (rms_in / rms_out) to restore loudness.
@@ -1457,3 +1606,149 @@ impl StreamingNoise {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::StreamingNoise;
+    use crate::noise_params::{load_noise_params_from_str, StaticNotch, MAX_STATIC_NOTCH_CASCADE};
+    use serde_json::json;
+
+    /// Power of `freq` in the left channel via a Goertzel filter.
+    fn tone_power(samples: &[f32], freq: f32, sample_rate: f32) -> f32 {
+        let coeff = 2.0 * (2.0 * std::f32::consts::PI * freq / sample_rate).cos();
+        let (mut s1, mut s2) = (0.0f32, 0.0f32);
+        for frame in samples.chunks_exact(2) {
+            let s0 = frame[0] + coeff * s1 - s2;
+            s2 = s1;
+            s1 = s0;
+        }
+        s1 * s1 + s2 * s2 - coeff * s1 * s2
+    }
+
+    #[test]
+    fn short_noise_buffers_swap_without_overrunning() {
+        // 0.02 s gives buffers shorter than the swap crossfade, which used to
+        // leave the cursor past the end of the new buffer.
+        let params = load_noise_params_from_str(
+            r#"{"duration_seconds": 0.02, "sample_rate": 48000, "seed": 3,
+                "noise_parameters": {"name": "pink"}}"#,
+        )
+        .unwrap();
+        let mut out = vec![0.0f32; 4800 * 2];
+        StreamingNoise::new(&params, 48_000).generate(&mut out);
+        assert!(out.iter().all(|s| s.is_finite()));
+        assert!(out[out.len() / 2..].iter().any(|&s| s != 0.0));
+    }
+
+    #[test]
+    fn static_notch_removes_its_frequency() {
+        let base = r#"{"duration_seconds": 2.0, "sample_rate": 48000, "seed": 7,
+            "noise_parameters": {"name": "white"}"#;
+        let plain = load_noise_params_from_str(&format!("{base}}}")).unwrap();
+        let notched = load_noise_params_from_str(&format!(
+            r#"{base}, "static_notches": [{{"freq": 3000.0, "q": 4.0, "casc": 4}}]}}"#
+        ))
+        .unwrap();
+        assert_eq!(notched.parsed_static_notches().len(), 1);
+
+        let mut a = vec![0.0f32; 48_000 * 2];
+        let mut b = vec![0.0f32; 48_000 * 2];
+        StreamingNoise::new(&plain, 48_000).generate(&mut a);
+        StreamingNoise::new(&notched, 48_000).generate(&mut b);
+
+        // Skip the first blocks while the filter settles.
+        let start = 8192 * 2;
+        let p_plain = tone_power(&a[start..], 3000.0, 48_000.0);
+        let p_notched = tone_power(&b[start..], 3000.0, 48_000.0);
+        assert!(p_notched < p_plain * 0.01, "{p_notched} vs {p_plain}");
+    }
+
+    #[test]
+    fn static_notch_cascade_is_capped() {
+        let casc = |value: serde_json::Value| StaticNotch::from_value(&value).map(|n| n.casc);
+        assert_eq!(
+            casc(json!([3000.0, 4.0, 1e12])),
+            Some(MAX_STATIC_NOTCH_CASCADE)
+        );
+        assert_eq!(casc(json!({"freq": 3000.0, "casc": 0})), Some(10));
+        assert_eq!(casc(json!({"freq": 3000.0, "casc": 4})), Some(4));
+    }
+
+    #[test]
+    fn input_audio_loops_without_a_seam() {
+        let sample_rate = 48_000u32;
+        let path = std::env::temp_dir().join(format!(
+            "streaming_noise_loop_test_{}.wav",
+            std::process::id()
+        ));
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        // 0.73 s of 440 Hz: not a whole number of cycles, so a naive loop would click.
+        for i in 0..35_040 {
+            let x = (2.0 * std::f32::consts::PI * 440.0 * i as f32 / sample_rate as f32).sin();
+            writer
+                .write_sample((x * 0.5 * i16::MAX as f32) as i16)
+                .unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let params = load_noise_params_from_str(&format!(
+            r#"{{"duration_seconds": 3.0, "sample_rate": 48000, "input_audio_path": {:?}}}"#,
+            path.to_string_lossy()
+        ))
+        .unwrap();
+        let mut out = vec![0.0f32; 48_000 * 3 * 2];
+        StreamingNoise::new(&params, sample_rate).generate(&mut out);
+        std::fs::remove_file(&path).ok();
+
+        // Fading between two phases of the tone adds a little slope.
+        let max_step = 2.0 * std::f32::consts::PI * 440.0 / sample_rate as f32 * 0.5 * 1.1;
+        let left: Vec<f32> = out.chunks_exact(2).map(|f| f[0]).collect();
+        assert!(left.iter().any(|x| x.abs() > 0.3));
+        // The first frame starts mid-waveform (the folded-in tail), so only
+        // check continuity from there on.
+        for w in left[1..].windows(2) {
+            assert!((w[1] - w[0]).abs() < max_step, "discontinuity in loop");
+        }
+    }
+
+    #[test]
+    fn input_audio_keeps_its_level_across_the_loop_seam() {
+        let sample_rate = 8_000u32;
+        let path = std::env::temp_dir().join(format!(
+            "streaming_noise_level_test_{}.wav",
+            std::process::id()
+        ));
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        let level = i16::MAX / 2;
+        for _ in 0..sample_rate * 2 {
+            writer.write_sample(level).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let params = load_noise_params_from_str(&format!(
+            r#"{{"duration_seconds": 1.0, "input_audio_path": {:?}}}"#,
+            path.to_string_lossy()
+        ))
+        .unwrap();
+        let looped = super::load_input_audio(&params, sample_rate).expect("decoded input");
+        std::fs::remove_file(&path).ok();
+
+        let expected = level as f32 / i16::MAX as f32;
+        assert!(looped.len() < sample_rate as usize * 2, "tail was folded in");
+        for (i, x) in looped.iter().enumerate() {
+            assert!((x - expected).abs() < 1e-3, "sample {i}: {x} vs {expected}");
+        }
+    }
+}
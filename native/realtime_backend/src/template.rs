@@ -0,0 +1,575 @@
+//! Parametric track templates.
+//!
+//! A template is ordinary track JSON with a top-level `vars` object. Any
+//! string in the document may refer to a variable as `${name}`.
+//!
+//! In numeric positions (voice and envelope params, step durations, starts
+//! and volumes, the numeric global settings, clip and background noise
+//! timing and levels, repeat counts and offsets):
+//!
+//! * a string that is exactly one reference, e.g. `"${carrier}"`, is replaced
+//!   by the variable's value, evaluated if it is itself an expression;
+//! * any other string containing a reference is evaluated as an arithmetic
+//!   expression, e.g. `"${carrier}*1.5"` or `"max(${length}/4, 30)"`, and
+//!   replaced by the resulting number;
+//! * a string that is not a valid expression, such as a file name
+//!   `"${dir}/rain.wav"`, has its references substituted as text.
+//!
+//! Everywhere else, such as in descriptions, references are only substituted
+//! as text, so `"${hi}-${lo} Hz"` stays a string. A string that is exactly
+//! one reference to an array or object variable is replaced by its value.
+//!
+//! Variables may themselves refer to other variables. Expressions support
+//! `+ - * / % ^`, parentheses and the functions `min`, `max`, `abs`,
+//! `round`, `floor`, `ceil` and `sqrt`.
+//!
+//! [`instantiate_template`] resolves a template, with optional overrides for
+//! its variables, into a concrete [`TrackData`]. [`crate::migration::parse_track`]
+//! instantiates templates with their declared values, so a template can also
+//! be played directly.
+
+use crate::migration::migrate_track_value;
+use crate::models::TrackData;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// A template that cannot be instantiated.
+#[derive(Clone, Debug)]
+pub struct TemplateError {
+    /// JSON path of the offending value, e.g. `$.steps[1].duration`.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+impl Error for TemplateError {}
+
+/// Whether a track document declares template variables.
+pub fn is_template(root: &Value) -> bool {
+    root.get("vars").is_some_and(Value::is_object)
+}
+
+/// Instantiate a template and parse the result. `overrides` replaces the
+/// values of declared variables; overriding an undeclared variable is an
+/// error so misspelled names do not go unnoticed.
+pub fn instantiate_template(
+    template_json: &str,
+    overrides: &Map<String, Value>,
+) -> Result<TrackData, Box<dyn Error>> {
+    let value: Value = serde_json::from_str(template_json)?;
+    let value = instantiate_value(migrate_track_value(value)?, overrides)?;
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Substitute every variable reference in a template document and drop its
+/// `vars`. Documents without `vars` are returned unchanged when `overrides`
+/// is empty.
+pub fn instantiate_value(
+    mut root: Value,
+    overrides: &Map<String, Value>,
+) -> Result<Value, TemplateError> {
+    let obj = root.as_object_mut().ok_or_else(|| TemplateError {
+        path: "$".into(),
+        message: "Track document must be a JSON object".into(),
+    })?;
+    let mut declared = match obj.remove("vars") {
+        None => Map::new(),
+        Some(Value::Object(vars)) => vars,
+        Some(_) => {
+            return Err(TemplateError {
+                path: "$.vars".into(),
+                message: "Template vars must be an object".into(),
+            })
+        }
+    };
+    for (name, value) in overrides {
+        if !declared.contains_key(name) {
+            return Err(TemplateError {
+                path: "$.vars".into(),
+                message: format!("Cannot override undeclared variable '{name}'"),
+            });
+        }
+        declared.insert(name.clone(), value.clone());
+    }
+
+    let mut vars = Vars {
+        declared,
+        resolved: HashMap::new(),
+        numbers: HashMap::new(),
+        resolving: Vec::new(),
+    };
+    let names: Vec<String> = vars.declared.keys().cloned().collect();
+    for name in names {
+        vars.get(&name, &format!("$.vars.{name}"))?;
+    }
+    substitute(&mut root, &mut vars, "$", &[])?;
+    Ok(root)
+}
+
+/// Numeric positions, as the keys leading to them with array indices left
+/// out. `*` matches any key.
+const NUMERIC_POSITIONS: &[&[&str]] = &[
+    &["global_settings", "sample_rate"],
+    &["global_settings", "crossfade_duration"],
+    &["global_settings", "normalization_level"],
+    &["steps", "duration"],
+    &["steps", "start"],
+    &["steps", "crossfade_duration"],
+    &["steps", "binaural_volume"],
+    &["steps", "noise_volume"],
+    &["steps", "normalization_level"],
+    &["steps", "voices", "params", "*"],
+    &["steps", "voices", "volume_envelope", "params", "*"],
+    &["clips", "start"],
+    &["clips", "amp"],
+    &["background_noise", "amp"],
+    &["background_noise", "start_time"],
+    &["background_noise", "fade_in"],
+    &["background_noise", "fade_out"],
+    &["repeats", "count"],
+    &["repeats", "param_offsets", "*"],
+];
+
+fn is_numeric_position(keys: &[&str]) -> bool {
+    NUMERIC_POSITIONS.iter().any(|position| {
+        position.len() == keys.len() && position.iter().zip(keys).all(|(p, k)| *p == "*" || p == k)
+    })
+}
+
+struct Vars {
+    declared: Map<String, Value>,
+    /// Values with their references substituted as text.
+    resolved: HashMap<String, Value>,
+    /// Values as numbers, for the variables used in numeric positions.
+    numbers: HashMap<String, Option<f64>>,
+    /// Variables being resolved, to report reference cycles.
+    resolving: Vec<String>,
+}
+
+impl Vars {
+    fn declared(&self, name: &str, path: &str) -> Result<Value, TemplateError> {
+        self.declared
+            .get(name)
+            .cloned()
+            .ok_or_else(|| TemplateError {
+                path: path.into(),
+                message: format!("Unknown variable '{name}'"),
+            })
+    }
+
+    fn enter(&mut self, name: &str) -> Result<(), TemplateError> {
+        if self.resolving.iter().any(|n| n == name) {
+            return Err(TemplateError {
+                path: format!("$.vars.{name}"),
+                message: format!(
+                    "Variable '{name}' refers to itself ({} -> {name})",
+                    self.resolving.join(" -> ")
+                ),
+            });
+        }
+        self.resolving.push(name.to_string());
+        Ok(())
+    }
+
+    /// The variable's value with the references in it substituted as text.
+    fn get(&mut self, name: &str, path: &str) -> Result<Value, TemplateError> {
+        if let Some(value) = self.resolved.get(name) {
+            return Ok(value.clone());
+        }
+        let mut value = self.declared(name, path)?;
+        self.enter(name)?;
+        let result = substitute(&mut value, self, &format!("$.vars.{name}"), &[]);
+        self.resolving.pop();
+        result?;
+        self.resolved.insert(name.to_string(), value.clone());
+        Ok(value)
+    }
+
+    /// The variable's value as a number: numbers as they are and strings
+    /// evaluated as expressions. `None` when it is neither.
+    fn number(&mut self, name: &str, path: &str) -> Result<Option<f64>, TemplateError> {
+        if let Some(number) = self.numbers.get(name) {
+            return Ok(*number);
+        }
+        let value = self.declared(name, path)?;
+        self.enter(name)?;
+        let result = match &value {
+            Value::Number(n) => Ok(n.as_f64()),
+            Value::String(text) => evaluate_text(text, self, &format!("$.vars.{name}")),
+            _ => Ok(None),
+        };
+        self.resolving.pop();
+        let number = result?;
+        self.numbers.insert(name.to_string(), number);
+        Ok(number)
+    }
+}
+
+/// Substitute the references in `value`, found under `keys`.
+fn substitute(
+    value: &mut Value,
+    vars: &mut Vars,
+    path: &str,
+    keys: &[&str],
+) -> Result<(), TemplateError> {
+    match value {
+        Value::String(text) if text.contains("${") => {
+            *value = resolve_string(text, vars, path, is_numeric_position(keys))?;
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                substitute(item, vars, &format!("{path}[{i}]"), keys)?;
+            }
+        }
+        Value::Object(obj) => {
+            for (key, item) in obj.iter_mut() {
+                let keys = [keys, &[key.as_str()]].concat();
+                substitute(item, vars, &format!("{path}.{key}"), &keys)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn number_value(number: f64, text: &str, path: &str) -> Result<Value, TemplateError> {
+    serde_json::Number::from_f64(number)
+        .map(Value::Number)
+        .ok_or_else(|| TemplateError {
+            path: path.into(),
+            message: format!("'{text}' does not evaluate to a finite number"),
+        })
+}
+
+/// Evaluate `text` as an arithmetic expression over the variables it
+/// refers to. `None` when it is not an expression.
+fn evaluate_text(text: &str, vars: &mut Vars, path: &str) -> Result<Option<f64>, TemplateError> {
+    let refs = references(text).map_err(|message| TemplateError {
+        path: path.into(),
+        message,
+    })?;
+    let mut values = HashMap::new();
+    for (_, _, name) in &refs {
+        let value = match vars.number(name, path)? {
+            Some(number) => number_value(number, name, path)?,
+            None => vars.get(name, path)?,
+        };
+        values.insert(*name, value);
+    }
+    evaluate(text, &values).map_err(|message| TemplateError {
+        path: path.into(),
+        message: format!("Cannot evaluate '{text}': {message}"),
+    })
+}
+
+fn resolve_string(
+    text: &str,
+    vars: &mut Vars,
+    path: &str,
+    numeric: bool,
+) -> Result<Value, TemplateError> {
+    let refs = references(text).map_err(|message| TemplateError {
+        path: path.into(),
+        message,
+    })?;
+    let whole = match refs.as_slice() {
+        [(0, end, name)] if *end == text.len() => Some(*name),
+        _ => None,
+    };
+    if numeric {
+        let number = match whole {
+            Some(name) => vars.number(name, path)?,
+            None => evaluate_text(text, vars, path)?,
+        };
+        if let Some(number) = number {
+            return number_value(number, text, path);
+        }
+    }
+    if let Some(name) = whole {
+        let value = vars.get(name, path)?;
+        if numeric || value.is_array() || value.is_object() {
+            return Ok(value);
+        }
+    }
+
+    // Substitute the references as text.
+    let mut values = HashMap::new();
+    for (_, _, name) in &refs {
+        values.insert(*name, vars.get(name, path)?);
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for (start, end, name) in &refs {
+        out.push_str(&text[last..*start]);
+        match &values[name] {
+            Value::String(s) => out.push_str(s),
+            other => out.push_str(&other.to_string()),
+        }
+        last = *end;
+    }
+    out.push_str(&text[last..]);
+    Ok(Value::String(out))
+}
+
+/// Byte range and name of every `${name}` reference in `text`.
+fn references(text: &str) -> Result<Vec<(usize, usize, &str)>, String> {
+    let mut refs = Vec::new();
+    let mut from = 0;
+    while let Some(offset) = text[from..].find("${") {
+        let start = from + offset;
+        let close = text[start..]
+            .find('}')
+            .ok_or_else(|| format!("Unterminated variable reference in '{text}'"))?;
+        let name = text[start + 2..start + close].trim();
+        if name.is_empty() {
+            return Err(format!("Empty variable reference in '{text}'"));
+        }
+        refs.push((start, start + close + 1, name));
+        from = start + close + 1;
+    }
+    Ok(refs)
+}
+
+/// Evaluate `text` as an arithmetic expression. Returns `Ok(None)` when the
+/// text does not parse as one, and an error when it parses but cannot be
+/// evaluated (a referenced variable is not a number, division by zero, ...).
+fn evaluate(text: &str, values: &HashMap<&str, Value>) -> Result<Option<f64>, String> {
+    let mut parser = Parser {
+        src: text.as_bytes(),
+        pos: 0,
+        values,
+        error: None,
+    };
+    let result = parser.expr();
+    parser.skip_ws();
+    match (result, parser.error) {
+        (Some(_), Some(error)) if parser.pos == parser.src.len() => Err(error),
+        (Some(value), None) if parser.pos == parser.src.len() => Ok(Some(value)),
+        _ => Ok(None),
+    }
+}
+
+/// Recursive descent parser that evaluates as it goes. Syntax errors yield
+/// `None`; evaluation errors are recorded in `error`.
+struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+    values: &'a HashMap<&'a str, Value>,
+    error: Option<String>,
+}
+
+impl Parser<'_> {
+    fn skip_ws(&mut self) {
+        while self.src.get(self.pos).is_some_and(u8::is_ascii_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        self.skip_ws();
+        if self.src.get(self.pos) == Some(&c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expr(&mut self) -> Option<f64> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat(b'+') {
+                value += self.term()?;
+            } else if self.eat(b'-') {
+                value -= self.term()?;
+            } else {
+                return Some(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Option<f64> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat(b'*') {
+                value *= self.unary()?;
+            } else if self.eat(b'/') {
+                let divisor = self.unary()?;
+                if divisor == 0.0 {
+                    self.error.get_or_insert_with(|| "division by zero".into());
+                }
+                value /= divisor;
+            } else if self.eat(b'%') {
+                value %= self.unary()?;
+            } else {
+                return Some(value);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Option<f64> {
+        if self.eat(b'-') {
+            return self.unary().map(|v| -v);
+        }
+        if self.eat(b'+') {
+            return self.unary();
+        }
+        let base = self.atom()?;
+        if self.eat(b'^') {
+            // Right associative, and binds tighter than a leading minus.
+            return Some(base.powf(self.unary()?));
+        }
+        Some(base)
+    }
+
+    fn atom(&mut self) -> Option<f64> {
+        self.skip_ws();
+        let rest = &self.src[self.pos..];
+        if rest.starts_with(b"${") {
+            let close = rest.iter().position(|&c| c == b'}')?;
+            let name = std::str::from_utf8(&rest[2..close]).ok()?.trim();
+            self.pos += close + 1;
+            return match self.values.get(name) {
+                Some(Value::Number(n)) => n.as_f64(),
+                Some(other) => {
+                    self.error.get_or_insert_with(|| {
+                        format!("variable '{name}' is {other}, not a number")
+                    });
+                    Some(0.0)
+                }
+                None => None,
+            };
+        }
+        if self.eat(b'(') {
+            let value = self.expr()?;
+            return self.eat(b')').then_some(value);
+        }
+
+        let start = self.pos;
+        let first = *rest.first()?;
+        if first.is_ascii_digit() || first == b'.' {
+            while self.pos < self.src.len()
+                && (self.src[self.pos].is_ascii_digit() || self.src[self.pos] == b'.')
+            {
+                self.pos += 1;
+            }
+            // Exponent, e.g. 1e-3.
+            if matches!(self.src.get(self.pos), Some(b'e' | b'E')) {
+                let mut end = self.pos + 1;
+                if matches!(self.src.get(end), Some(b'+' | b'-')) {
+                    end += 1;
+                }
+                if self.src.get(end).is_some_and(u8::is_ascii_digit) {
+                    self.pos = end;
+                    while self.src.get(self.pos).is_some_and(u8::is_ascii_digit) {
+                        self.pos += 1;
+                    }
+                }
+            }
+            return std::str::from_utf8(&self.src[start..self.pos])
+                .ok()?
+                .parse()
+                .ok();
+        }
+        if first.is_ascii_alphabetic() {
+            while self
+                .src
+                .get(self.pos)
+                .is_some_and(u8::is_ascii_alphanumeric)
+            {
+                self.pos += 1;
+            }
+            let name = std::str::from_utf8(&self.src[start..self.pos]).ok()?;
+            return self.call(name);
+        }
+        None
+    }
+
+    fn call(&mut self, name: &str) -> Option<f64> {
+        if !self.eat(b'(') {
+            return None;
+        }
+        let mut args = vec![self.expr()?];
+        while self.eat(b',') {
+            args.push(self.expr()?);
+        }
+        if !self.eat(b')') {
+            return None;
+        }
+        match (name, args.as_slice()) {
+            ("min", [first, rest @ ..]) => Some(rest.iter().fold(*first, |a, &b| a.min(b))),
+            ("max", [first, rest @ ..]) => Some(rest.iter().fold(*first, |a, &b| a.max(b))),
+            ("abs", [x]) => Some(x.abs()),
+            ("round", [x]) => Some(x.round()),
+            ("floor", [x]) => Some(x.floor()),
+            ("ceil", [x]) => Some(x.ceil()),
+            ("sqrt", [x]) => Some(x.sqrt()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPLATE: &str = r#"{
+        "vars": {
+            "carrier": 200, "length": 600, "beat": "${carrier}/40",
+            "hi": "${carrier}", "lo": 100, "name": "Carrier ${carrier} Hz"
+        },
+        "global_settings": {"sample_rate": 44100},
+        "steps": [{
+            "duration": "${length}/2",
+            "description": "${hi}-${lo} Hz",
+            "voices": [{
+                "synth_function_name": "binaural_beat",
+                "params": {"baseFreq": "${carrier}*1.5", "beatFreq": "${beat}"}
+            }]
+        }, {
+            "duration": "max(${length} - 400, 60)",
+            "description": "${name}",
+            "voices": []
+        }]
+    }"#;
+
+    #[test]
+    fn templates_instantiate_with_defaults_and_overrides() {
+        let track = instantiate_template(TEMPLATE, &Map::new()).expect("template instantiates");
+        let params = &track.steps[0].voices[0].params;
+        assert_eq!(params["baseFreq"], 300.0);
+        assert_eq!(params["beatFreq"], 5.0);
+        assert_eq!(track.steps[0].duration, 300.0);
+        assert_eq!(track.steps[0].description, "200-100 Hz");
+        assert_eq!(track.steps[1].duration, 200.0);
+        assert_eq!(track.steps[1].description, "Carrier 200 Hz");
+
+        let overrides = serde_json::json!({"carrier": 100, "length": 200});
+        let track = instantiate_template(TEMPLATE, overrides.as_object().unwrap()).unwrap();
+        let params = &track.steps[0].voices[0].params;
+        assert_eq!(params["baseFreq"], 150.0);
+        assert_eq!(params["beatFreq"], 2.5);
+        assert_eq!(track.steps[1].duration, 60.0);
+    }
+
+    #[test]
+    fn template_errors_point_at_the_offending_value() {
+        let unknown = TEMPLATE.replace("${length}/2", "${lenght}/2");
+        let err =
+            instantiate_value(serde_json::from_str(&unknown).unwrap(), &Map::new()).unwrap_err();
+        assert_eq!(err.path, "$.steps[0].duration");
+
+        let cyclic = TEMPLATE.replace("\"carrier\": 200", "\"carrier\": \"${beat}*40\"");
+        assert!(instantiate_template(&cyclic, &Map::new()).is_err());
+
+        let overrides = serde_json::json!({"carier": 100});
+        assert!(instantiate_template(TEMPLATE, overrides.as_object().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,1337 @@
+//! Static checks for track JSON.
+//!
+//! The scheduler is deliberately forgiving: unknown synth functions are
+//! dropped, unread params are ignored and missing clips play silence.
+//! [`validate_track_value`] reports those cases up front so an editor can
+//! point at the offending field before playback starts.
+
+use crate::automation::{CURVES as AUTOMATION_CURVES, STEP_TARGETS};
+use crate::config::CONFIG;
+use crate::dsp::envelope::{FADE_CURVES, LINEAR_FADE_TYPES, TIME_UNITS};
+use crate::dsp::wavetable::MIN_CYCLE_SAMPLES;
+use crate::models::{
+    AutomationLane, BackgroundNoiseData, Breakpoint, ClipData, GlobalSettings, ModulationData,
+    StepData, TrackData, VoiceData, VolumeEnvelope, BINAURAL_MIX_SCALING, MAX_INDIVIDUAL_GAIN,
+    MAX_REPEAT_COUNT, NOISE_MIX_SCALING, TRACK_FORMAT_VERSION, VOICE_FILE_PARAMS,
+};
+use crate::modulation::{LFO_SHAPES as MOD_LFO_SHAPES, POLARITIES, SOURCE_PARAM_KEYS};
+use crate::template;
+use crate::voice_factory::voice_factory;
+use crate::voices::{synth_registry, synth_spec, ParamSpec, ParamType};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// The track will not play as written (missing file, dropped voice, ...).
+    Error,
+    /// The track plays but probably not as intended.
+    Warning,
+    /// Worth knowing, nothing is wrong.
+    Info,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    /// JSON path of the offending value, e.g. `$.steps[2].voices[0].params.beatFreq`.
+    pub path: String,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Envelope types understood by `Envelope::new` and the params each reads.
+const ENVELOPE_PARAM_KEYS: &[(&str, &[&str])] = &[
+    (
+        "linear_fade",
+        &["fade_duration", "start_amp", "end_amp", "floor_db"],
+    ),
+    ("adsr", &["attack", "decay", "sustain_level", "release"]),
+    ("linen", &["attack", "release"]),
+    (
+        "fade_in",
+        &["fade_duration", "start_amp", "end_amp", "floor_db"],
+    ),
+    (
+        "fade_out",
+        &["fade_duration", "start_amp", "end_amp", "floor_db"],
+    ),
+    (
+        "fade_in_out",
+        &[
+            "fade_in",
+            "fade_out",
+            "start_amp",
+            "amp",
+            "end_amp",
+            "floor_db",
+        ],
+    ),
+    ("breakpoints", &[]),
+    ("tremolo", &["rate", "depth"]),
+];
+
+/// Envelopes whose ramps follow `curve`.
+const FADE_ENVELOPES: &[&str] = &["linear_fade", "fade_in", "fade_out", "fade_in_out"];
+
+/// Envelope params that are times, and so follow `time_unit`.
+const ENVELOPE_TIME_KEYS: &[&str] = &[
+    "fade_duration",
+    "fade_in",
+    "fade_out",
+    "attack",
+    "decay",
+    "release",
+];
+
+/// Voice pairs where the beat frequency must stay below the carrier.
+const BEAT_CARRIER_KEYS: &[(&str, &str, &str)] = &[
+    ("binaural_beat", "baseFreq", "beatFreq"),
+    ("binaural_chord", "baseFreq", "beatFreq"),
+    ("monaural_beat", "baseFreq", "beatFreq"),
+    ("isochronic_tone", "baseFreq", "beatFreq"),
+    ("spatial_angle_modulation", "carrierFreq", "beatFreq"),
+];
+
+/// Validate a track given as JSON text.
+pub fn validate_track_json(track_json: &str) -> Result<Vec<Diagnostic>, serde_json::Error> {
+    let root: Value = serde_json::from_str(track_json)?;
+    Ok(validate_track_value(&root))
+}
+
+/// Validate an already parsed track. Structural problems that stop the track
+/// from deserializing are reported as a single error at `$`.
+pub fn validate_track_value(root: &Value) -> Vec<Diagnostic> {
+    let mut v = Validator::default();
+    // Templates are checked as instantiated with their declared values.
+    let instantiated;
+    let root = if template::is_template(root) {
+        match template::instantiate_value(root.clone(), &Map::new()) {
+            Ok(value) => {
+                instantiated = value;
+                &instantiated
+            }
+            Err(e) => {
+                v.push(&e.path, Severity::Error, e.message);
+                return v.out;
+            }
+        }
+    } else {
+        root
+    };
+    let track: TrackData = match serde_json::from_value(root.clone()) {
+        Ok(track) => track,
+        Err(e) => {
+            v.push("$", Severity::Error, format!("Track does not parse: {e}"));
+            return v.out;
+        }
+    };
+
+    if track.format_version > TRACK_FORMAT_VERSION {
+        v.push(
+            &child_path("$", field_name(root, &["format_version", "formatVersion"])),
+            Severity::Error,
+            format!(
+                "Track format version {} is newer than supported version {TRACK_FORMAT_VERSION}",
+                track.format_version
+            ),
+        );
+    }
+
+    let global_path = child_path(
+        "$",
+        field_name(root, &["global_settings", "globalSettings", "global"]),
+    );
+    v.check_global(&track.global_settings, &global_path);
+
+    let steps_key = field_name(root, &["steps", "progression"]);
+    let steps_path = child_path("$", steps_key);
+    let raw_steps = root.get(steps_key).and_then(|s| s.as_array());
+    if track.steps.is_empty() {
+        v.push(&steps_path, Severity::Warning, "Track has no steps");
+    }
+
+    let clips_key = field_name(root, &["clips", "overlay_clips"]);
+    let clips_path = child_path("$", clips_key);
+    for (i, clip) in track.clips.iter().enumerate() {
+        v.check_clip(clip, &format!("{clips_path}[{i}]"));
+    }
+
+    let noise_key = field_name(root, &["background_noise", "noise"]);
+    let noise_path = child_path("$", noise_key);
+    if let Some(noise) = &track.background_noise {
+        v.check_background_noise(noise, &noise_path);
+    }
+
+    let mut cursor = 0.0f64;
+    for (i, step) in track.steps.iter().enumerate() {
+        let path = format!("{steps_path}[{i}]");
+        let raw = raw_steps.and_then(|s| s.get(i)).unwrap_or(&Value::Null);
+        v.check_step(step, raw, &path);
+
+        let start = step.start.unwrap_or(cursor);
+        let end = start + step.duration.max(0.0);
+        cursor = end;
+        v.check_step_headroom(&track, step, start, end, &path);
+    }
+
+    if !track.repeats.is_empty() {
+        v.check_repeats(&track, &child_path("$", "repeats"), &steps_path);
+    }
+    v.out
+}
+
+#[derive(Default)]
+struct Validator {
+    out: Vec<Diagnostic>,
+}
+
+impl Validator {
+    fn push(&mut self, path: &str, severity: Severity, message: impl Into<String>) {
+        self.out.push(Diagnostic {
+            path: path.to_string(),
+            severity,
+            message: message.into(),
+        });
+    }
+
+    fn check_global(&mut self, global: &GlobalSettings, path: &str) {
+        if global.sample_rate == 0 {
+            self.push(
+                &format!("{path}.sample_rate"),
+                Severity::Error,
+                "Sample rate must be positive",
+            );
+        } else if !(8_000..=192_000).contains(&global.sample_rate) {
+            self.push(
+                &format!("{path}.sample_rate"),
+                Severity::Warning,
+                format!("Unusual sample rate {} Hz", global.sample_rate),
+            );
+        }
+        self.check_crossfade(
+            Some(global.crossfade_duration),
+            Some(&global.crossfade_curve),
+            &format!("{path}.crossfade_duration"),
+            &format!("{path}.crossfade_curve"),
+        );
+        self.check_normalization(
+            global.normalization_level,
+            &format!("{path}.normalization_level"),
+        );
+    }
+
+    fn check_crossfade(
+        &mut self,
+        duration: Option<f64>,
+        curve: Option<&str>,
+        duration_path: &str,
+        curve_path: &str,
+    ) {
+        if let Some(duration) = duration.filter(|d| *d < 0.0) {
+            self.push(
+                duration_path,
+                Severity::Error,
+                format!("Crossfade duration {duration} s is negative"),
+            );
+        }
+        if let Some(curve) = curve.filter(|c| !matches!(*c, "linear" | "equal_power")) {
+            self.push(
+                curve_path,
+                Severity::Warning,
+                format!("Unknown crossfade curve '{curve}'; 'linear' will be used"),
+            );
+        }
+    }
+
+    fn check_repeats(&mut self, track: &TrackData, path: &str, steps_path: &str) {
+        let active = track.active_repeat_blocks();
+        for (i, block) in track.repeats.iter().enumerate() {
+            let block_path = format!("{path}[{i}]");
+            let end = block.start_step.saturating_add(block.step_count);
+            if block.step_count == 0 {
+                self.push(
+                    &format!("{block_path}.step_count"),
+                    Severity::Error,
+                    "Repeat block covers no steps and is ignored",
+                );
+            } else if end > track.steps.len() {
+                self.push(
+                    &block_path,
+                    Severity::Error,
+                    format!(
+                        "Repeat block runs past the last step (steps {}..{end} of {}) and is ignored",
+                        block.start_step,
+                        track.steps.len()
+                    ),
+                );
+            } else if !active.contains(&i) {
+                self.push(
+                    &block_path,
+                    Severity::Error,
+                    "Repeat block overlaps an earlier block and is ignored",
+                );
+            } else {
+                for step in block.start_step..end {
+                    if track.steps[step].start.is_some() {
+                        self.push(
+                            &format!("{steps_path}[{step}].start"),
+                            Severity::Warning,
+                            "Explicit start is ignored inside a repeat block",
+                        );
+                    }
+                }
+            }
+            if block.count == 0 {
+                self.push(
+                    &format!("{block_path}.count"),
+                    Severity::Warning,
+                    "Repeat count is 0, so the block's steps never play",
+                );
+            } else if block.count > MAX_REPEAT_COUNT {
+                self.push(
+                    &format!("{block_path}.count"),
+                    Severity::Error,
+                    format!(
+                        "Repeat count {} is above the limit of {MAX_REPEAT_COUNT}; the block plays {MAX_REPEAT_COUNT} times",
+                        block.count
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_normalization(&mut self, level: f32, path: &str) {
+        if !(0.0..=1.0).contains(&level) {
+            self.push(
+                path,
+                Severity::Warning,
+                format!("Normalization level {level} is outside 0..1"),
+            );
+        }
+    }
+
+    fn check_step(&mut self, step: &StepData, raw: &Value, path: &str) {
+        let duration_path = child_path(
+            path,
+            field_name(
+                raw,
+                &["duration", "Duration", "durationSeconds", "stepDuration"],
+            ),
+        );
+        if step.duration < 0.0 {
+            self.push(
+                &duration_path,
+                Severity::Error,
+                format!("Step duration {} s is negative", step.duration),
+            );
+        } else if step.duration == 0.0 {
+            self.push(&duration_path, Severity::Warning, "Step has zero duration");
+        }
+        if let Some(start) = step.start {
+            if start < 0.0 {
+                self.push(
+                    &format!("{path}.start"),
+                    Severity::Error,
+                    format!("Step start {start} s is negative"),
+                );
+            }
+        }
+        self.check_crossfade(
+            step.crossfade_duration,
+            step.crossfade_curve.as_deref(),
+            &child_path(
+                path,
+                field_name(raw, &["crossfade_duration", "crossfadeDuration"]),
+            ),
+            &child_path(
+                path,
+                field_name(raw, &["crossfade_curve", "crossfadeCurve"]),
+            ),
+        );
+        for (key, volume) in [
+            ("binaural_volume", step.binaural_volume),
+            ("noise_volume", step.noise_volume),
+        ] {
+            if volume < 0.0 {
+                self.push(
+                    &format!("{path}.{key}"),
+                    Severity::Error,
+                    format!("Volume {volume} is negative"),
+                );
+            } else if volume > MAX_INDIVIDUAL_GAIN {
+                self.push(
+                    &format!("{path}.{key}"),
+                    Severity::Warning,
+                    format!("Volume {volume} is above the {MAX_INDIVIDUAL_GAIN} limit and will be clamped"),
+                );
+            }
+        }
+        self.check_normalization(
+            step.normalization_level,
+            &format!("{path}.normalization_level"),
+        );
+        self.check_automation(
+            &step.automation,
+            step.duration,
+            &child_path(path, "automation"),
+            |target| {
+                (!STEP_TARGETS.contains(&target))
+                    .then(|| format!("Steps can only automate {}", STEP_TARGETS.join(", ")))
+            },
+        );
+        for (i, lane) in step.automation.iter().enumerate() {
+            if !STEP_TARGETS.contains(&lane.target.as_str()) {
+                continue;
+            }
+            for (j, point) in lane.points.iter().enumerate() {
+                if !(0.0..=MAX_INDIVIDUAL_GAIN as f64).contains(&point.value) {
+                    self.push(
+                        &format!("{path}.automation[{i}].points[{j}].value"),
+                        Severity::Warning,
+                        format!(
+                            "Volume {} is outside 0 to {MAX_INDIVIDUAL_GAIN} and will be clamped",
+                            point.value
+                        ),
+                    );
+                }
+            }
+        }
+
+        let voices_path = format!("{path}.voices");
+        if step.voices.is_empty() {
+            self.push(&voices_path, Severity::Warning, "Step has no voices");
+        }
+        let raw_voices = raw.get("voices").and_then(|v| v.as_array());
+        for (i, voice) in step.voices.iter().enumerate() {
+            let raw_voice = raw_voices.and_then(|v| v.get(i)).unwrap_or(&Value::Null);
+            self.check_voice(
+                voice,
+                raw_voice,
+                step.duration,
+                &format!("{voices_path}[{i}]"),
+            );
+        }
+
+        let has_noise_bed = step
+            .voices
+            .iter()
+            .any(|v| v.voice_type.eq_ignore_ascii_case("noise"));
+        for (i, voice) in step.voices.iter().enumerate() {
+            let masked = voice.synth_function_name == "subliminal_encode"
+                && voice
+                    .params
+                    .get("mode")
+                    .and_then(|v| v.as_str())
+                    .is_some_and(|m| m.eq_ignore_ascii_case("masked"));
+            if masked && !has_noise_bed {
+                self.push(
+                    &format!("{voices_path}[{i}].params.mode"),
+                    Severity::Warning,
+                    "Masked subliminal has no noise voice in its step to play under and will be silent",
+                );
+            }
+        }
+    }
+
+    fn check_voice(&mut self, voice: &VoiceData, raw: &Value, duration: f64, path: &str) {
+        let synth_path = child_path(
+            path,
+            field_name(
+                raw,
+                &["synth_function_name", "synthFunctionName", "synth_function"],
+            ),
+        );
+        let params_path = child_path(path, field_name(raw, &["params", "parameters"]));
+        let synth = voice.synth_function_name.as_str();
+
+        match synth_spec(synth) {
+            // Custom synths declare no params, so there is nothing to check.
+            None if voice_factory(synth).is_some() => {}
+            None => {
+                let names = synth_registry()
+                    .iter()
+                    .flat_map(|s| std::iter::once(s.name).chain(s.aliases.iter().copied()));
+                let hint = closest(synth, names)
+                    .map(|s| format!(" (did you mean '{s}'?)"))
+                    .unwrap_or_default();
+                self.push(
+                    &synth_path,
+                    Severity::Error,
+                    format!("Unknown synth function '{synth}'{hint}; the voice will be skipped"),
+                );
+            }
+            Some(spec) => {
+                let mut keys: Vec<&String> = voice.params.keys().collect();
+                keys.sort();
+                for key in keys {
+                    let key_path = child_path(&params_path, key);
+                    if let Some(param) = spec.params.iter().find(|p| p.keys().any(|k| k == key)) {
+                        self.check_param_type(param, &voice.params[key], &key_path);
+                        continue;
+                    }
+                    let hint = closest(key, spec.param_keys())
+                        .map(|s| format!("; did you mean '{s}'?"))
+                        .unwrap_or_default();
+                    self.push(
+                        &key_path,
+                        Severity::Warning,
+                        format!("'{synth}' does not read '{key}'{hint}"),
+                    );
+                }
+                self.check_param_ranges(synth, &voice.params, &params_path);
+            }
+        }
+
+        for &key in VOICE_FILE_PARAMS {
+            for file in param_paths(voice.params.get(key)) {
+                self.check_file(&file, &child_path(&params_path, key), "Audio file");
+            }
+        }
+
+        if !matches!(
+            voice.voice_type.to_lowercase().as_str(),
+            "binaural" | "noise"
+        ) {
+            self.push(
+                &child_path(path, "voice_type"),
+                Severity::Info,
+                format!(
+                    "Voice type '{}' is mixed on the binaural bus",
+                    voice.voice_type
+                ),
+            );
+        }
+
+        if let Some(env) = &voice.volume_envelope {
+            let env_path = child_path(
+                path,
+                field_name(raw, &["volume_envelope", "volumeEnvelope"]),
+            );
+            self.check_envelope(env, duration, &env_path);
+        }
+
+        if synth_spec(synth).is_none() && voice_factory(synth).is_some() {
+            // Custom voices are opaque, so nothing can drive their params.
+            if voice.modulation.is_some() {
+                self.push(
+                    &child_path(path, "modulation"),
+                    Severity::Warning,
+                    format!(
+                        "Custom synth '{synth}' cannot be modulated; the modulation is ignored"
+                    ),
+                );
+            }
+            if !voice.automation.is_empty() {
+                self.push(
+                    &child_path(path, "automation"),
+                    Severity::Warning,
+                    format!("Custom synth '{synth}' cannot be automated; the lanes are ignored"),
+                );
+            }
+        } else if let Some(modulation) = &voice.modulation {
+            self.check_modulation(synth, modulation, &child_path(path, "modulation"));
+        }
+        if let Some(spec) = synth_spec(synth) {
+            self.check_automation(
+                &voice.automation,
+                duration,
+                &child_path(path, "automation"),
+                |target| {
+                    spec.modulation_fields(target)
+                        .is_empty()
+                        .then(|| format!("'{synth}' cannot automate '{target}'"))
+                },
+            );
+        }
+    }
+
+    /// `unsupported` explains why a lane target cannot be driven, if it can't.
+    fn check_automation(
+        &mut self,
+        lanes: &[AutomationLane],
+        duration: f64,
+        path: &str,
+        unsupported: impl Fn(&str) -> Option<String>,
+    ) {
+        let mut targets: Vec<&str> = Vec::new();
+        for (i, lane) in lanes.iter().enumerate() {
+            let lane_path = format!("{path}[{i}]");
+            if let Some(reason) = unsupported(&lane.target) {
+                self.push(
+                    &format!("{lane_path}.target"),
+                    Severity::Warning,
+                    format!("{reason}; the lane is ignored"),
+                );
+                continue;
+            }
+            if targets.contains(&lane.target.as_str()) {
+                self.push(
+                    &format!("{lane_path}.target"),
+                    Severity::Warning,
+                    format!(
+                        "'{}' is automated more than once; the last lane wins",
+                        lane.target
+                    ),
+                );
+            }
+            targets.push(&lane.target);
+            if lane.points.is_empty() {
+                self.push(
+                    &format!("{lane_path}.points"),
+                    Severity::Warning,
+                    "Lane has no breakpoints; it is ignored",
+                );
+                continue;
+            }
+            self.check_breakpoints(&lane.points, duration, " s", &format!("{lane_path}.points"));
+        }
+    }
+
+    /// Order, times and curves of breakpoints running from 0 to `end`, in
+    /// the unit named by `unit`.
+    fn check_breakpoints(&mut self, points: &[Breakpoint], end: f64, unit: &str, path: &str) {
+        if points.windows(2).any(|w| w[1].time < w[0].time) {
+            self.push(
+                path,
+                Severity::Info,
+                "Breakpoints are out of order and will be sorted by time",
+            );
+        }
+        for (j, point) in points.iter().enumerate() {
+            let point_path = format!("{path}[{j}]");
+            if !(0.0..=end).contains(&point.time) {
+                self.push(
+                    &format!("{point_path}.time"),
+                    Severity::Warning,
+                    format!(
+                        "Breakpoint at {}{unit} lies outside the step (0 to {end}{unit})",
+                        point.time
+                    ),
+                );
+            }
+            let curve = point.curve.to_lowercase();
+            if !AUTOMATION_CURVES.contains(&curve.as_str()) {
+                let hint = closest(&curve, AUTOMATION_CURVES.iter().copied())
+                    .map(|s| format!(" (did you mean '{s}'?)"))
+                    .unwrap_or_default();
+                self.push(
+                    &format!("{point_path}.curve"),
+                    Severity::Warning,
+                    format!("Unknown curve '{}'{hint}; linear is used", point.curve),
+                );
+            }
+        }
+    }
+
+    fn check_envelope(&mut self, env: &VolumeEnvelope, duration: f64, path: &str) {
+        let Some((_, known)) = ENVELOPE_PARAM_KEYS
+            .iter()
+            .find(|(name, _)| *name == env.envelope_type)
+        else {
+            self.push(
+                &format!("{path}.type"),
+                Severity::Warning,
+                format!(
+                    "Unknown envelope type '{}'; the envelope is ignored",
+                    env.envelope_type
+                ),
+            );
+            return;
+        };
+        let mut keys: Vec<&String> = env.params.keys().collect();
+        keys.sort();
+        for key in keys.into_iter().filter(|k| !known.contains(&k.as_str())) {
+            let hint = closest(key, known.iter().copied())
+                .map(|s| format!("; did you mean '{s}'?"))
+                .unwrap_or_default();
+            self.push(
+                &child_path(&format!("{path}.params"), key),
+                Severity::Warning,
+                format!(
+                    "'{}' envelope does not read '{key}'{hint}",
+                    env.envelope_type
+                ),
+            );
+        }
+
+        let mut fraction = false;
+        if let Some(unit) = &env.time_unit {
+            fraction = unit.eq_ignore_ascii_case("fraction");
+            if !TIME_UNITS.contains(&unit.to_lowercase().as_str()) {
+                self.push(
+                    &format!("{path}.time_unit"),
+                    Severity::Warning,
+                    format!("Unknown time unit '{unit}'; seconds are used"),
+                );
+            }
+        }
+        if fraction {
+            for key in ENVELOPE_TIME_KEYS {
+                if let Some(value) = env.params.get(*key).filter(|v| **v > 1.0) {
+                    self.push(
+                        &child_path(&format!("{path}.params"), key),
+                        Severity::Warning,
+                        format!("{key} of {value} is longer than the whole step"),
+                    );
+                }
+            }
+        }
+
+        let is_fade = FADE_ENVELOPES.contains(&env.envelope_type.as_str());
+        if let Some(curve) = &env.curve {
+            if !is_fade {
+                self.push(
+                    &format!("{path}.curve"),
+                    Severity::Info,
+                    format!("'{}' envelope ignores curve", env.envelope_type),
+                );
+            } else if !FADE_CURVES.contains(&curve.to_lowercase().as_str()) {
+                let hint = closest(curve, FADE_CURVES.iter().copied())
+                    .map(|s| format!(" (did you mean '{s}'?)"))
+                    .unwrap_or_default();
+                self.push(
+                    &format!("{path}.curve"),
+                    Severity::Warning,
+                    format!("Unknown fade curve '{curve}'{hint}; linear is used"),
+                );
+            }
+        }
+
+        if let Some(fade_type) = &env.fade_type {
+            if env.envelope_type != "linear_fade" {
+                self.push(
+                    &format!("{path}.fade_type"),
+                    Severity::Info,
+                    format!("'{}' envelope ignores fade_type", env.envelope_type),
+                );
+            } else if !LINEAR_FADE_TYPES.contains(&fade_type.to_lowercase().as_str()) {
+                let hint = closest(fade_type, LINEAR_FADE_TYPES.iter().copied())
+                    .map(|s| format!(" (did you mean '{s}'?)"))
+                    .unwrap_or_default();
+                self.push(
+                    &format!("{path}.fade_type"),
+                    Severity::Warning,
+                    format!("Unknown fade type '{fade_type}'{hint}; the fade runs in"),
+                );
+            }
+        }
+
+        if env.envelope_type == "breakpoints" {
+            if env.points.is_empty() {
+                self.push(
+                    &format!("{path}.points"),
+                    Severity::Warning,
+                    "Envelope has no breakpoints; the voice plays at full volume",
+                );
+            }
+            let (end, unit) = if fraction {
+                (1.0, "")
+            } else {
+                (duration, " s")
+            };
+            self.check_breakpoints(&env.points, end, unit, &format!("{path}.points"));
+            for (j, point) in env.points.iter().enumerate() {
+                if point.value < 0.0 {
+                    self.push(
+                        &format!("{path}.points[{j}].value"),
+                        Severity::Warning,
+                        format!("Gain {} is negative and will be silent", point.value),
+                    );
+                }
+            }
+        } else if !env.points.is_empty() {
+            self.push(
+                &format!("{path}.points"),
+                Severity::Info,
+                format!("'{}' envelope ignores points", env.envelope_type),
+            );
+        }
+
+        if env.envelope_type == "tremolo" {
+            if let Some(depth) = env
+                .params
+                .get("depth")
+                .filter(|d| !(0.0..=1.0).contains(*d))
+            {
+                self.push(
+                    &format!("{path}.params.depth"),
+                    Severity::Warning,
+                    format!("Tremolo depth {depth} will be clamped to 0..1"),
+                );
+            }
+        }
+    }
+
+    fn check_modulation(&mut self, synth: &str, modulation: &ModulationData, path: &str) {
+        let mut ids: Vec<&str> = Vec::new();
+        for (i, source) in modulation.sources.iter().enumerate() {
+            let source_path = format!("{path}.sources[{i}]");
+            if ids.contains(&source.id.as_str()) {
+                self.push(
+                    &format!("{source_path}.id"),
+                    Severity::Warning,
+                    format!("Duplicate modulation source id '{}'", source.id),
+                );
+            }
+            ids.push(&source.id);
+            let source_type = source.source_type.to_lowercase();
+            let Some((_, known)) = SOURCE_PARAM_KEYS
+                .iter()
+                .find(|(name, _)| *name == source_type)
+            else {
+                let hint = closest(&source_type, SOURCE_PARAM_KEYS.iter().map(|(n, _)| *n))
+                    .map(|s| format!(" (did you mean '{s}'?)"))
+                    .unwrap_or_default();
+                self.push(
+                    &format!("{source_path}.type"),
+                    Severity::Warning,
+                    format!(
+                        "Unknown modulation source type '{}'{hint}; its routes are ignored",
+                        source.source_type
+                    ),
+                );
+                continue;
+            };
+            let mut keys: Vec<&String> = source.params.keys().collect();
+            keys.sort();
+            for key in keys {
+                let key_path = child_path(&format!("{source_path}.params"), key);
+                if !known.contains(&key.as_str()) {
+                    let hint = closest(key, known.iter().copied())
+                        .map(|s| format!("; did you mean '{s}'?"))
+                        .unwrap_or_default();
+                    self.push(
+                        &key_path,
+                        Severity::Warning,
+                        format!("'{source_type}' source does not read '{key}'{hint}"),
+                    );
+                } else if key == "shape"
+                    && !source.params[key]
+                        .as_str()
+                        .is_some_and(|v| MOD_LFO_SHAPES.contains(&v.to_lowercase().as_str()))
+                {
+                    self.push(
+                        &key_path,
+                        Severity::Warning,
+                        format!(
+                            "Expected one of {}, got {}; a sine is used",
+                            MOD_LFO_SHAPES.join(", "),
+                            source.params[key]
+                        ),
+                    );
+                }
+            }
+        }
+
+        let spec = synth_spec(synth);
+        for (i, route) in modulation.routes.iter().enumerate() {
+            let route_path = format!("{path}.routes[{i}]");
+            if !ids.contains(&route.source.as_str()) {
+                self.push(
+                    &format!("{route_path}.source"),
+                    Severity::Warning,
+                    format!(
+                        "Modulation source '{}' is not declared; the route is ignored",
+                        route.source
+                    ),
+                );
+            }
+            if spec.is_some_and(|spec| spec.modulation_fields(&route.target).is_empty()) {
+                self.push(
+                    &format!("{route_path}.target"),
+                    Severity::Warning,
+                    format!(
+                        "'{synth}' cannot modulate '{}'; the route is ignored",
+                        route.target
+                    ),
+                );
+            }
+            if let Some(polarity) = &route.polarity {
+                if !POLARITIES.contains(&polarity.to_lowercase().as_str()) {
+                    self.push(
+                        &format!("{route_path}.polarity"),
+                        Severity::Warning,
+                        format!(
+                            "Expected one of {}, got '{polarity}'; the source's own range is used",
+                            POLARITIES.join(", ")
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Values of the wrong JSON type are ignored by the voices in favour of
+    /// the default, so flag them.
+    fn check_param_type(&mut self, param: &ParamSpec, value: &Value, path: &str) {
+        let ok = match param.param_type {
+            ParamType::Float => value.is_number(),
+            ParamType::Int => value.is_i64() || value.is_u64(),
+            ParamType::Bool => value.is_boolean(),
+            ParamType::Text => value.is_string(),
+            ParamType::Choice => value
+                .as_str()
+                .is_some_and(|v| param.choices.is_empty() || param.choices.contains(&v)),
+            ParamType::Json => true,
+        };
+        if ok {
+            return;
+        }
+        let expected = match param.param_type {
+            ParamType::Float => "a number".to_string(),
+            ParamType::Int => "an integer".to_string(),
+            ParamType::Bool => "true or false".to_string(),
+            ParamType::Text => "a string".to_string(),
+            ParamType::Choice => format!("one of {}", param.choices.join(", ")),
+            ParamType::Json => unreachable!(),
+        };
+        self.push(
+            path,
+            Severity::Warning,
+            format!("Expected {expected}, got {value}; the default will be used"),
+        );
+    }
+
+    fn check_param_ranges(&mut self, synth: &str, params: &HashMap<String, Value>, path: &str) {
+        let transition = synth.ends_with("_transition");
+        let base_synth = synth.trim_end_matches("_transition");
+
+        if let Some((_, carrier_key, beat_key)) = BEAT_CARRIER_KEYS
+            .iter()
+            .find(|(name, _, _)| *name == base_synth)
+        {
+            let prefixes: &[&str] = if transition { &["start", "end"] } else { &[""] };
+            for prefix in prefixes {
+                let carrier = endpoint(params, carrier_key, prefix);
+                let beat = endpoint(params, beat_key, prefix);
+                if let (Some((_, carrier)), Some((beat_name, beat))) = (carrier, beat) {
+                    if beat.abs() > carrier {
+                        self.push(
+                            &child_path(path, &beat_name),
+                            Severity::Warning,
+                            format!(
+                                "Beat frequency {beat} Hz is higher than the {carrier} Hz carrier"
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+
+        let mut keys: Vec<&String> = params.keys().collect();
+        keys.sort();
+        for key in keys {
+            let Some(value) = params[key].as_f64() else {
+                continue;
+            };
+            let name = strip_transition_prefix(key);
+            let is_carrier = matches!(name, "baseFreq" | "baseFreqL" | "baseFreqR" | "carrierFreq");
+            if is_carrier && value <= 0.0 {
+                self.push(
+                    &child_path(path, key),
+                    Severity::Error,
+                    format!("Carrier frequency {value} Hz must be positive"),
+                );
+            } else if matches!(name, "amp" | "ampL" | "ampR") && value < 0.0 {
+                self.push(
+                    &child_path(path, key),
+                    Severity::Warning,
+                    format!("Amplitude {value} is negative"),
+                );
+            }
+        }
+
+        if params
+            .get("carrierWaveform")
+            .and_then(|v| v.as_str())
+            .is_some_and(|w| w.eq_ignore_ascii_case("wavetable"))
+        {
+            let usable = match params.get("carrierWavetable") {
+                Some(Value::Array(items)) => {
+                    items.iter().filter(|v| v.is_number()).count() >= MIN_CYCLE_SAMPLES
+                }
+                Some(Value::String(file)) => !file.is_empty(),
+                _ => false,
+            };
+            if !usable {
+                self.push(
+                    &child_path(path, "carrierWavetable"),
+                    Severity::Warning,
+                    format!(
+                        "A 'wavetable' carrier needs an audio file or at least {MIN_CYCLE_SAMPLES} samples; a sine is used"
+                    ),
+                );
+            }
+        }
+
+        if synth == "binaural_chord" {
+            let chord = params
+                .get("partialMode")
+                .and_then(|v| v.as_str())
+                .is_some_and(|m| m.eq_ignore_ascii_case("chord"));
+            for key in ["chordRatios", "partialAmps", "partialBeats"] {
+                let Some(value) = params.get(key).filter(|v| !v.is_null()) else {
+                    continue;
+                };
+                let key_path = child_path(path, key);
+                let Some(items) = value.as_array() else {
+                    self.push(
+                        &key_path,
+                        Severity::Warning,
+                        format!("Expected an array of numbers, got {value}; it is ignored"),
+                    );
+                    continue;
+                };
+                if key == "chordRatios" && !chord {
+                    self.push(
+                        &key_path,
+                        Severity::Info,
+                        "Chord ratios are only read when partialMode is 'chord'",
+                    );
+                }
+                for (i, item) in items.iter().enumerate() {
+                    let problem = match (key, item.as_f64()) {
+                        ("partialBeats", _) if item.is_null() => None,
+                        (_, None) => Some("is not a number"),
+                        ("chordRatios", Some(r)) if r <= 0.0 => Some("must be positive"),
+                        ("partialAmps", Some(a)) if a < 0.0 => Some("is negative"),
+                        _ => None,
+                    };
+                    if let Some(problem) = problem {
+                        let fallback = match key {
+                            "chordRatios" => "the partial is dropped",
+                            "partialAmps" => "the default amplitude is used",
+                            _ => "the voice's beatFreq is used",
+                        };
+                        self.push(
+                            &format!("{key_path}[{i}]"),
+                            Severity::Warning,
+                            format!("{item} {problem}; {fallback}"),
+                        );
+                    }
+                }
+            }
+        }
+
+        if synth == "subliminal_encode" {
+            if let Some(carrier) = params.get("carrierFreq").and_then(|v| v.as_f64()) {
+                if !(15_000.0..=20_000.0).contains(&carrier) {
+                    self.push(
+                        &child_path(path, "carrierFreq"),
+                        Severity::Warning,
+                        format!("Carrier {carrier} Hz will be clamped to 15000..20000 Hz"),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_clip(&mut self, clip: &ClipData, path: &str) {
+        if clip.file_path.is_empty() {
+            self.push(path, Severity::Error, "Clip has no file path");
+        } else {
+            self.check_file(&clip.file_path, &format!("{path}.file_path"), "Clip file");
+        }
+        if clip.start < 0.0 {
+            self.push(
+                &format!("{path}.start"),
+                Severity::Error,
+                format!("Clip start {} s is negative", clip.start),
+            );
+        }
+        if clip.amp < 0.0 {
+            self.push(
+                &format!("{path}.amp"),
+                Severity::Warning,
+                format!("Clip gain {} is negative", clip.amp),
+            );
+        }
+    }
+
+    fn check_background_noise(&mut self, noise: &BackgroundNoiseData, path: &str) {
+        let file_path = format!("{path}.file_path");
+        if noise.file_path.ends_with(".noise") {
+            self.check_file(&noise.file_path, &file_path, "Noise preset");
+        } else if noise.params.is_none() && noise.amp != 0.0 {
+            let message = if noise.file_path.is_empty() {
+                "Background noise has neither a .noise file nor inline params".to_string()
+            } else {
+                format!(
+                    "'{}' is not a .noise file; background noise is disabled",
+                    noise.file_path
+                )
+            };
+            self.push(&file_path, Severity::Warning, message);
+        }
+        if let Some(params) = &noise.params {
+            if !params.input_audio_path.is_empty() {
+                self.check_file(
+                    &params.input_audio_path,
+                    &format!("{path}.params.input_audio_path"),
+                    "Audio file",
+                );
+            }
+        }
+        for (key, value) in [
+            ("start_time", noise.start_time),
+            ("fade_in", noise.fade_in),
+            ("fade_out", noise.fade_out),
+        ] {
+            if value < 0.0 {
+                self.push(
+                    &format!("{path}.{key}"),
+                    Severity::Error,
+                    format!("{value} s is negative"),
+                );
+            }
+        }
+        if noise.amp < 0.0 {
+            self.push(
+                &format!("{path}.amp"),
+                Severity::Warning,
+                format!("Noise gain {} is negative", noise.amp),
+            );
+        }
+    }
+
+    /// Estimate the worst-case sum of the step's buses plus background noise
+    /// and any clips that start during it.
+    fn check_step_headroom(
+        &mut self,
+        track: &TrackData,
+        step: &StepData,
+        start: f64,
+        end: f64,
+        path: &str,
+    ) {
+        let has_noise = step
+            .voices
+            .iter()
+            .any(|v| v.voice_type.eq_ignore_ascii_case("noise"));
+        let has_binaural = step
+            .voices
+            .iter()
+            .any(|v| !v.voice_type.eq_ignore_ascii_case("noise"));
+        let level = step.normalization_level.clamp(0.0, 1.0);
+        let mut peak = 0.0f32;
+        if has_binaural {
+            peak +=
+                level * step.binaural_volume.clamp(0.0, MAX_INDIVIDUAL_GAIN) * BINAURAL_MIX_SCALING;
+        }
+        if has_noise {
+            peak += level * step.noise_volume.clamp(0.0, MAX_INDIVIDUAL_GAIN) * NOISE_MIX_SCALING;
+        }
+        if let Some(noise) = &track.background_noise {
+            peak += noise.amp.abs() * CONFIG.noise_gain;
+        }
+        for clip in &track.clips {
+            if (start..end).contains(&clip.start) {
+                peak += clip.amp.abs() * CONFIG.clip_gain;
+            }
+        }
+        if peak > 1.0 {
+            self.push(
+                path,
+                Severity::Warning,
+                format!("Estimated peak level {peak:.2} exceeds full scale; expect clipping"),
+            );
+        }
+    }
+
+    fn check_file(&mut self, file: &str, path: &str, what: &str) {
+        if !Path::new(file).exists() {
+            self.push(path, Severity::Error, format!("{what} '{file}' not found"));
+        }
+    }
+}
+
+/// First of `names` present on `obj`, or the canonical (first) name.
+fn field_name<'a>(obj: &Value, names: &[&'a str]) -> &'a str {
+    names
+        .iter()
+        .copied()
+        .find(|n| obj.get(*n).is_some())
+        .unwrap_or(names[0])
+}
+
+fn child_path(parent: &str, key: &str) -> String {
+    let plain = !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if plain {
+        format!("{parent}.{key}")
+    } else {
+        format!("{parent}[{}]", Value::String(key.to_string()))
+    }
+}
+
+/// Resolve a transition endpoint the way the voices do: `startX` falls back
+/// to `X`, `endX` falls back to the start value.
+fn endpoint(params: &HashMap<String, Value>, key: &str, prefix: &str) -> Option<(String, f64)> {
+    let get = |k: &str| {
+        params
+            .get(k)
+            .and_then(|v| v.as_f64())
+            .map(|v| (k.to_string(), v))
+    };
+    let prefixed = |p: &str| {
+        let mut chars = key.chars();
+        let first = chars.next()?.to_ascii_uppercase();
+        get(&format!("{p}{first}{}", chars.as_str()))
+    };
+    match prefix {
+        "start" => prefixed("start").or_else(|| get(key)),
+        "end" => prefixed("end").or_else(|| endpoint(params, key, "start")),
+        _ => get(key),
+    }
+}
+
+/// `startBaseFreq` -> `baseFreq`, `endAmpL` -> `ampL`; other keys unchanged.
+fn strip_transition_prefix(key: &str) -> &str {
+    for prefix in ["start", "end"] {
+        if let Some(rest) = key.strip_prefix(prefix) {
+            if rest.starts_with(|c: char| c.is_ascii_uppercase()) {
+                return rest;
+            }
+        }
+    }
+    key
+}
+
+fn param_paths(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::String(s)) => s
+            .split(';')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(String::from)
+            .collect(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|v| v.as_str())
+            .map(String::from)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Closest candidate to `needle`: a case-insensitive match, otherwise the
+/// nearest one within two edits.
+fn closest<'a>(needle: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let needle_lower = needle.to_lowercase();
+    let mut best: Option<(usize, &'a str)> = None;
+    for candidate in candidates {
+        let distance = edit_distance(&needle_lower, &candidate.to_lowercase());
+        if best.is_none_or(|(d, _)| distance < d) {
+            best = Some((distance, candidate));
+        }
+    }
+    best.filter(|(d, _)| *d <= 2 && *d < needle.len() / 2)
+        .map(|(_, c)| c)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { prev } else { prev + 1 };
+            prev = row[j + 1];
+            row[j + 1] = cost.min(row[j] + 1).min(prev + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_unknown_synth_misspelled_param_and_bad_values() {
+        let json = r#"{
+            "global_settings": {"sample_rate": 44100},
+            "steps": [{
+                "duration": -5.0,
+                "voices": [
+                    {"synth_function_name": "binaural_beats", "params": {}},
+                    {"synth_function_name": "binaural_beat",
+                     "params": {"baseFreq": 5.0, "beatFreq": 10.0, "ampl": 0.5}},
+                    {"synth_function_name": "binaural_beat",
+                     "params": {"carrierWaveform": "wavetable",
+                                "carrierWavetable": "/definitely/missing-cycle.wav"}}
+                ]
+            }],
+            "clips": [{"file_path": "/definitely/missing.wav"}]
+        }"#;
+        let diags = validate_track_json(json).unwrap();
+        let find = |path: &str| {
+            diags
+                .iter()
+                .find(|d| d.path == path)
+                .unwrap_or_else(|| panic!("no diagnostic at {path}: {diags:?}"))
+        };
+
+        assert_eq!(find("$.steps[0].duration").severity, Severity::Error);
+        let unknown = find("$.steps[0].voices[0].synth_function_name");
+        assert!(unknown.message.contains("binaural_beat'"));
+        assert!(find("$.steps[0].voices[1].params.ampl")
+            .message
+            .contains("did you mean 'ampL'"));
+        assert_eq!(
+            find("$.steps[0].voices[1].params.beatFreq").severity,
+            Severity::Warning
+        );
+        assert_eq!(
+            find("$.steps[0].voices[2].params.carrierWavetable").severity,
+            Severity::Error
+        );
+        assert_eq!(find("$.clips[0].file_path").severity, Severity::Error);
+    }
+
+    #[test]
+    fn clean_track_has_no_diagnostics() {
+        let json = r#"{
+            "global_settings": {"sample_rate": 44100},
+            "steps": [{
+                "duration": 30.0,
+                "voices": [{"synth_function_name": "binaural_beat_transition",
+                            "params": {"startBaseFreq": 200.0, "endBeatFreq": 4.0,
+                                       "flangeEnable": true, "startPanFreq": 0.1}}]
+            }]
+        }"#;
+        let diags = validate_track_json(json).unwrap();
+        assert!(diags.is_empty(), "{diags:?}");
+    }
+
+    #[test]
+    fn registry_defaults_validate_cleanly() {
+        for spec in synth_registry() {
+            let track = serde_json::json!({
+                "global_settings": {"sample_rate": 44100},
+                "steps": [{
+                    "duration": 10.0,
+                    "voices": [{
+                        "synth_function_name": spec.name,
+                        "params": spec.default_params(),
+                    }]
+                }]
+            });
+            let diags = validate_track_value(&track);
+            assert!(diags.is_empty(), "{}: {diags:?}", spec.name);
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! Synths defined outside this crate.
+//!
+//! A [`VoiceFactory`] registered under a synth name builds the voice for any
+//! step voice with that `synth_function_name`, so downstream crates can add
+//! synths without touching [`VoiceKind`](crate::voices::VoiceKind). Built-in
+//! synths always win: their names and aliases cannot be registered. Custom
+//! voices get volume envelopes and spatial params like built-in ones, but not
+//! modulation or automation, which need a [`SynthSpec`](crate::voices::SynthSpec).
+
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Arc, RwLock};
+
+use crate::scheduler::Voice;
+use crate::voices::synth_spec;
+
+/// A voice built by a [`VoiceFactory`]. The hooks default to a voice that
+/// keeps no carrier phase and is never louder than 1.0.
+pub trait CustomVoice: Voice {
+    /// Current carrier phases (left, right) in radians, when the voice keeps
+    /// them. The scheduler hands them to the voice that replaces this one
+    /// when the track is edited or seeked, so the carrier does not click.
+    fn phases(&self) -> Option<(f32, f32)> {
+        None
+    }
+
+    /// Continue from phases returned by [`Self::phases`].
+    fn set_phases(&mut self, _phase_l: f32, _phase_r: f32) {}
+
+    /// Move a voice just built to `frame` frames into the step, when playback
+    /// starts part way through it. By default the voice starts afresh.
+    fn seek(&mut self, _frame: usize) {}
+
+    /// Largest absolute sample the voice produces, which the step's gain
+    /// stages divide by.
+    fn normalization_peak(&self) -> f32 {
+        1.0
+    }
+}
+
+/// Builds voices for one custom synth from a step voice's params, the step
+/// duration in seconds and the output sample rate. Returning `None` skips the
+/// voice, as an unknown synth name would.
+///
+/// Closures with the same signature as [`Self::create`] are factories.
+pub trait VoiceFactory: Send + Sync {
+    fn create(
+        &self,
+        params: &HashMap<String, Value>,
+        duration: f32,
+        sample_rate: f32,
+    ) -> Option<Box<dyn CustomVoice>>;
+}
+
+impl<F> VoiceFactory for F
+where
+    F: Fn(&HashMap<String, Value>, f32, f32) -> Option<Box<dyn CustomVoice>> + Send + Sync,
+{
+    fn create(
+        &self,
+        params: &HashMap<String, Value>,
+        duration: f32,
+        sample_rate: f32,
+    ) -> Option<Box<dyn CustomVoice>> {
+        self(params, duration, sample_rate)
+    }
+}
+
+static FACTORIES: Lazy<RwLock<HashMap<String, Arc<dyn VoiceFactory>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Make `name` a synth function built by `factory`, replacing any factory
+/// already registered under it. Fails for built-in synth names and aliases.
+pub fn register_voice_factory(
+    name: &str,
+    factory: impl VoiceFactory + 'static,
+) -> Result<(), Box<dyn Error>> {
+    if name.is_empty() {
+        return Err("Synth function name is empty".into());
+    }
+    if synth_spec(name).is_some() {
+        return Err(format!("'{name}' is a built-in synth function").into());
+    }
+    FACTORIES
+        .write()
+        .map_err(|_| "Voice factory registry is poisoned")?
+        .insert(name.to_string(), Arc::new(factory));
+    Ok(())
+}
+
+/// Remove the factory registered under `name`. Returns whether there was one.
+pub fn unregister_voice_factory(name: &str) -> bool {
+    FACTORIES
+        .write()
+        .map(|mut factories| factories.remove(name).is_some())
+        .unwrap_or(false)
+}
+
+/// Names of every registered custom synth, sorted.
+pub fn custom_synth_names() -> Vec<String> {
+    let mut names: Vec<String> = FACTORIES
+        .read()
+        .map(|factories| factories.keys().cloned().collect())
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+/// The factory registered under `name`, if any.
+pub fn voice_factory(name: &str) -> Option<Arc<dyn VoiceFactory>> {
+    FACTORIES.read().ok()?.get(name).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::StepData;
+    use crate::validation::validate_track_value;
+    use crate::voices::voices_for_step;
+
+    /// A constant level that keeps a running "phase" of frames played.
+    struct Dc {
+        level: f32,
+        frames: f32,
+    }
+
+    impl Voice for Dc {
+        fn process(&mut self, output: &mut [f32]) {
+            for frame in output.chunks_exact_mut(2) {
+                frame[0] += self.level;
+                frame[1] += self.level;
+                self.frames += 1.0;
+            }
+        }
+
+        fn is_finished(&self) -> bool {
+            false
+        }
+    }
+
+    impl CustomVoice for Dc {
+        fn phases(&self) -> Option<(f32, f32)> {
+            Some((self.frames, self.frames))
+        }
+
+        fn set_phases(&mut self, phase_l: f32, _phase_r: f32) {
+            self.frames = phase_l;
+        }
+
+        fn normalization_peak(&self) -> f32 {
+            self.level
+        }
+    }
+
+    #[test]
+    fn registered_synths_build_step_voices() {
+        let dc = |params: &HashMap<String, Value>, _: f32, _: f32| {
+            let level = params.get("level")?.as_f64()? as f32;
+            Some(Box::new(Dc { level, frames: 0.0 }) as Box<dyn CustomVoice>)
+        };
+        assert!(register_voice_factory("binaural_beat", dc).is_err());
+        assert!(register_voice_factory("noise", dc).is_err());
+        // Unique to this process, so parallel test runs do not share it.
+        let name = format!("test_dc_{}", std::process::id());
+        register_voice_factory(&name, dc).unwrap();
+        assert!(custom_synth_names().contains(&name));
+
+        let step: StepData = serde_json::from_value(serde_json::json!({
+            "duration": 1.0, "voices": [
+                {"synth_function_name": name, "params": {"level": 0.25},
+                 "volume_envelope": {"type": "linen",
+                                     "params": {"attack": 0.0, "release": 0.0}}},
+                {"synth_function_name": name, "params": {}}
+            ]
+        }))
+        .unwrap();
+        let mut voices = voices_for_step(&step, 100.0);
+        assert_eq!(voices.len(), 1, "a factory returning None skips the voice");
+        let voice = &mut voices[0];
+        assert_eq!(voice.normalization_peak, 0.25);
+
+        let mut out = vec![0.0; 20];
+        voice.kind.process(&mut out);
+        assert!(out.iter().all(|s| (s - 0.25).abs() < 1e-6), "{out:?}");
+        assert_eq!(voice.kind.get_phases(), Some((10.0, 10.0)));
+        voice.kind.set_phases(3.0, 3.0);
+        assert_eq!(voice.kind.get_phases(), Some((3.0, 3.0)));
+
+        let diags = validate_track_value(&serde_json::json!({
+            "global_settings": {"sample_rate": 44100},
+            "steps": [{"duration": 1.0, "voices": [
+                {"synth_function_name": name, "params": {"level": 0.25},
+                 "modulation": {"sources": [], "routes": []},
+                 "automation": [{"target": "level",
+                                 "points": [{"time": 0.0, "value": 0.5}]}]}
+            ]}]
+        }));
+        let paths: Vec<&str> = diags.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "$.steps[0].voices[0].modulation",
+                "$.steps[0].voices[0].automation"
+            ],
+            "{diags:?}"
+        );
+
+        assert!(unregister_voice_factory(&name));
+        assert!(voices_for_step(&step, 100.0).is_empty());
+    }
+}
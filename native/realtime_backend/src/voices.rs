@@ -1,5 +1,7 @@
+use once_cell::sync::Lazy;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
 use std::fs::File;
 
 use symphonia::core::audio::SampleBuffer;
@@ -11,20 +13,29 @@ use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
 use symphonia::default::{get_codecs, get_probe};
 
+use crate::automation::Lane;
+use crate::dsp::envelope::Envelope;
+use crate::dsp::flanger::{Flanger, FlangerParams, FlangerShape, FlangerTargets};
+use crate::dsp::spatializer::{SpatialDecoder, SpatialParams, Spatializer, TrajectoryPoint};
+use crate::dsp::subliminal::{Arrangement, Encoding, Layout, LAYOUTS, MODES};
 use crate::dsp::trig::{cos_lut, sin_lut};
-use crate::dsp::{
-    build_volume_envelope, pan2, skewed_sine_phase, skewed_triangle_phase, trapezoid_envelope,
-};
-use crate::models::{StepData, VoiceData};
+use crate::dsp::wavetable::Carrier;
+use crate::dsp::{balance2, pan2, skewed_sine_phase, skewed_triangle_phase, trapezoid_envelope};
+use crate::models::{AutomationLane, ModulationData, StepData, VoiceData};
+use crate::modulation::{ModMatrix, MOD_BLOCK_FRAMES};
 use crate::noise_params::{NoiseParams, NoiseSweep};
 use crate::scheduler::Voice;
 use crate::scheduler::{StepVoice, VoiceType};
 use crate::streaming_noise::StreamingNoise;
+use crate::voice_factory::{voice_factory, CustomVoice};
 
 /// Strongly typed wrapper for all available voice implementations.
 pub enum VoiceKind {
     BinauralBeat(BinauralBeatVoice),
     BinauralBeatTransition(BinauralBeatTransitionVoice),
+    MonauralBeat(MonauralBeatVoice),
+    MonauralBeatTransition(MonauralBeatTransitionVoice),
+    BinauralChord(BinauralChordVoice),
     IsochronicTone(IsochronicToneVoice),
     IsochronicToneTransition(IsochronicToneTransitionVoice),
     QamBeat(QamBeatVoice),
@@ -39,8 +50,12 @@ pub enum VoiceKind {
     RhythmicWaveshapingTransition(RhythmicWaveshapingTransitionVoice),
     SubliminalEncode(SubliminalEncodeVoice),
     VolumeEnvelope(Box<VolumeEnvelopeVoice>),
+    Spatial(Box<SpatialVoice>),
+    Modulated(Box<ModulatedVoice>),
     NoiseSweptNotch(NoiseSweptNotchVoice),
     NoiseSweptNotchTransition(NoiseSweptNotchTransitionVoice),
+    /// A synth from a registered [`VoiceFactory`](crate::voice_factory::VoiceFactory).
+    Custom(Box<dyn CustomVoice>),
 }
 
 fn get_f32(params: &HashMap<String, Value>, key: &str, default: f32) -> f32 {
@@ -55,6 +70,198 @@ fn get_bool(params: &HashMap<String, Value>, key: &str, default: bool) -> bool {
     params.get(key).and_then(|v| v.as_bool()).unwrap_or(default)
 }
 
+/// Carrier oscillator selected by `carrierWaveform`. `wavetable` reads one
+/// cycle from `carrierWavetable`, given as an array of samples or an audio
+/// file path. Anything unusable falls back to a sine, with a warning.
+fn carrier_from_params(params: &HashMap<String, Value>) -> Carrier {
+    let name = params
+        .get("carrierWaveform")
+        .and_then(|v| v.as_str())
+        .unwrap_or("sine");
+    if !name.eq_ignore_ascii_case("wavetable") {
+        return Carrier::from_name(name).unwrap_or_default();
+    }
+    let cycle: Vec<f32> = match params.get("carrierWavetable") {
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|v| v.as_f64())
+            .map(|v| v as f32)
+            .collect(),
+        Some(Value::String(path)) if !path.is_empty() => match load_audio_file(path) {
+            Ok((samples, _)) => samples,
+            Err(e) => {
+                log::warn!("Cannot load carrier wavetable {path}: {e}; using a sine");
+                return Carrier::default();
+            }
+        },
+        _ => Vec::new(),
+    };
+    Carrier::from_cycle(&cycle).unwrap_or_else(|| {
+        log::warn!("Carrier wavetable has no usable cycle; using a sine");
+        Carrier::default()
+    })
+}
+
+/// Read the `flange*` voice parameters. When `prefix` is `"start"` or `"end"`
+/// the prefixed keys (e.g. `startFlangeRateHz`) take precedence over the plain
+/// ones. Returns the enable flag alongside the parsed parameters.
+fn flanger_params_from_json(
+    params: &HashMap<String, Value>,
+    prefix: &str,
+) -> (bool, FlangerParams) {
+    let num = |name: &str, default: f32| {
+        let plain = get_f32(params, &format!("flange{name}"), default);
+        if prefix.is_empty() {
+            plain
+        } else {
+            get_f32(params, &format!("{prefix}Flange{name}"), plain)
+        }
+    };
+    let flag = |name: &str, default: bool| {
+        let plain = get_bool(params, &format!("flange{name}"), default);
+        if prefix.is_empty() {
+            plain
+        } else {
+            get_bool(params, &format!("{prefix}Flange{name}"), plain)
+        }
+    };
+    let d = FlangerParams::default();
+    let shape = params
+        .get(&format!("{prefix}FlangeShape"))
+        .or_else(|| params.get("flangeShape"))
+        .and_then(|v| v.as_str())
+        .map(FlangerShape::from_name)
+        .unwrap_or(d.shape);
+    let enabled = flag("Enable", false);
+    let fp = FlangerParams {
+        targets: FlangerTargets {
+            rate_hz: num("RateHz", d.targets.rate_hz),
+            delay_ms: num("DelayMs", d.targets.delay_ms),
+            depth_ms: num("DepthMs", d.targets.depth_ms),
+            feedback: num("Feedback", d.targets.feedback),
+            mix: num("Mix", d.targets.mix),
+            loop_hpf_hz: num("LoopHpfHz", d.targets.loop_hpf_hz),
+            loop_lpf_hz: num("LoopLpfHz", d.targets.loop_lpf_hz),
+        },
+        shape,
+        min_delay_ms: num("MinDelayMs", d.min_delay_ms),
+        max_delay_ms: num("MaxDelayMs", d.max_delay_ms),
+        delay_law: flag("DelayLaw", d.delay_law),
+        interp: flag("Interp", d.interp),
+        stereo_mode: flag("StereoMode", d.stereo_mode),
+        spread_deg: num("SpreadDeg", d.spread_deg),
+        loudness_mode: flag("LoudnessMode", d.loudness_mode),
+        loudness_tc_ms: num("LoudnessTcMs", d.loudness_tc_ms),
+        loudness_min_gain: num("LoudnessMinGain", d.loudness_min_gain),
+        loudness_max_gain: num("LoudnessMaxGain", d.loudness_max_gain),
+        dezipper_rate_ms: num("DezipperRateMs", d.dezipper_rate_ms),
+        dezipper_delay_ms: num("DezipperDelayMs", d.dezipper_delay_ms),
+        dezipper_depth_ms: num("DezipperDepthMs", d.dezipper_depth_ms),
+        dezipper_feedback_ms: num("DezipperFeedbackMs", d.dezipper_feedback_ms),
+        dezipper_wet_ms: num("DezipperWetMs", d.dezipper_wet_ms),
+        dezipper_filter_ms: num("DezipperFilterMs", d.dezipper_filter_ms),
+    };
+    (enabled, fp)
+}
+
+/// The flanger of a transition voice and its start and end targets.
+fn transition_flanger(
+    params: &HashMap<String, Value>,
+    sample_rate: f32,
+) -> (Option<Flanger>, FlangerTargets, FlangerTargets) {
+    let (start_enable, start_params) = flanger_params_from_json(params, "start");
+    let (end_enable, end_params) = flanger_params_from_json(params, "end");
+    // A flanger that is only enabled at one end fades in/out via its wet mix.
+    let mut start = start_params.targets;
+    let mut end = end_params.targets;
+    if !start_enable {
+        start.mix = 0.0;
+    }
+    if !end_enable {
+        end.mix = 0.0;
+    }
+    let flanger = (start_enable || end_enable).then(|| {
+        let mut fp = start_params.clone();
+        fp.targets = start;
+        fp.max_delay_ms = fp.max_delay_ms.max(end_params.max_delay_ms);
+        Flanger::new(&fp, sample_rate)
+    });
+    (flanger, start, end)
+}
+
+/// Largest gain of a voice's flanger, if it has one.
+fn flanger_gain(flanger: &Option<Flanger>) -> f32 {
+    flanger.as_ref().map_or(1.0, Flanger::max_gain)
+}
+
+/// Largest gain of a transition voice's flanger over the transition.
+fn transition_flanger_gain(flanger: &Option<Flanger>, end: &FlangerTargets) -> f32 {
+    flanger.as_ref().map_or(1.0, |f| f.max_gain_towards(end))
+}
+
+/// Read the `spatial*` voice parameters. Returns `None` unless `spatialEnable` is set.
+///
+/// `spatialTrajectory` is a list of keyframes such as
+/// `{"t": 0.0, "azimuthDeg": -90.0, "distanceM": 1.0}`; without keyframes the
+/// source sits at `spatialAzimuthDeg` / `spatialDistanceM`.
+fn spatial_params_from_json(params: &HashMap<String, Value>) -> Option<SpatialParams> {
+    if !get_bool(params, "spatialEnable", false) {
+        return None;
+    }
+    let d = SpatialParams::default();
+    let mut trajectory: Vec<TrajectoryPoint> = params
+        .get("spatialTrajectory")
+        .and_then(|v| v.as_array())
+        .map(|points| {
+            points
+                .iter()
+                .filter_map(|p| {
+                    let num = |keys: &[&str]| {
+                        keys.iter()
+                            .find_map(|k| p.get(*k).and_then(|v| v.as_f64()))
+                            .map(|v| v as f32)
+                    };
+                    Some(TrajectoryPoint {
+                        time: num(&["t", "time"])?,
+                        azimuth_deg: num(&["azimuthDeg", "azimuth"]).unwrap_or(d.azimuth_deg),
+                        distance_m: num(&["distanceM", "distance"]).unwrap_or(d.distance_m),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    trajectory.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+    Some(SpatialParams {
+        trajectory,
+        azimuth_deg: get_f32(params, "spatialAzimuthDeg", d.azimuth_deg),
+        distance_m: get_f32(params, "spatialDistanceM", d.distance_m),
+        decoder: params
+            .get("spatialDecoder")
+            .and_then(|v| v.as_str())
+            .map(SpatialDecoder::from_name)
+            .unwrap_or(d.decoder),
+        use_itd_ild: get_bool(params, "spatialUseItdIld", d.use_itd_ild),
+        head_radius_m: get_f32(params, "spatialHeadRadiusM", d.head_radius_m),
+        ild_max_db: get_f32(params, "spatialIldMaxDb", d.ild_max_db),
+        ild_xover_hz: get_f32(params, "spatialIldXoverHz", d.ild_xover_hz),
+        itd_scale: get_f32(params, "spatialItdScale", d.itd_scale),
+        ear_angle_deg: get_f32(params, "spatialEarAngleDeg", d.ear_angle_deg),
+        max_deg_per_s: get_f32(params, "spatialMaxDegPerS", d.max_deg_per_s),
+        max_delay_step_samples: get_f32(
+            params,
+            "spatialMaxDelayStepSamples",
+            d.max_delay_step_samples,
+        ),
+        min_distance_m: get_f32(params, "spatialMinDistanceM", d.min_distance_m),
+        ref_distance_m: get_f32(params, "spatialRefDistanceM", d.ref_distance_m),
+        rolloff: get_f32(params, "spatialRolloff", d.rolloff),
+        hf_roll_db_per_m: get_f32(params, "spatialHfRollDbPerM", d.hf_roll_db_per_m),
+        dezipper_theta_ms: get_f32(params, "spatialDezipperThetaMs", d.dezipper_theta_ms),
+        dezipper_dist_ms: get_f32(params, "spatialDezipperDistMs", d.dezipper_dist_ms),
+    })
+}
+
 #[derive(Clone, Copy)]
 enum TransitionCurve {
     Linear,
@@ -95,27 +302,170 @@ impl TransitionCurve {
     }
 }
 
+/// Auto-pan settings shared by the binaural and QAM voices (`pan`, `panType`,
+/// `panFreq`, `panRangeMin`, `panRangeMax`, `panPhase`).
+#[derive(Clone, Copy, PartialEq)]
+struct AutoPan {
+    equal_power: bool,
+    range_min: f32,
+    range_max: f32,
+    freq: f32,
+    phase: f32,
+}
+
+impl AutoPan {
+    /// Parse the pan parameters. With a `prefix` of `"start"` the prefixed
+    /// keys (e.g. `startPanFreq`) take precedence over the plain ones.
+    fn from_params(params: &HashMap<String, Value>, prefix: &str) -> Self {
+        let key = |name: &str| {
+            if prefix.is_empty() {
+                format!("pan{name}")
+            } else {
+                format!("{prefix}Pan{name}")
+            }
+        };
+        let num = |name: &str, default: f32| {
+            get_f32(
+                params,
+                &key(name),
+                get_f32(params, &format!("pan{name}"), default),
+            )
+        };
+        let pan = num("", 0.0);
+        let pan_type = params
+            .get(&key("Type"))
+            .or_else(|| params.get("panType"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("linear");
+        Self {
+            equal_power: matches!(pan_type, "equal_power" | "constant_power"),
+            range_min: num("RangeMin", pan).clamp(-1.0, 1.0),
+            range_max: num("RangeMax", pan).clamp(-1.0, 1.0),
+            freq: num("Freq", 0.0),
+            phase: num("Phase", 0.0),
+        }
+    }
+
+    /// The `endPan*` params of a transition, each falling back to `start`
+    /// like the voices' other end params. There is no `endPanType`: the
+    /// start's pan law applies to the whole transition.
+    fn end_from_params(params: &HashMap<String, Value>, start: &Self) -> Self {
+        let num = |name: &str, default: f32| get_f32(params, &format!("endPan{name}"), default);
+        let pan = get_f32_opt(params, "endPan");
+        Self {
+            equal_power: start.equal_power,
+            range_min: num("RangeMin", pan.unwrap_or(start.range_min)).clamp(-1.0, 1.0),
+            range_max: num("RangeMax", pan.unwrap_or(start.range_max)).clamp(-1.0, 1.0),
+            freq: num("Freq", start.freq),
+            phase: num("Phase", start.phase),
+        }
+    }
+
+    fn lerp(&self, other: &Self, alpha: f32) -> Self {
+        Self {
+            equal_power: self.equal_power,
+            range_min: self.range_min + (other.range_min - self.range_min) * alpha,
+            range_max: self.range_max + (other.range_max - self.range_max) * alpha,
+            freq: self.freq + (other.freq - self.freq) * alpha,
+            phase: self.phase + (other.phase - self.phase) * alpha,
+        }
+    }
+
+    fn is_centered(&self) -> bool {
+        self.range_min == 0.0 && self.range_max == 0.0
+    }
+
+    /// Pan position for an LFO that has run `cycles` periods.
+    fn position(&self, cycles: f32) -> f32 {
+        let pan_min = self.range_min.min(self.range_max);
+        let pan_max = self.range_min.max(self.range_max);
+        let pan_center = (pan_min + pan_max) * 0.5;
+        let pan_range = (pan_max - pan_min) * 0.5;
+        if self.freq != 0.0 && pan_range > 0.0 {
+            let pan_osc = sin_lut(2.0 * std::f32::consts::PI * cycles + self.phase);
+            (pan_center + pan_range * pan_osc).clamp(-1.0, 1.0)
+        } else {
+            pan_center
+        }
+    }
+
+    fn apply(&self, left: f32, right: f32, cycles: f32) -> (f32, f32) {
+        balance2(left, right, self.position(cycles), self.equal_power)
+    }
+
+    /// Largest gain the pan law gives either channel: 1 for the linear law,
+    /// up to √2 (+3 dB) on the near side for the equal-power law.
+    fn peak_gain(&self) -> f32 {
+        if !self.equal_power || self.is_centered() {
+            return 1.0;
+        }
+        let (lo, hi) = if self.freq != 0.0 {
+            (
+                self.range_min.min(self.range_max),
+                self.range_min.max(self.range_max),
+            )
+        } else {
+            let center = self.position(0.0);
+            (center, center)
+        };
+        let angle = |pan: f32| (pan + 1.0) * std::f32::consts::FRAC_PI_4;
+        angle(lo).cos().max(angle(hi).sin()) * std::f32::consts::SQRT_2
+    }
+
+    /// [`Self::peak_gain`] anywhere along a transition from `self` to `end`.
+    /// The law is the start's throughout, and the LFO sweeps each end's full
+    /// range as soon as either end moves.
+    fn transition_peak_gain(&self, end: &Self) -> f32 {
+        let sweeping = self.freq != 0.0 || end.freq != 0.0;
+        let bound = |pan: &Self| {
+            Self {
+                equal_power: self.equal_power,
+                freq: if sweeping { 1.0 } else { pan.freq },
+                ..*pan
+            }
+            .peak_gain()
+        };
+        bound(self).max(bound(end))
+    }
+}
+
 impl VoiceKind {
     fn normalization_peak(&self) -> f32 {
         match self {
             VoiceKind::NoiseSweptNotch(v) => v.cached_peak(),
             VoiceKind::NoiseSweptNotchTransition(v) => v.cached_peak(),
+            VoiceKind::MonauralBeat(v) => v.normalization_peak(),
+            VoiceKind::MonauralBeatTransition(v) => v.normalization_peak(),
             VoiceKind::VolumeEnvelope(v) => v.normalization_peak(),
+            VoiceKind::Spatial(v) => v.normalization_peak(),
+            VoiceKind::BinauralBeat(v) => flanger_gain(&v.flanger) * v.pan.peak_gain(),
+            VoiceKind::BinauralBeatTransition(v) => {
+                transition_flanger_gain(&v.flanger, &v.end_flanger)
+                    * v.start_pan.transition_peak_gain(&v.end_pan)
+            }
+            VoiceKind::IsochronicTone(v) => flanger_gain(&v.flanger),
+            VoiceKind::IsochronicToneTransition(v) => {
+                transition_flanger_gain(&v.flanger, &v.end_flanger)
+            }
+            VoiceKind::QamBeat(v) => v.pan.peak_gain(),
+            VoiceKind::QamBeatTransition(v) => v.start_pan.transition_peak_gain(&v.end_pan),
+            VoiceKind::Modulated(v) => v.inner.normalization_peak(),
+            VoiceKind::Custom(v) => v.normalization_peak(),
             _ => 1.0,
         }
     }
 }
 
-/// Wrapper voice that applies a precomputed volume envelope to another voice.
+/// Wrapper voice that applies a volume envelope to another voice.
 pub struct VolumeEnvelopeVoice {
     inner: Box<VoiceKind>,
-    envelope: Vec<f32>,
+    envelope: Envelope,
     idx: usize,
     temp_buf: Vec<f32>,
 }
 
 impl VolumeEnvelopeVoice {
-    pub fn new(inner: Box<VoiceKind>, envelope: Vec<f32>) -> Self {
+    pub fn new(inner: Box<VoiceKind>, envelope: Envelope) -> Self {
         Self {
             inner,
             envelope,
@@ -126,8 +476,13 @@ impl VolumeEnvelopeVoice {
 
     pub fn normalization_peak(&self) -> f32 {
         let inner_peak = self.inner.normalization_peak();
-        let env_peak = self.envelope.iter().cloned().fold(1.0f32, f32::max);
-        inner_peak * env_peak
+        inner_peak * self.envelope.peak().max(1.0)
+    }
+
+    /// Move the envelope to `frame` frames into the step. The wrapped voice
+    /// is not advanced.
+    pub fn seek(&mut self, frame: usize) {
+        self.idx = frame.min(self.envelope.len());
     }
 }
 
@@ -141,11 +496,7 @@ impl Voice for VolumeEnvelopeVoice {
         self.inner.process(&mut self.temp_buf);
         let frames = output.len() / 2;
         for i in 0..frames {
-            let env = if self.idx < self.envelope.len() {
-                self.envelope[self.idx]
-            } else {
-                *self.envelope.last().unwrap_or(&1.0)
-            };
+            let env = self.envelope.value_at(self.idx);
             output[i * 2] += self.temp_buf[i * 2] * env;
             output[i * 2 + 1] += self.temp_buf[i * 2 + 1] * env;
             if self.idx < self.envelope.len() {
@@ -159,6 +510,137 @@ impl Voice for VolumeEnvelopeVoice {
     }
 }
 
+/// Wrapper voice that places another voice's output on a spatial trajectory.
+pub struct SpatialVoice {
+    inner: Box<VoiceKind>,
+    spatializer: Spatializer,
+    temp_buf: Vec<f32>,
+}
+
+impl SpatialVoice {
+    pub fn new(inner: Box<VoiceKind>, params: SpatialParams, sample_rate: f32) -> Self {
+        Self {
+            inner,
+            spatializer: Spatializer::new(params, sample_rate),
+            temp_buf: Vec::new(),
+        }
+    }
+
+    pub fn normalization_peak(&self) -> f32 {
+        self.inner.normalization_peak() * self.spatializer.peak_gain()
+    }
+}
+
+impl Voice for SpatialVoice {
+    fn process(&mut self, output: &mut [f32]) {
+        if self.temp_buf.len() != output.len() {
+            self.temp_buf.resize(output.len(), 0.0);
+        }
+        self.temp_buf.fill(0.0);
+        self.inner.process(&mut self.temp_buf);
+        let frames = output.len() / 2;
+        for i in 0..frames {
+            let (l, r) = self
+                .spatializer
+                .process(self.temp_buf[i * 2], self.temp_buf[i * 2 + 1]);
+            output[i * 2] += l;
+            output[i * 2 + 1] += r;
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.inner.is_finished()
+    }
+}
+
+/// Wrapper voice that drives another voice's fields from its modulation
+/// routes and automation lanes. Routes update every `MOD_BLOCK_FRAMES`
+/// frames, or every frame while lanes are present, so lanes are sample
+/// accurate. A lane sets the value routes are summed onto. Neither raises
+/// the normalization peak.
+pub struct ModulatedVoice {
+    inner: Box<VoiceKind>,
+    matrix: ModMatrix,
+    /// Each lane with the fields it drives.
+    lanes: Vec<(Lane, Vec<usize>)>,
+    elapsed_samples: usize,
+    sample_rate: f32,
+}
+
+impl ModulatedVoice {
+    pub fn new(
+        mut inner: Box<VoiceKind>,
+        spec: &SynthSpec,
+        modulation: Option<&ModulationData>,
+        automation: &[AutomationLane],
+        duration: f32,
+        sample_rate: f32,
+    ) -> Self {
+        let matrix = ModMatrix::new(
+            modulation.unwrap_or(&ModulationData::default()),
+            duration,
+            |key| {
+                spec.modulation_fields(key)
+                    .into_iter()
+                    .filter_map(|field| inner.field_mut(field).map(|v| (field, *v)))
+                    .collect()
+            },
+        );
+        let lanes = automation
+            .iter()
+            .filter_map(|data| {
+                let fields = spec.modulation_fields(&data.target);
+                Lane::new(data)
+                    .filter(|_| !fields.is_empty())
+                    .map(|lane| (lane, fields))
+            })
+            .collect();
+        Self {
+            inner,
+            matrix,
+            lanes,
+            elapsed_samples: 0,
+            sample_rate,
+        }
+    }
+}
+
+impl Voice for ModulatedVoice {
+    fn process(&mut self, output: &mut [f32]) {
+        let block_frames = if self.lanes.is_empty() {
+            MOD_BLOCK_FRAMES
+        } else {
+            1
+        };
+        for block in output.chunks_mut(block_frames * 2) {
+            let t = self.elapsed_samples as f64 / self.sample_rate as f64;
+            for (lane, fields) in &self.lanes {
+                let value = lane.value_at(t) as f32;
+                for &field in fields {
+                    if !self.matrix.set_base(field, value) {
+                        if let Some(f) = self.inner.field_mut(field) {
+                            *f = value;
+                        }
+                    }
+                }
+            }
+            let inner = &mut self.inner;
+            self.matrix
+                .step(block.len() / 2, self.sample_rate, |field, value| {
+                    if let Some(f) = inner.field_mut(field) {
+                        *f = value;
+                    }
+                });
+            self.inner.process(block);
+            self.elapsed_samples += block.len() / 2;
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.inner.is_finished()
+    }
+}
+
 pub struct BinauralBeatVoice {
     amp_l: f32,
     amp_r: f32,
@@ -187,11 +669,17 @@ pub struct BinauralBeatVoice {
     amp_osc_skew_r: f32,
     phase_osc_freq: f32,
     phase_osc_range: f32,
+    flanger: Option<Flanger>,
+    pan: AutoPan,
+    pan_cycles: f32,
     phase_l: f32,
     phase_r: f32,
     sample_rate: f32,
     remaining_samples: usize,
     sample_idx: usize,
+    /// Sum both carriers into each ear before flanging and panning.
+    monaural: bool,
+    carrier: Carrier,
 }
 
 pub struct BinauralBeatTransitionVoice {
@@ -247,6 +735,12 @@ pub struct BinauralBeatTransitionVoice {
     start_amp_osc_skew_r: f32,
     end_amp_osc_skew_r: f32,
     freq_osc_shape: LfoShape,
+    flanger: Option<Flanger>,
+    start_flanger: FlangerTargets,
+    end_flanger: FlangerTargets,
+    start_pan: AutoPan,
+    end_pan: AutoPan,
+    pan_cycles: f32,
     curve: TransitionCurve,
     initial_offset: f32,
     post_offset: f32,
@@ -256,6 +750,46 @@ pub struct BinauralBeatTransitionVoice {
     phase_r: f32,
     sample_idx: usize,
     duration: f32,
+    /// Sum both carriers into each ear before flanging and panning.
+    monaural: bool,
+    carrier: Carrier,
+}
+
+/// Monaural beat: the two carriers of a binaural beat summed in both ears,
+/// so the beat is audible on speakers. The `L` and `R` params address the
+/// lower and upper carrier (swapped by `leftHigh`).
+pub struct MonauralBeatVoice {
+    inner: BinauralBeatVoice,
+}
+
+/// Transitioning counterpart of [`MonauralBeatVoice`].
+pub struct MonauralBeatTransitionVoice {
+    inner: BinauralBeatTransitionVoice,
+}
+
+/// Binaural beat on several carrier partials at once: the harmonics of
+/// `baseFreq`, or a chord of frequency ratios over it. Every partial beats
+/// at `beatFreq` unless `partialBeats` gives it its own rate.
+pub struct BinauralChordVoice {
+    amp_l: f32,
+    amp_r: f32,
+    base_freq: f32,
+    beat_freq: f32,
+    left_high: bool,
+    partials: Vec<ChordPartial>,
+    sample_rate: f32,
+    remaining_samples: usize,
+}
+
+struct ChordPartial {
+    /// Carrier frequency as a multiple of `base_freq`.
+    ratio: f32,
+    /// Share of the voice amplitude; the partials of a voice sum to 1.
+    amp: f32,
+    /// Own beat frequency, or `None` to follow the voice's `beat_freq`.
+    beat_freq: Option<f32>,
+    phase_l: f32,
+    phase_r: f32,
 }
 
 pub struct IsochronicToneVoice {
@@ -286,6 +820,7 @@ pub struct IsochronicToneVoice {
     phase_osc_range: f32,
     ramp_percent: f32,
     gap_percent: f32,
+    flanger: Option<Flanger>,
     pan: f32,
     pan_range_min: f32,
     pan_range_max: f32,
@@ -297,6 +832,7 @@ pub struct IsochronicToneVoice {
     sample_rate: f32,
     remaining_samples: usize,
     sample_idx: usize,
+    carrier: Carrier,
 }
 
 pub struct IsochronicToneTransitionVoice {
@@ -354,6 +890,9 @@ pub struct IsochronicToneTransitionVoice {
     end_ramp_percent: f32,
     start_gap_percent: f32,
     end_gap_percent: f32,
+    flanger: Option<Flanger>,
+    start_flanger: FlangerTargets,
+    end_flanger: FlangerTargets,
     pan: f32,
     start_pan_range_min: f32,
     end_pan_range_min: f32,
@@ -373,6 +912,7 @@ pub struct IsochronicToneTransitionVoice {
     beat_phase: f32,
     sample_idx: usize,
     duration: f32,
+    carrier: Carrier,
 }
 
 pub struct QamBeatVoice {
@@ -410,6 +950,8 @@ pub struct QamBeatVoice {
     sideband_depth: f32,
     attack_time: f32,
     release_time: f32,
+    pan: AutoPan,
+    pan_cycles: f32,
     sample_rate: f32,
     remaining_samples: usize,
     sample_idx: usize,
@@ -482,6 +1024,9 @@ pub struct QamBeatTransitionVoice {
     sideband_depth: f32,
     attack_time: f32,
     release_time: f32,
+    start_pan: AutoPan,
+    end_pan: AutoPan,
+    pan_cycles: f32,
     curve: TransitionCurve,
     initial_offset: f32,
     post_offset: f32,
@@ -666,11 +1211,24 @@ pub struct RhythmicWaveshapingTransitionVoice {
 }
 
 pub struct SubliminalEncodeVoice {
-    samples: Vec<f32>,
-    position: usize,
+    arrangement: Arrangement,
+    amp: f32,
+    /// Set for masked clips, which play under the noise bed instead of
+    /// being normalised to `amp`.
+    bed: Option<NoiseBedFollower>,
     remaining_samples: usize,
 }
 
+/// Seconds a masked subliminal takes to follow a change in the noise bed.
+const NOISE_BED_FOLLOW_SECONDS: f32 = 0.25;
+
+/// Smoothed RMS of the noise bed a masked subliminal plays under.
+struct NoiseBedFollower {
+    target: f32,
+    level: f32,
+    coeff: f32,
+}
+
 /// Pre-allocated buffer size for noise voice scratch buffers.
 /// Based on typical audio callback buffer of 2048 frames * 2 channels.
 const NOISE_VOICE_SCRATCH_SIZE: usize = 4096;
@@ -715,8 +1273,22 @@ impl SubliminalEncodeVoice {
         let mode = params
             .get("mode")
             .and_then(|v| v.as_str())
-            .unwrap_or("sequence")
-            .to_lowercase();
+            .unwrap_or("sequence");
+        let encoding = Encoding::from_mode(
+            mode,
+            carrier,
+            get_f32(params, "speed", 2.0).clamp(1.0, 8.0),
+            get_f32(params, "snrDb", -15.0).clamp(-40.0, 0.0),
+        );
+        let layout = match encoding {
+            None => Layout::from_name(mode),
+            Some(_) => Layout::from_name(
+                params
+                    .get("layout")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("sequence"),
+            ),
+        };
 
         let mut paths: Vec<String> = Vec::new();
         if let Some(v) = params.get("audio_paths") {
@@ -743,72 +1315,35 @@ impl SubliminalEncodeVoice {
 
         let mut segments: Vec<Vec<f32>> = Vec::new();
         for p in &paths {
-            if let Some(seg) = load_and_modulate(p, sample_rate as u32, carrier) {
+            let seg = match encoding {
+                None => load_and_modulate(p, sample_rate as u32, carrier),
+                Some(encoding) => load_audio_file(p)
+                    .ok()
+                    .and_then(|(data, sr)| encoding.encode(&data, sr, sample_rate as u32)),
+            };
+            if let Some(seg) = seg {
                 segments.push(seg);
             }
         }
 
         let total_samples = (duration * sample_rate) as usize;
-        if segments.is_empty() || total_samples == 0 {
-            return Self {
-                samples: vec![0.0; total_samples * 2],
-                position: 0,
-                remaining_samples: total_samples,
-            };
-        }
-
-        let mut mono = vec![0.0f32; total_samples];
-        if mode == "stack" {
-            for seg in &segments {
-                for i in 0..total_samples {
-                    mono[i] += seg[i % seg.len()];
-                }
-            }
-            if segments.len() > 1 {
-                for v in &mut mono {
-                    *v /= segments.len() as f32;
-                }
-            }
-        } else {
-            let mut pos = 0usize;
-            let mut idx = 0usize;
-            let pause = sample_rate as usize;
-            while pos < total_samples {
-                let seg = &segments[idx % segments.len()];
-                let len = seg.len().min(total_samples - pos);
-                mono[pos..pos + len].copy_from_slice(&seg[..len]);
-                pos += len;
-                if pos >= total_samples {
-                    break;
-                }
-                let pad = pause.min(total_samples - pos);
-                pos += pad;
-                idx += 1;
-            }
-        }
-
-        let max_val = mono.iter().fold(0.0f32, |m, v| m.max(v.abs()));
-        if max_val > 0.0 {
-            for v in &mut mono {
-                *v = *v / max_val * amp;
-            }
-        }
-
-        let mut samples = Vec::with_capacity(total_samples * 2);
-        for s in &mono {
-            samples.push(*s);
-            samples.push(*s);
-        }
-
+        // Clips are followed by a one-second pause in sequence.
+        let pause = sample_rate as usize;
+        let bed = matches!(encoding, Some(Encoding::Masked { .. })).then(|| NoiseBedFollower {
+            target: 0.0,
+            level: 0.0,
+            coeff: (-1.0 / (NOISE_BED_FOLLOW_SECONDS * sample_rate)).exp(),
+        });
         Self {
-            samples,
-            position: 0,
+            arrangement: Arrangement::new(segments, layout, pause, total_samples),
+            amp,
+            bed,
             remaining_samples: total_samples,
         }
     }
 }
 
-fn load_audio_file(path: &str) -> Result<(Vec<f32>, u32), Box<dyn std::error::Error>> {
+pub(crate) fn load_audio_file(path: &str) -> Result<(Vec<f32>, u32), Box<dyn std::error::Error>> {
     let file = File::open(path)?;
     let mss = MediaSourceStream::new(Box::new(file), Default::default());
     let hint = Hint::new();
@@ -861,7 +1396,7 @@ fn load_audio_file(path: &str) -> Result<(Vec<f32>, u32), Box<dyn std::error::Er
     Ok((samples, sample_rate))
 }
 
-fn resample_linear(input: &[f32], src_rate: u32, dst_rate: u32) -> Vec<f32> {
+pub(crate) fn resample_linear(input: &[f32], src_rate: u32, dst_rate: u32) -> Vec<f32> {
     if src_rate == dst_rate || input.is_empty() {
         return input.to_vec();
     }
@@ -1238,7 +1773,11 @@ fn noise_params_from_json(
         fade_in: get_f32(params, "fade_in", 0.0),
         fade_out: get_f32(params, "fade_out", 0.0),
         amp_envelope: Vec::new(),
-        static_notches: Vec::new(),
+        static_notches: params
+            .get("static_notches")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default(),
     };
 
     crate::noise_params::apply_color_params(noise_params)
@@ -1465,6 +2004,9 @@ impl BinauralBeatVoice {
         let amp_osc_skew_r = get_f32(params, "ampOscSkewR", 0.0);
         let phase_osc_freq = get_f32(params, "phaseOscFreq", 0.0);
         let phase_osc_range = get_f32(params, "phaseOscRange", 0.0);
+        let (flange_enable, flange_params) = flanger_params_from_json(params, "");
+        let flanger = flange_enable.then(|| Flanger::new(&flange_params, sample_rate));
+        let pan = AutoPan::from_params(params, "");
 
         let total_samples = (duration * sample_rate) as usize;
         Self {
@@ -1495,11 +2037,16 @@ impl BinauralBeatVoice {
             amp_osc_skew_r,
             phase_osc_freq,
             phase_osc_range,
+            flanger,
+            pan,
+            pan_cycles: 0.0,
             phase_l: start_phase_l,
             phase_r: start_phase_r,
             sample_rate,
             remaining_samples: total_samples,
             sample_idx: 0,
+            monaural: false,
+            carrier: carrier_from_params(params),
         }
     }
 }
@@ -1663,6 +2210,9 @@ impl BinauralBeatTransitionVoice {
                 .and_then(|v| v.as_str())
                 .unwrap_or("sine"),
         );
+        let (flanger, start_flanger, end_flanger) = transition_flanger(params, sample_rate);
+        let start_pan = AutoPan::from_params(params, "start");
+        let end_pan = AutoPan::end_from_params(params, &start_pan);
 
         let curve = TransitionCurve::from_str(
             params
@@ -1728,6 +2278,12 @@ impl BinauralBeatTransitionVoice {
             start_amp_osc_skew_r,
             end_amp_osc_skew_r,
             freq_osc_shape,
+            flanger,
+            start_flanger,
+            end_flanger,
+            start_pan,
+            end_pan,
+            pan_cycles: 0.0,
             curve,
             initial_offset,
             post_offset,
@@ -1737,6 +2293,115 @@ impl BinauralBeatTransitionVoice {
             phase_r: start_start_phase_r,
             sample_idx: 0,
             duration,
+            monaural: false,
+            carrier: carrier_from_params(params),
+        }
+    }
+}
+
+impl MonauralBeatVoice {
+    pub fn new(params: &HashMap<String, Value>, duration: f32, sample_rate: f32) -> Self {
+        let mut inner = BinauralBeatVoice::new(params, duration, sample_rate);
+        inner.monaural = true;
+        Self { inner }
+    }
+
+    /// Both carriers can peak together in each ear.
+    fn normalization_peak(&self) -> f32 {
+        (self.inner.amp_l.abs() + self.inner.amp_r.abs())
+            * flanger_gain(&self.inner.flanger)
+            * self.inner.pan.peak_gain()
+    }
+}
+
+impl MonauralBeatTransitionVoice {
+    pub fn new(params: &HashMap<String, Value>, duration: f32, sample_rate: f32) -> Self {
+        let mut inner = BinauralBeatTransitionVoice::new(params, duration, sample_rate);
+        inner.monaural = true;
+        Self { inner }
+    }
+
+    /// Both carriers can peak together in each ear; amplitudes move
+    /// linearly between the endpoints, so the larger endpoint sum bounds it.
+    fn normalization_peak(&self) -> f32 {
+        let v = &self.inner;
+        (v.start_amp_l.abs() + v.start_amp_r.abs()).max(v.end_amp_l.abs() + v.end_amp_r.abs())
+            * transition_flanger_gain(&v.flanger, &v.end_flanger)
+            * v.start_pan.transition_peak_gain(&v.end_pan)
+    }
+}
+
+/// Numbers of the JSON array `key`, `None` standing in for anything else.
+fn get_f32_list(params: &HashMap<String, Value>, key: &str) -> Vec<Option<f32>> {
+    params
+        .get(key)
+        .and_then(|v| v.as_array())
+        .map(|items| items.iter().map(|v| v.as_f64().map(|v| v as f32)).collect())
+        .unwrap_or_default()
+}
+
+impl BinauralChordVoice {
+    pub fn new(params: &HashMap<String, Value>, duration: f32, sample_rate: f32) -> Self {
+        let chord = params
+            .get("partialMode")
+            .and_then(|v| v.as_str())
+            .is_some_and(|m| m.eq_ignore_ascii_case("chord"));
+        // (ratio, default amp) of each partial
+        let defaults: Vec<(f32, f32)> = if chord {
+            let ratios: Vec<f32> = get_f32_list(params, "chordRatios")
+                .into_iter()
+                .flatten()
+                .filter(|r| *r > 0.0)
+                .collect();
+            if ratios.is_empty() {
+                DEFAULT_CHORD_RATIOS.iter().map(|&r| (r, 1.0)).collect()
+            } else {
+                ratios.into_iter().map(|r| (r, 1.0)).collect()
+            }
+        } else {
+            let count = get_f32(params, "harmonicCount", DEFAULT_HARMONIC_COUNT as f32)
+                .clamp(1.0, MAX_CHORD_PARTIALS as f32) as usize;
+            let rolloff = get_f32(params, "harmonicRolloff", 1.0);
+            (1..=count)
+                .map(|n| (n as f32, (n as f32).powf(-rolloff)))
+                .collect()
+        };
+        let amps = get_f32_list(params, "partialAmps");
+        let beats = get_f32_list(params, "partialBeats");
+        let mut partials: Vec<ChordPartial> = defaults
+            .into_iter()
+            .take(MAX_CHORD_PARTIALS)
+            .enumerate()
+            .map(|(i, (ratio, amp))| ChordPartial {
+                ratio,
+                amp: amps
+                    .get(i)
+                    .copied()
+                    .flatten()
+                    .filter(|a| *a >= 0.0)
+                    .unwrap_or(amp),
+                beat_freq: beats.get(i).copied().flatten(),
+                phase_l: 0.0,
+                phase_r: 0.0,
+            })
+            .collect();
+        // Scale the partials to sum to 1 so each ear peaks at its amp.
+        let total: f32 = partials.iter().map(|p| p.amp).sum();
+        if total > 0.0 {
+            for partial in &mut partials {
+                partial.amp /= total;
+            }
+        }
+
+        Self {
+            amp_l: get_f32(params, "ampL", 0.5),
+            amp_r: get_f32(params, "ampR", 0.5),
+            base_freq: get_f32(params, "baseFreq", 200.0),
+            beat_freq: get_f32(params, "beatFreq", 4.0),
+            left_high: get_bool(params, "leftHigh", false),
+            partials,
+            sample_rate,
+            remaining_samples: (duration * sample_rate) as usize,
         }
     }
 }
@@ -1771,6 +2436,8 @@ impl IsochronicToneVoice {
         let phase_osc_range = get_f32(params, "phaseOscRange", 0.0);
         let ramp_percent = get_f32(params, "rampPercent", 0.2);
         let gap_percent = get_f32(params, "gapPercent", 0.15);
+        let (flange_enable, flange_params) = flanger_params_from_json(params, "");
+        let flanger = flange_enable.then(|| Flanger::new(&flange_params, sample_rate));
         let pan = get_f32(params, "pan", 0.0);
         let pan_range_min = get_f32(params, "panRangeMin", pan).clamp(-1.0, 1.0);
         let pan_range_max = get_f32(params, "panRangeMax", pan).clamp(-1.0, 1.0);
@@ -1807,6 +2474,7 @@ impl IsochronicToneVoice {
             phase_osc_range,
             ramp_percent,
             gap_percent,
+            flanger,
             pan,
             pan_range_min,
             pan_range_max,
@@ -1818,6 +2486,7 @@ impl IsochronicToneVoice {
             sample_rate,
             remaining_samples: total_samples,
             sample_idx: 0,
+            carrier: carrier_from_params(params),
         }
     }
 }
@@ -1987,6 +2656,7 @@ impl IsochronicToneTransitionVoice {
             get_f32(params, "gapPercent", 0.15),
         );
         let end_gap_percent = get_f32(params, "endGapPercent", start_gap_percent);
+        let (flanger, start_flanger, end_flanger) = transition_flanger(params, sample_rate);
         let pan = get_f32(params, "pan", 0.0);
         let start_pan_range_min = get_f32(
             params,
@@ -2074,6 +2744,9 @@ impl IsochronicToneTransitionVoice {
             end_ramp_percent,
             start_gap_percent,
             end_gap_percent,
+            flanger,
+            start_flanger,
+            end_flanger,
             pan,
             start_pan_range_min,
             end_pan_range_min,
@@ -2093,6 +2766,7 @@ impl IsochronicToneTransitionVoice {
             beat_phase: 0.0,
             sample_idx: 0,
             duration,
+            carrier: carrier_from_params(params),
         }
     }
 }
@@ -2134,6 +2808,7 @@ impl QamBeatVoice {
         let sideband_depth = get_f32(params, "sidebandDepth", 0.1);
         let attack_time = get_f32(params, "attackTime", 0.0);
         let release_time = get_f32(params, "releaseTime", 0.0);
+        let pan = AutoPan::from_params(params, "");
         let total_samples = (duration * sample_rate) as usize;
 
         Self {
@@ -2171,6 +2846,8 @@ impl QamBeatVoice {
             sideband_depth,
             attack_time,
             release_time,
+            pan,
+            pan_cycles: 0.0,
             sample_rate,
             remaining_samples: total_samples,
             sample_idx: 0,
@@ -2356,6 +3033,8 @@ impl QamBeatTransitionVoice {
         let sideband_depth = get_f32(params, "sidebandDepth", 0.1);
         let attack_time = get_f32(params, "attackTime", 0.0);
         let release_time = get_f32(params, "releaseTime", 0.0);
+        let start_pan = AutoPan::from_params(params, "start");
+        let end_pan = AutoPan::end_from_params(params, &start_pan);
         let curve = TransitionCurve::from_str(
             params
                 .get("transition_curve")
@@ -2427,6 +3106,9 @@ impl QamBeatTransitionVoice {
             sideband_depth,
             attack_time,
             release_time,
+            start_pan,
+            end_pan,
+            pan_cycles: 0.0,
             curve,
             initial_offset,
             post_offset,
@@ -2880,8 +3562,23 @@ impl Voice for BinauralBeatVoice {
                 - self.amp_osc_depth_r
                     * (0.5 * (1.0 + skewed_sine_phase(amp_phase_r.fract(), self.amp_osc_skew_r)));
 
-            let sample_l = sin_lut(ph_l) * env_l * self.amp_l;
-            let sample_r = sin_lut(ph_r) * env_r * self.amp_r;
+            let mut sample_l =
+                self.carrier.sample(ph_l, freq_l, self.sample_rate) * env_l * self.amp_l;
+            let mut sample_r =
+                self.carrier.sample(ph_r, freq_r, self.sample_rate) * env_r * self.amp_r;
+            if self.monaural {
+                let mono = sample_l + sample_r;
+                (sample_l, sample_r) = (mono, mono);
+            }
+
+            if let Some(flanger) = self.flanger.as_mut() {
+                (sample_l, sample_r) = flanger.process(sample_l, sample_r);
+            }
+
+            if !self.pan.is_centered() {
+                (sample_l, sample_r) = self.pan.apply(sample_l, sample_r, self.pan_cycles);
+                self.pan_cycles = (self.pan_cycles + self.pan.freq * dt).fract();
+            }
 
             output[i * 2] += sample_l;
             output[i * 2 + 1] += sample_r;
@@ -3033,8 +3730,25 @@ impl Voice for BinauralBeatTransitionVoice {
                 - amp_osc_depth_r
                     * (0.5 * (1.0 + skewed_sine_phase(amp_phase_r.fract(), amp_osc_skew_r)));
 
-            let sample_l = sin_lut(ph_l) * env_l * amp_l;
-            let sample_r = sin_lut(ph_r) * env_r * amp_r;
+            let mut sample_l = self.carrier.sample(ph_l, freq_l, self.sample_rate) * env_l * amp_l;
+            let mut sample_r = self.carrier.sample(ph_r, freq_r, self.sample_rate) * env_r * amp_r;
+            if self.monaural {
+                let mono = sample_l + sample_r;
+                (sample_l, sample_r) = (mono, mono);
+            }
+
+            if let Some(flanger) = self.flanger.as_mut() {
+                if self.start_flanger != self.end_flanger {
+                    flanger.set_targets(&self.start_flanger.lerp(&self.end_flanger, alpha));
+                }
+                (sample_l, sample_r) = flanger.process(sample_l, sample_r);
+            }
+
+            let pan = self.start_pan.lerp(&self.end_pan, alpha);
+            if !pan.is_centered() {
+                (sample_l, sample_r) = pan.apply(sample_l, sample_r, self.pan_cycles);
+                self.pan_cycles = (self.pan_cycles + pan.freq * dt).fract();
+            }
 
             output[i * 2] += sample_l;
             output[i * 2 + 1] += sample_r;
@@ -3049,20 +3763,87 @@ impl Voice for BinauralBeatTransitionVoice {
     }
 }
 
-impl Voice for IsochronicToneVoice {
+impl Voice for MonauralBeatVoice {
     fn process(&mut self, output: &mut [f32]) {
-        let channels = 2;
-        let frames = output.len() / channels;
-        for i in 0..frames {
-            if self.remaining_samples == 0 {
-                break;
-            }
-            let dt = 1.0 / self.sample_rate;
-            let t = self.sample_idx as f32 / self.sample_rate;
+        self.inner.process(output);
+    }
 
-            let phase_l_vib = self.freq_osc_freq_l * t
-                + self.freq_osc_phase_offset_l / (2.0 * std::f32::consts::PI);
-            let phase_r_vib = self.freq_osc_freq_r * t
+    fn is_finished(&self) -> bool {
+        self.inner.is_finished()
+    }
+}
+
+impl Voice for MonauralBeatTransitionVoice {
+    fn process(&mut self, output: &mut [f32]) {
+        self.inner.process(output);
+    }
+
+    fn is_finished(&self) -> bool {
+        self.inner.is_finished()
+    }
+}
+
+impl Voice for BinauralChordVoice {
+    fn process(&mut self, output: &mut [f32]) {
+        let frames = output.len() / 2;
+        let dt = 1.0 / self.sample_rate;
+        let nyquist = 0.5 * self.sample_rate;
+        let fade_start = nyquist * (1.0 - CHORD_NYQUIST_FADE);
+        for i in 0..frames {
+            if self.remaining_samples == 0 {
+                break;
+            }
+            let mut sample_l = 0.0;
+            let mut sample_r = 0.0;
+            for partial in &mut self.partials {
+                let carrier = self.base_freq * partial.ratio;
+                let half_beat = 0.5 * partial.beat_freq.unwrap_or(self.beat_freq);
+                let (freq_l, freq_r) = if self.left_high {
+                    (carrier + half_beat, carrier - half_beat)
+                } else {
+                    (carrier - half_beat, carrier + half_beat)
+                };
+                let (freq_l, freq_r) = (freq_l.max(0.0), freq_r.max(0.0));
+                partial.phase_l += 2.0 * std::f32::consts::PI * freq_l * dt;
+                partial.phase_l = partial.phase_l.rem_euclid(2.0 * std::f32::consts::PI);
+                partial.phase_r += 2.0 * std::f32::consts::PI * freq_r * dt;
+                partial.phase_r = partial.phase_r.rem_euclid(2.0 * std::f32::consts::PI);
+                // Partials at or above Nyquist would alias; they keep their
+                // phase but stay silent, and fade out on the way there so a
+                // sweeping carrier does not drop them with a click.
+                let top = freq_l.max(freq_r);
+                if top < nyquist {
+                    let fade = ((nyquist - top) / (nyquist - fade_start)).min(1.0);
+                    let amp = partial.amp * fade * fade * (3.0 - 2.0 * fade);
+                    sample_l += amp * sin_lut(partial.phase_l);
+                    sample_r += amp * sin_lut(partial.phase_r);
+                }
+            }
+            output[i * 2] += sample_l * self.amp_l;
+            output[i * 2 + 1] += sample_r * self.amp_r;
+            self.remaining_samples -= 1;
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.remaining_samples == 0
+    }
+}
+
+impl Voice for IsochronicToneVoice {
+    fn process(&mut self, output: &mut [f32]) {
+        let channels = 2;
+        let frames = output.len() / channels;
+        for i in 0..frames {
+            if self.remaining_samples == 0 {
+                break;
+            }
+            let dt = 1.0 / self.sample_rate;
+            let t = self.sample_idx as f32 / self.sample_rate;
+
+            let phase_l_vib = self.freq_osc_freq_l * t
+                + self.freq_osc_phase_offset_l / (2.0 * std::f32::consts::PI);
+            let phase_r_vib = self.freq_osc_freq_r * t
                 + self.freq_osc_phase_offset_r / (2.0 * std::f32::consts::PI);
             let vib_l = (self.freq_osc_range_l * 0.5)
                 * skewed_sine_phase(phase_l_vib.fract(), self.freq_osc_skew_l);
@@ -3119,8 +3900,14 @@ impl Voice for IsochronicToneVoice {
                 - self.amp_osc_depth_r
                     * (0.5 * (1.0 + skewed_sine_phase(amp_phase_r.fract(), self.amp_osc_skew_r)));
 
-            let mut sample_l = sin_lut(ph_l) * env_l * self.amp_l * iso_env;
-            let mut sample_r = sin_lut(ph_r) * env_r * self.amp_r * iso_env;
+            let mut sample_l =
+                self.carrier.sample(ph_l, freq_l, self.sample_rate) * env_l * self.amp_l * iso_env;
+            let mut sample_r =
+                self.carrier.sample(ph_r, freq_r, self.sample_rate) * env_r * self.amp_r * iso_env;
+
+            if let Some(flanger) = self.flanger.as_mut() {
+                (sample_l, sample_r) = flanger.process(sample_l, sample_r);
+            }
 
             // Calculate time-varying pan based on pan envelope parameters
             let pan_min = self.pan_range_min.min(self.pan_range_max);
@@ -3292,8 +4079,17 @@ impl Voice for IsochronicToneTransitionVoice {
                 - amp_osc_depth_r
                     * (0.5 * (1.0 + skewed_sine_phase(amp_phase_r.fract(), amp_osc_skew_r)));
 
-            let mut sample_l = sin_lut(ph_l) * env_l * amp_l * iso_env;
-            let mut sample_r = sin_lut(ph_r) * env_r * amp_r * iso_env;
+            let mut sample_l =
+                self.carrier.sample(ph_l, freq_l, self.sample_rate) * env_l * amp_l * iso_env;
+            let mut sample_r =
+                self.carrier.sample(ph_r, freq_r, self.sample_rate) * env_r * amp_r * iso_env;
+
+            if let Some(flanger) = self.flanger.as_mut() {
+                if self.start_flanger != self.end_flanger {
+                    flanger.set_targets(&self.start_flanger.lerp(&self.end_flanger, alpha));
+                }
+                (sample_l, sample_r) = flanger.process(sample_l, sample_r);
+            }
 
             // Calculate time-varying pan based on interpolated pan envelope parameters
             let pan_range_min = self.start_pan_range_min
@@ -3447,8 +4243,16 @@ impl Voice for QamBeatVoice {
                 env_mult *= (self.duration - t) / self.release_time;
             }
 
-            output[i * 2] += sig_l * self.amp_l * env_mult;
-            output[i * 2 + 1] += sig_r * self.amp_r * env_mult;
+            let mut sample_l = sig_l * self.amp_l * env_mult;
+            let mut sample_r = sig_r * self.amp_r * env_mult;
+
+            if !self.pan.is_centered() {
+                (sample_l, sample_r) = self.pan.apply(sample_l, sample_r, self.pan_cycles);
+                self.pan_cycles = (self.pan_cycles + self.pan.freq / self.sample_rate).fract();
+            }
+
+            output[i * 2] += sample_l;
+            output[i * 2 + 1] += sample_r;
 
             self.remaining_samples -= 1;
             self.sample_idx += 1;
@@ -3630,8 +4434,17 @@ impl Voice for QamBeatTransitionVoice {
                 env_mult *= (self.duration - t) / self.release_time;
             }
 
-            output[i * 2] += sig_l * amp_l * env_mult;
-            output[i * 2 + 1] += sig_r * amp_r * env_mult;
+            let mut sample_l = sig_l * amp_l * env_mult;
+            let mut sample_r = sig_r * amp_r * env_mult;
+
+            let pan = self.start_pan.lerp(&self.end_pan, alpha);
+            if !pan.is_centered() {
+                (sample_l, sample_r) = pan.apply(sample_l, sample_r, self.pan_cycles);
+                self.pan_cycles = (self.pan_cycles + pan.freq / self.sample_rate).fract();
+            }
+
+            output[i * 2] += sample_l;
+            output[i * 2 + 1] += sample_r;
 
             self.remaining_samples -= 1;
             self.sample_idx += 1;
@@ -4048,14 +4861,20 @@ impl Voice for SubliminalEncodeVoice {
     fn process(&mut self, output: &mut [f32]) {
         let channels = 2;
         let frames = output.len() / channels;
+        let peak = self.arrangement.peak();
         for i in 0..frames {
             if self.remaining_samples == 0 {
                 break;
             }
-            let sample = self.samples[self.position];
+            let mut sample = self.arrangement.next_sample();
+            if let Some(bed) = &mut self.bed {
+                bed.level = bed.target + (bed.level - bed.target) * bed.coeff;
+                sample *= bed.level;
+            } else if peak > 0.0 {
+                sample = sample / peak * self.amp;
+            }
             output[i * 2] += sample;
-            output[i * 2 + 1] += self.samples[self.position + 1];
-            self.position += 2;
+            output[i * 2 + 1] += sample;
             self.remaining_samples -= 1;
         }
     }
@@ -4065,18 +4884,502 @@ impl Voice for SubliminalEncodeVoice {
     }
 }
 
+/// `seek` for voices that read their place in the step from `sample_idx`.
+macro_rules! seekable {
+    ($($voice:ty),* $(,)?) => {$(
+        impl $voice {
+            fn seek(&mut self, frame: usize) {
+                self.sample_idx += frame;
+                self.remaining_samples = self.remaining_samples.saturating_sub(frame);
+            }
+        }
+    )*};
+}
+
+seekable!(
+    BinauralBeatVoice,
+    BinauralBeatTransitionVoice,
+    IsochronicToneVoice,
+    IsochronicToneTransitionVoice,
+    QamBeatVoice,
+    QamBeatTransitionVoice,
+    StereoAmIndependentVoice,
+    StereoAmIndependentTransitionVoice,
+    WaveShapeStereoAmVoice,
+    WaveShapeStereoAmTransitionVoice,
+    SpatialAngleModulationVoice,
+    SpatialAngleModulationTransitionVoice,
+    RhythmicWaveshapingVoice,
+    RhythmicWaveshapingTransitionVoice,
+);
+
+/// Voices whose fields a modulation route or automation lane can drive.
+/// Only fields read on every sample are listed, so a new value takes effect
+/// at once; phases and state precomputed in `new` are left out.
+trait Modulatable {
+    const FIELDS: &'static [&'static str];
+    /// The field at `index` in `FIELDS`.
+    fn field_mut(&mut self, index: usize) -> Option<&mut f32>;
+}
+
+macro_rules! modulatable {
+    ($voice:ty { $($field:ident),* $(,)? }) => {
+        impl Modulatable for $voice {
+            const FIELDS: &'static [&'static str] = &[$(stringify!($field)),*];
+
+            fn field_mut(&mut self, index: usize) -> Option<&mut f32> {
+                [$(&mut self.$field),*].into_iter().nth(index)
+            }
+        }
+    };
+}
+
+modulatable!(BinauralBeatVoice {
+    amp_l,
+    amp_r,
+    base_freq,
+    beat_freq,
+    amp_osc_depth_l,
+    amp_osc_freq_l,
+    amp_osc_depth_r,
+    amp_osc_freq_r,
+    freq_osc_range_l,
+    freq_osc_freq_l,
+    freq_osc_range_r,
+    freq_osc_freq_r,
+    freq_osc_skew_l,
+    freq_osc_skew_r,
+    freq_osc_phase_offset_l,
+    freq_osc_phase_offset_r,
+    amp_osc_phase_offset_l,
+    amp_osc_phase_offset_r,
+    amp_osc_skew_l,
+    amp_osc_skew_r,
+    phase_osc_freq,
+    phase_osc_range,
+});
+
+modulatable!(BinauralBeatTransitionVoice {
+    start_amp_l,
+    end_amp_l,
+    start_amp_r,
+    end_amp_r,
+    start_base_freq,
+    end_base_freq,
+    start_beat_freq,
+    end_beat_freq,
+    start_phase_osc_freq,
+    end_phase_osc_freq,
+    start_phase_osc_range,
+    end_phase_osc_range,
+    start_amp_osc_depth_l,
+    end_amp_osc_depth_l,
+    start_amp_osc_freq_l,
+    end_amp_osc_freq_l,
+    start_amp_osc_depth_r,
+    end_amp_osc_depth_r,
+    start_amp_osc_freq_r,
+    end_amp_osc_freq_r,
+    start_amp_osc_phase_offset_l,
+    end_amp_osc_phase_offset_l,
+    start_amp_osc_phase_offset_r,
+    end_amp_osc_phase_offset_r,
+    start_freq_osc_range_l,
+    end_freq_osc_range_l,
+    start_freq_osc_freq_l,
+    end_freq_osc_freq_l,
+    start_freq_osc_range_r,
+    end_freq_osc_range_r,
+    start_freq_osc_freq_r,
+    end_freq_osc_freq_r,
+    start_freq_osc_skew_l,
+    end_freq_osc_skew_l,
+    start_freq_osc_skew_r,
+    end_freq_osc_skew_r,
+    start_freq_osc_phase_offset_l,
+    end_freq_osc_phase_offset_l,
+    start_freq_osc_phase_offset_r,
+    end_freq_osc_phase_offset_r,
+    start_amp_osc_skew_l,
+    end_amp_osc_skew_l,
+    start_amp_osc_skew_r,
+    end_amp_osc_skew_r,
+});
+
+modulatable!(IsochronicToneVoice {
+    amp_l,
+    amp_r,
+    base_freq,
+    beat_freq,
+    amp_osc_depth_l,
+    amp_osc_freq_l,
+    amp_osc_depth_r,
+    amp_osc_freq_r,
+    freq_osc_range_l,
+    freq_osc_freq_l,
+    freq_osc_range_r,
+    freq_osc_freq_r,
+    freq_osc_skew_l,
+    freq_osc_skew_r,
+    freq_osc_phase_offset_l,
+    freq_osc_phase_offset_r,
+    amp_osc_phase_offset_l,
+    amp_osc_phase_offset_r,
+    amp_osc_skew_l,
+    amp_osc_skew_r,
+    phase_osc_freq,
+    phase_osc_range,
+    ramp_percent,
+    gap_percent,
+    pan_range_min,
+    pan_range_max,
+    pan_freq,
+    pan_phase,
+});
+
+modulatable!(IsochronicToneTransitionVoice {
+    start_amp_l,
+    end_amp_l,
+    start_amp_r,
+    end_amp_r,
+    start_base_freq,
+    end_base_freq,
+    start_beat_freq,
+    end_beat_freq,
+    start_phase_osc_freq,
+    end_phase_osc_freq,
+    start_phase_osc_range,
+    end_phase_osc_range,
+    start_amp_osc_depth_l,
+    end_amp_osc_depth_l,
+    start_amp_osc_freq_l,
+    end_amp_osc_freq_l,
+    start_amp_osc_depth_r,
+    end_amp_osc_depth_r,
+    start_amp_osc_freq_r,
+    end_amp_osc_freq_r,
+    start_amp_osc_phase_offset_l,
+    end_amp_osc_phase_offset_l,
+    start_amp_osc_phase_offset_r,
+    end_amp_osc_phase_offset_r,
+    start_freq_osc_range_l,
+    end_freq_osc_range_l,
+    start_freq_osc_freq_l,
+    end_freq_osc_freq_l,
+    start_freq_osc_range_r,
+    end_freq_osc_range_r,
+    start_freq_osc_freq_r,
+    end_freq_osc_freq_r,
+    start_freq_osc_skew_l,
+    end_freq_osc_skew_l,
+    start_freq_osc_skew_r,
+    end_freq_osc_skew_r,
+    start_freq_osc_phase_offset_l,
+    end_freq_osc_phase_offset_l,
+    start_freq_osc_phase_offset_r,
+    end_freq_osc_phase_offset_r,
+    start_amp_osc_skew_l,
+    end_amp_osc_skew_l,
+    start_amp_osc_skew_r,
+    end_amp_osc_skew_r,
+    start_ramp_percent,
+    end_ramp_percent,
+    start_gap_percent,
+    end_gap_percent,
+    start_pan_range_min,
+    end_pan_range_min,
+    start_pan_range_max,
+    end_pan_range_max,
+    start_pan_freq,
+    end_pan_freq,
+    start_pan_phase,
+    end_pan_phase,
+});
+
+modulatable!(QamBeatVoice {
+    amp_l,
+    amp_r,
+    base_freq_l,
+    base_freq_r,
+    qam_am_freq_l,
+    qam_am_depth_l,
+    qam_am_phase_offset_l,
+    qam_am_freq_r,
+    qam_am_depth_r,
+    qam_am_phase_offset_r,
+    qam_am2_freq_l,
+    qam_am2_depth_l,
+    qam_am2_phase_offset_l,
+    qam_am2_freq_r,
+    qam_am2_depth_r,
+    qam_am2_phase_offset_r,
+    mod_shape_l,
+    mod_shape_r,
+    cross_mod_depth,
+    harmonic_depth,
+    harmonic_ratio,
+    sub_harmonic_freq,
+    sub_harmonic_depth,
+    phase_osc_freq,
+    phase_osc_range,
+    phase_osc_phase_offset,
+    sideband_offset,
+    sideband_depth,
+});
+
+modulatable!(QamBeatTransitionVoice {
+    start_amp_l,
+    end_amp_l,
+    start_amp_r,
+    end_amp_r,
+    start_base_freq_l,
+    end_base_freq_l,
+    start_base_freq_r,
+    end_base_freq_r,
+    start_qam_am_freq_l,
+    end_qam_am_freq_l,
+    start_qam_am_depth_l,
+    end_qam_am_depth_l,
+    start_qam_am_freq_r,
+    end_qam_am_freq_r,
+    start_qam_am_depth_r,
+    end_qam_am_depth_r,
+    start_qam_am_phase_offset_l,
+    end_qam_am_phase_offset_l,
+    start_qam_am_phase_offset_r,
+    end_qam_am_phase_offset_r,
+    start_qam_am2_freq_l,
+    end_qam_am2_freq_l,
+    start_qam_am2_depth_l,
+    end_qam_am2_depth_l,
+    start_qam_am2_freq_r,
+    end_qam_am2_freq_r,
+    start_qam_am2_depth_r,
+    end_qam_am2_depth_r,
+    start_qam_am2_phase_offset_l,
+    end_qam_am2_phase_offset_l,
+    start_qam_am2_phase_offset_r,
+    end_qam_am2_phase_offset_r,
+    start_mod_shape_l,
+    end_mod_shape_l,
+    start_mod_shape_r,
+    end_mod_shape_r,
+    start_cross_mod_depth,
+    end_cross_mod_depth,
+    harmonic_ratio,
+    start_harmonic_depth,
+    end_harmonic_depth,
+    start_sub_harmonic_freq,
+    end_sub_harmonic_freq,
+    start_sub_harmonic_depth,
+    end_sub_harmonic_depth,
+    start_phase_osc_freq,
+    end_phase_osc_freq,
+    start_phase_osc_range,
+    end_phase_osc_range,
+    phase_osc_phase_offset,
+    sideband_offset,
+    sideband_depth,
+});
+
+modulatable!(StereoAmIndependentVoice {
+    amp,
+    carrier_freq,
+    stereo_width_hz,
+    mod_freq_l,
+    mod_depth_l,
+    mod_freq_r,
+    mod_depth_r,
+});
+
+modulatable!(StereoAmIndependentTransitionVoice {
+    amp,
+    start_carrier_freq,
+    end_carrier_freq,
+    start_stereo_width_hz,
+    end_stereo_width_hz,
+    start_mod_freq_l,
+    end_mod_freq_l,
+    start_mod_depth_l,
+    end_mod_depth_l,
+    start_mod_freq_r,
+    end_mod_freq_r,
+    start_mod_depth_r,
+    end_mod_depth_r,
+});
+
+modulatable!(WaveShapeStereoAmVoice {
+    amp,
+    carrier_freq,
+    shape_mod_freq,
+    shape_mod_depth,
+    shape_amount,
+    stereo_mod_freq_l,
+    stereo_mod_depth_l,
+    stereo_mod_freq_r,
+    stereo_mod_depth_r,
+});
+
+modulatable!(WaveShapeStereoAmTransitionVoice {
+    amp,
+    start_carrier_freq,
+    end_carrier_freq,
+    start_shape_mod_freq,
+    end_shape_mod_freq,
+    start_shape_mod_depth,
+    end_shape_mod_depth,
+    start_shape_amount,
+    end_shape_amount,
+    start_stereo_mod_freq_l,
+    end_stereo_mod_freq_l,
+    start_stereo_mod_depth_l,
+    end_stereo_mod_depth_l,
+    start_stereo_mod_freq_r,
+    end_stereo_mod_freq_r,
+    start_stereo_mod_depth_r,
+    end_stereo_mod_depth_r,
+});
+
+modulatable!(SpatialAngleModulationVoice {
+    amp,
+    carrier_freq,
+    beat_freq,
+    path_radius,
+});
+
+modulatable!(SpatialAngleModulationTransitionVoice {
+    amp,
+    start_carrier_freq,
+    end_carrier_freq,
+    start_beat_freq,
+    end_beat_freq,
+    start_path_radius,
+    end_path_radius,
+});
+
+modulatable!(RhythmicWaveshapingVoice {
+    amp,
+    carrier_freq,
+    mod_freq,
+    mod_depth,
+    shape_amount,
+    pan,
+});
+
+modulatable!(RhythmicWaveshapingTransitionVoice {
+    amp,
+    start_carrier_freq,
+    end_carrier_freq,
+    start_mod_freq,
+    end_mod_freq,
+    start_mod_depth,
+    end_mod_depth,
+    start_shape_amount,
+    end_shape_amount,
+    pan,
+});
+
+modulatable!(NoiseSweptNotchVoice { amp });
+
+modulatable!(NoiseSweptNotchTransitionVoice { amp });
+
+modulatable!(BinauralChordVoice {
+    amp_l,
+    amp_r,
+    base_freq,
+    beat_freq,
+});
+
+impl Modulatable for MonauralBeatVoice {
+    const FIELDS: &'static [&'static str] = BinauralBeatVoice::FIELDS;
+
+    fn field_mut(&mut self, index: usize) -> Option<&mut f32> {
+        self.inner.field_mut(index)
+    }
+}
+
+impl Modulatable for MonauralBeatTransitionVoice {
+    const FIELDS: &'static [&'static str] = BinauralBeatTransitionVoice::FIELDS;
+
+    fn field_mut(&mut self, index: usize) -> Option<&mut f32> {
+        self.inner.field_mut(index)
+    }
+}
+
+/// Fields of the voice `synth_function_name` builds that modulation can drive.
+fn modulatable_fields(synth_function_name: &str) -> &'static [&'static str] {
+    match synth_function_name {
+        "binaural_beat" | "monaural_beat" => BinauralBeatVoice::FIELDS,
+        "binaural_beat_transition" | "monaural_beat_transition" => {
+            BinauralBeatTransitionVoice::FIELDS
+        }
+        "binaural_chord" => BinauralChordVoice::FIELDS,
+        "isochronic_tone" => IsochronicToneVoice::FIELDS,
+        "isochronic_tone_transition" => IsochronicToneTransitionVoice::FIELDS,
+        "qam_beat" => QamBeatVoice::FIELDS,
+        "qam_beat_transition" => QamBeatTransitionVoice::FIELDS,
+        "stereo_am_independent" => StereoAmIndependentVoice::FIELDS,
+        "stereo_am_independent_transition" => StereoAmIndependentTransitionVoice::FIELDS,
+        "wave_shape_stereo_am" => WaveShapeStereoAmVoice::FIELDS,
+        "wave_shape_stereo_am_transition" => WaveShapeStereoAmTransitionVoice::FIELDS,
+        "spatial_angle_modulation" => SpatialAngleModulationVoice::FIELDS,
+        "spatial_angle_modulation_transition" => SpatialAngleModulationTransitionVoice::FIELDS,
+        "rhythmic_waveshaping" => RhythmicWaveshapingVoice::FIELDS,
+        "rhythmic_waveshaping_transition" => RhythmicWaveshapingTransitionVoice::FIELDS,
+        "noise_swept_notch" => NoiseSweptNotchVoice::FIELDS,
+        "noise_swept_notch_transition" => NoiseSweptNotchTransitionVoice::FIELDS,
+        _ => &[],
+    }
+}
+
 impl VoiceKind {
+    /// The modulatable field at `index` in the voice's field list, if any.
+    fn field_mut(&mut self, index: usize) -> Option<&mut f32> {
+        match self {
+            VoiceKind::BinauralBeat(v) => v.field_mut(index),
+            VoiceKind::BinauralBeatTransition(v) => v.field_mut(index),
+            VoiceKind::MonauralBeat(v) => v.field_mut(index),
+            VoiceKind::MonauralBeatTransition(v) => v.field_mut(index),
+            VoiceKind::BinauralChord(v) => v.field_mut(index),
+            VoiceKind::IsochronicTone(v) => v.field_mut(index),
+            VoiceKind::IsochronicToneTransition(v) => v.field_mut(index),
+            VoiceKind::QamBeat(v) => v.field_mut(index),
+            VoiceKind::QamBeatTransition(v) => v.field_mut(index),
+            VoiceKind::StereoAmIndependent(v) => v.field_mut(index),
+            VoiceKind::StereoAmIndependentTransition(v) => v.field_mut(index),
+            VoiceKind::WaveShapeStereoAm(v) => v.field_mut(index),
+            VoiceKind::WaveShapeStereoAmTransition(v) => v.field_mut(index),
+            VoiceKind::SpatialAngleModulation(v) => v.field_mut(index),
+            VoiceKind::SpatialAngleModulationTransition(v) => v.field_mut(index),
+            VoiceKind::RhythmicWaveshaping(v) => v.field_mut(index),
+            VoiceKind::RhythmicWaveshapingTransition(v) => v.field_mut(index),
+            VoiceKind::SubliminalEncode(_) => None,
+            VoiceKind::VolumeEnvelope(v) => v.inner.field_mut(index),
+            VoiceKind::Spatial(v) => v.inner.field_mut(index),
+            VoiceKind::Modulated(v) => v.inner.field_mut(index),
+            VoiceKind::NoiseSweptNotch(v) => v.field_mut(index),
+            VoiceKind::NoiseSweptNotchTransition(v) => v.field_mut(index),
+            VoiceKind::Custom(_) => None,
+        }
+    }
+
     /// Returns the current accumulated phases (phase_l, phase_r) for voices that track stereo phase.
     /// This is used to maintain phase continuity when transitioning between voice instances.
     pub fn get_phases(&self) -> Option<(f32, f32)> {
         match self {
             VoiceKind::BinauralBeat(v) => Some((v.phase_l, v.phase_r)),
             VoiceKind::BinauralBeatTransition(v) => Some((v.phase_l, v.phase_r)),
+            VoiceKind::MonauralBeat(v) => Some((v.inner.phase_l, v.inner.phase_r)),
+            VoiceKind::MonauralBeatTransition(v) => Some((v.inner.phase_l, v.inner.phase_r)),
+            VoiceKind::BinauralChord(v) => v.partials.first().map(|p| (p.phase_l, p.phase_r)),
             VoiceKind::IsochronicTone(v) => Some((v.phase_l, v.phase_r)),
             VoiceKind::IsochronicToneTransition(v) => Some((v.phase_l, v.phase_r)),
             VoiceKind::QamBeat(v) => Some((v.phase_l, v.phase_r)),
             VoiceKind::QamBeatTransition(v) => Some((v.phase_l, v.phase_r)),
             VoiceKind::VolumeEnvelope(v) => v.inner.get_phases(),
+            VoiceKind::Spatial(v) => v.inner.get_phases(),
+            VoiceKind::Modulated(v) => v.inner.get_phases(),
+            VoiceKind::Custom(v) => v.phases(),
             // Other voice types don't track stereo carrier phases
             _ => None,
         }
@@ -4095,6 +5398,20 @@ impl VoiceKind {
                 v.phase_l = phase_l;
                 v.phase_r = phase_r;
             }
+            VoiceKind::MonauralBeat(v) => {
+                v.inner.phase_l = phase_l;
+                v.inner.phase_r = phase_r;
+            }
+            VoiceKind::MonauralBeatTransition(v) => {
+                v.inner.phase_l = phase_l;
+                v.inner.phase_r = phase_r;
+            }
+            VoiceKind::BinauralChord(v) => {
+                if let Some(p) = v.partials.first_mut() {
+                    p.phase_l = phase_l;
+                    p.phase_r = phase_r;
+                }
+            }
             VoiceKind::IsochronicTone(v) => {
                 v.phase_l = phase_l;
                 v.phase_r = phase_r;
@@ -4114,10 +5431,137 @@ impl VoiceKind {
             VoiceKind::VolumeEnvelope(v) => {
                 v.inner.set_phases(phase_l, phase_r);
             }
+            VoiceKind::Spatial(v) => {
+                v.inner.set_phases(phase_l, phase_r);
+            }
+            VoiceKind::Modulated(v) => {
+                v.inner.set_phases(phase_l, phase_r);
+            }
+            VoiceKind::Custom(v) => v.set_phases(phase_l, phase_r),
             // Other voice types don't track stereo carrier phases
             _ => {}
         }
     }
+
+    /// Carrier phases of every partial, one pair each: a single pair for
+    /// one-carrier voices, none for voices that don't track phase.
+    pub fn partial_phases(&self) -> Vec<(f32, f32)> {
+        match self {
+            VoiceKind::BinauralChord(v) => {
+                v.partials.iter().map(|p| (p.phase_l, p.phase_r)).collect()
+            }
+            VoiceKind::VolumeEnvelope(v) => v.inner.partial_phases(),
+            VoiceKind::Spatial(v) => v.inner.partial_phases(),
+            VoiceKind::Modulated(v) => v.inner.partial_phases(),
+            _ => self.get_phases().into_iter().collect(),
+        }
+    }
+
+    /// Restore partial phases from the front of `phases`, as returned by
+    /// [`Self::partial_phases`]. Returns how many pairs were used.
+    pub fn set_partial_phases(&mut self, phases: &[(f32, f32)]) -> usize {
+        match self {
+            VoiceKind::BinauralChord(v) => {
+                for (p, &(phase_l, phase_r)) in v.partials.iter_mut().zip(phases) {
+                    p.phase_l = phase_l;
+                    p.phase_r = phase_r;
+                }
+                v.partials.len().min(phases.len())
+            }
+            VoiceKind::VolumeEnvelope(v) => v.inner.set_partial_phases(phases),
+            VoiceKind::Spatial(v) => v.inner.set_partial_phases(phases),
+            VoiceKind::Modulated(v) => v.inner.set_partial_phases(phases),
+            _ => match (self.get_phases(), phases.first()) {
+                (Some(_), Some(&(phase_l, phase_r))) => {
+                    self.set_phases(phase_l, phase_r);
+                    1
+                }
+                _ => 0,
+            },
+        }
+    }
+
+    /// Move a voice just built to `frame` frames into the step, for voices
+    /// started part way through it: transition sweeps, envelopes, modulation,
+    /// spatial paths and subliminal clips pick up where they would be. Carrier,
+    /// pan and noise phases run freely and carry on from where they are.
+    pub fn seek(&mut self, frame: usize) {
+        match self {
+            VoiceKind::BinauralBeat(v) => v.seek(frame),
+            VoiceKind::BinauralBeatTransition(v) => v.seek(frame),
+            VoiceKind::MonauralBeat(v) => v.inner.seek(frame),
+            VoiceKind::MonauralBeatTransition(v) => v.inner.seek(frame),
+            VoiceKind::BinauralChord(v) => {
+                v.remaining_samples = v.remaining_samples.saturating_sub(frame);
+            }
+            VoiceKind::IsochronicTone(v) => v.seek(frame),
+            VoiceKind::IsochronicToneTransition(v) => v.seek(frame),
+            VoiceKind::QamBeat(v) => v.seek(frame),
+            VoiceKind::QamBeatTransition(v) => v.seek(frame),
+            VoiceKind::StereoAmIndependent(v) => v.seek(frame),
+            VoiceKind::StereoAmIndependentTransition(v) => v.seek(frame),
+            VoiceKind::WaveShapeStereoAm(v) => v.seek(frame),
+            VoiceKind::WaveShapeStereoAmTransition(v) => v.seek(frame),
+            VoiceKind::SpatialAngleModulation(v) => v.seek(frame),
+            VoiceKind::SpatialAngleModulationTransition(v) => v.seek(frame),
+            VoiceKind::RhythmicWaveshaping(v) => v.seek(frame),
+            VoiceKind::RhythmicWaveshapingTransition(v) => v.seek(frame),
+            VoiceKind::SubliminalEncode(v) => {
+                v.arrangement.seek(frame);
+                v.remaining_samples = v.remaining_samples.saturating_sub(frame);
+            }
+            VoiceKind::NoiseSweptNotch(v) => {
+                v.remaining_samples = v.remaining_samples.saturating_sub(frame);
+            }
+            VoiceKind::NoiseSweptNotchTransition(v) => {
+                v.generator.skip_clock(frame);
+                v.sample_idx += frame;
+                v.remaining_samples = v.remaining_samples.saturating_sub(frame);
+            }
+            VoiceKind::VolumeEnvelope(v) => {
+                v.seek(frame);
+                v.inner.seek(frame);
+            }
+            VoiceKind::Spatial(v) => {
+                v.spatializer.seek(frame);
+                v.inner.seek(frame);
+            }
+            VoiceKind::Modulated(v) => {
+                v.matrix.seek(frame, v.sample_rate);
+                v.elapsed_samples += frame;
+                v.inner.seek(frame);
+            }
+            VoiceKind::Custom(v) => v.seek(frame),
+        }
+    }
+
+    /// Whether this is a masked subliminal, which the scheduler mixes under
+    /// the noise bed rather than on the binaural bus.
+    pub fn is_masked(&self) -> bool {
+        match self {
+            VoiceKind::SubliminalEncode(v) => v.bed.is_some(),
+            VoiceKind::VolumeEnvelope(v) => v.inner.is_masked(),
+            VoiceKind::Spatial(v) => v.inner.is_masked(),
+            VoiceKind::Modulated(v) => v.inner.is_masked(),
+            _ => false,
+        }
+    }
+
+    /// Set the RMS of the noise bed a masked subliminal plays under. The
+    /// voice glides to it rather than jumping.
+    pub fn follow_noise_bed(&mut self, rms: f32) {
+        match self {
+            VoiceKind::SubliminalEncode(v) => {
+                if let Some(bed) = &mut v.bed {
+                    bed.target = rms;
+                }
+            }
+            VoiceKind::VolumeEnvelope(v) => v.inner.follow_noise_bed(rms),
+            VoiceKind::Spatial(v) => v.inner.follow_noise_bed(rms),
+            VoiceKind::Modulated(v) => v.inner.follow_noise_bed(rms),
+            _ => {}
+        }
+    }
 }
 
 impl Voice for VoiceKind {
@@ -4125,6 +5569,9 @@ impl Voice for VoiceKind {
         match self {
             VoiceKind::BinauralBeat(v) => v.process(output),
             VoiceKind::BinauralBeatTransition(v) => v.process(output),
+            VoiceKind::MonauralBeat(v) => v.process(output),
+            VoiceKind::MonauralBeatTransition(v) => v.process(output),
+            VoiceKind::BinauralChord(v) => v.process(output),
             VoiceKind::IsochronicTone(v) => v.process(output),
             VoiceKind::IsochronicToneTransition(v) => v.process(output),
             VoiceKind::QamBeat(v) => v.process(output),
@@ -4139,8 +5586,11 @@ impl Voice for VoiceKind {
             VoiceKind::RhythmicWaveshapingTransition(v) => v.process(output),
             VoiceKind::SubliminalEncode(v) => v.process(output),
             VoiceKind::VolumeEnvelope(v) => v.process(output),
+            VoiceKind::Spatial(v) => v.process(output),
+            VoiceKind::Modulated(v) => v.process(output),
             VoiceKind::NoiseSweptNotch(v) => v.process(output),
             VoiceKind::NoiseSweptNotchTransition(v) => v.process(output),
+            VoiceKind::Custom(v) => v.process(output),
         }
     }
 
@@ -4148,6 +5598,9 @@ impl Voice for VoiceKind {
         match self {
             VoiceKind::BinauralBeat(v) => v.is_finished(),
             VoiceKind::BinauralBeatTransition(v) => v.is_finished(),
+            VoiceKind::MonauralBeat(v) => v.is_finished(),
+            VoiceKind::MonauralBeatTransition(v) => v.is_finished(),
+            VoiceKind::BinauralChord(v) => v.is_finished(),
             VoiceKind::IsochronicTone(v) => v.is_finished(),
             VoiceKind::IsochronicToneTransition(v) => v.is_finished(),
             VoiceKind::QamBeat(v) => v.is_finished(),
@@ -4162,12 +5615,917 @@ impl Voice for VoiceKind {
             VoiceKind::RhythmicWaveshapingTransition(v) => v.is_finished(),
             VoiceKind::SubliminalEncode(v) => v.is_finished(),
             VoiceKind::VolumeEnvelope(v) => v.is_finished(),
+            VoiceKind::Spatial(v) => v.is_finished(),
+            VoiceKind::Modulated(v) => v.is_finished(),
             VoiceKind::NoiseSweptNotch(v) => v.is_finished(),
             VoiceKind::NoiseSweptNotchTransition(v) => v.is_finished(),
+            VoiceKind::Custom(v) => v.is_finished(),
         }
     }
 }
 
+/// Value type of a synth parameter as it appears in a voice's JSON params.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParamType {
+    Float,
+    Int,
+    Bool,
+    /// One of [`ParamSpec::choices`].
+    Choice,
+    /// Free text such as a file path.
+    Text,
+    /// Structured JSON (arrays or objects), e.g. noise sweeps.
+    Json,
+}
+
+/// Description of a parameter read by a voice constructor.
+#[derive(Clone, Debug)]
+pub struct ParamSpec {
+    pub name: &'static str,
+    pub param_type: ParamType,
+    /// Value used when the key is absent. `Value::Null` means the voice
+    /// derives it (e.g. from the noise colour).
+    pub default: Value,
+    pub unit: &'static str,
+    /// Sensible editing range for numeric params. Values outside it are
+    /// accepted by the voices.
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub choices: &'static [&'static str],
+    /// Other keys read for the same value.
+    pub aliases: &'static [&'static str],
+    /// Keys holding the start and end values in a `_transition` voice. The
+    /// end key falls back to the start value; whether the start key falls
+    /// back to `name` is up to the voice. A value kept from the start for
+    /// the whole transition has no end key.
+    pub start_key: Option<String>,
+    pub end_key: Option<String>,
+    /// Default of `end_key` when it does not simply follow the start value.
+    pub end_default: Option<Value>,
+}
+
+impl ParamSpec {
+    fn new(name: &'static str, param_type: ParamType, default: Value) -> Self {
+        Self {
+            name,
+            param_type,
+            default,
+            unit: "",
+            min: None,
+            max: None,
+            choices: &[],
+            aliases: &[],
+            start_key: None,
+            end_key: None,
+            end_default: None,
+        }
+    }
+
+    fn float(name: &'static str, default: f64, unit: &'static str, min: f64, max: f64) -> Self {
+        Self {
+            unit,
+            min: Some(min),
+            max: Some(max),
+            ..Self::new(name, ParamType::Float, Value::from(default))
+        }
+    }
+
+    fn int(name: &'static str, min: f64, max: f64) -> Self {
+        Self {
+            min: Some(min),
+            max: Some(max),
+            ..Self::new(name, ParamType::Int, Value::Null)
+        }
+    }
+
+    fn boolean(name: &'static str, default: bool) -> Self {
+        Self::new(name, ParamType::Bool, Value::Bool(default))
+    }
+
+    fn choice(name: &'static str, default: &str, choices: &'static [&'static str]) -> Self {
+        Self {
+            choices,
+            ..Self::new(name, ParamType::Choice, Value::from(default))
+        }
+    }
+
+    fn text(name: &'static str) -> Self {
+        Self::new(name, ParamType::Text, Value::from(""))
+    }
+
+    fn json(name: &'static str) -> Self {
+        Self::new(name, ParamType::Json, Value::Null)
+    }
+
+    /// Default left to the voice.
+    fn optional(mut self) -> Self {
+        self.default = Value::Null;
+        self
+    }
+
+    fn aliases(mut self, aliases: &'static [&'static str]) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// `baseFreq` -> `startBaseFreq` / `endBaseFreq`.
+    fn transition(mut self) -> Self {
+        let mut chars = self.name.chars();
+        let first = chars.next().map(|c| c.to_ascii_uppercase());
+        let rest = chars.as_str();
+        if let Some(first) = first {
+            self.start_key = Some(format!("start{first}{rest}"));
+            self.end_key = Some(format!("end{first}{rest}"));
+        }
+        self
+    }
+
+    /// `lfo_freq` -> `start_lfo_freq` / `end_lfo_freq`.
+    fn transition_snake(mut self) -> Self {
+        self.start_key = Some(format!("start_{}", self.name));
+        self.end_key = Some(format!("end_{}", self.name));
+        self
+    }
+
+    fn end_default(mut self, default: f64) -> Self {
+        self.end_default = Some(Value::from(default));
+        self
+    }
+
+    /// Every JSON key this parameter is read from.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.name)
+            .chain(self.aliases.iter().copied())
+            .chain(self.start_key.as_deref())
+            .chain(self.end_key.as_deref())
+    }
+}
+
+/// Parameters understood by one synth function.
+#[derive(Clone, Debug)]
+pub struct SynthSpec {
+    pub name: &'static str,
+    /// Other names `create_voice` accepts for this synth.
+    pub aliases: &'static [&'static str],
+    pub is_transition: bool,
+    pub params: Vec<ParamSpec>,
+    /// Voice fields a modulation route can drive (see [`Self::modulation_fields`]).
+    pub modulatable: &'static [&'static str],
+}
+
+impl SynthSpec {
+    fn new(name: &'static str, params: Vec<ParamSpec>) -> Self {
+        Self {
+            name,
+            aliases: &[],
+            is_transition: name.ends_with("_transition"),
+            params,
+            modulatable: modulatable_fields(name),
+        }
+    }
+
+    fn with(mut self, params: Vec<ParamSpec>) -> Self {
+        self.params.extend(params);
+        self
+    }
+
+    fn aliases(mut self, aliases: &'static [&'static str]) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    pub fn param(&self, name: &str) -> Option<&ParamSpec> {
+        self.params.iter().find(|p| p.name == name)
+    }
+
+    /// Indices into [`Self::modulatable`] of the fields a modulation route
+    /// or automation lane targeting params `key` drives: the key's own
+    /// field, or both the start and end fields of a transition param. Empty
+    /// when the param cannot be modulated.
+    pub fn modulation_fields(&self, key: &str) -> Vec<usize> {
+        let field = param_field_name(key);
+        let find = |name: &str| self.modulatable.iter().position(|f| *f == name);
+        if let Some(f) = find(&field) {
+            return vec![f];
+        }
+        match (
+            find(&format!("start_{field}")),
+            find(&format!("end_{field}")),
+        ) {
+            (Some(start), Some(end)) => vec![start, end],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Every params key the voice reads, including start/end variants.
+    pub fn param_keys(&self) -> impl Iterator<Item = &str> {
+        self.params.iter().flat_map(ParamSpec::keys)
+    }
+
+    /// A params object holding every default, with transition params split
+    /// into their start and end keys. Params without a default are omitted.
+    pub fn default_params(&self) -> serde_json::Map<String, Value> {
+        let mut out = serde_json::Map::new();
+        for p in self.params.iter().filter(|p| !p.default.is_null()) {
+            match (&p.start_key, &p.end_key) {
+                (Some(start), Some(end)) => {
+                    out.insert(start.clone(), p.default.clone());
+                    let end_value = p.end_default.clone().unwrap_or_else(|| p.default.clone());
+                    out.insert(end.clone(), end_value);
+                }
+                _ => {
+                    out.insert(p.name.to_string(), p.default.clone());
+                }
+            }
+        }
+        out
+    }
+}
+
+const LFO_SHAPES: &[&str] = &["sine", "triangle"];
+const CARRIER_WAVEFORMS: &[&str] = &["sine", "triangle", "square", "saw", "wavetable"];
+const PARTIAL_MODES: &[&str] = &["harmonics", "chord"];
+/// Just major triad.
+const DEFAULT_CHORD_RATIOS: &[f32] = &[1.0, 1.25, 1.5];
+const DEFAULT_HARMONIC_COUNT: usize = 5;
+const MAX_CHORD_PARTIALS: usize = 32;
+/// Share of the band, below Nyquist, over which chord partials fade out.
+const CHORD_NYQUIST_FADE: f32 = 0.2;
+const TRANSITION_CURVES: &[&str] = &["linear", "logarithmic", "exponential"];
+const NOISE_TYPES: &[&str] = &[
+    "white",
+    "pink",
+    "brown",
+    "red",
+    "green",
+    "blue",
+    "purple",
+    "deep brown",
+];
+
+/// Voice struct field holding a params key: `ampOscDepthL` -> `amp_osc_depth_l`.
+fn param_field_name(key: &str) -> String {
+    let mut field = String::with_capacity(key.len() + 4);
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            if !field.is_empty() {
+                field.push('_');
+            }
+            field.push(c.to_ascii_lowercase());
+        } else {
+            field.push(c);
+        }
+    }
+    field
+}
+
+/// Shortest decimal form of an `f32` default, so 0.12 is reported as 0.12
+/// rather than 0.11999999731779099.
+fn f32_default(v: f32) -> f64 {
+    v.to_string().parse().unwrap_or(v as f64)
+}
+
+fn map_transition(params: Vec<ParamSpec>, transition: bool) -> Vec<ParamSpec> {
+    if transition {
+        params.into_iter().map(ParamSpec::transition).collect()
+    } else {
+        params
+    }
+}
+
+/// The `flange*` keys read by `flanger_params_from_json`.
+fn flanger_param_specs(transition: bool) -> Vec<ParamSpec> {
+    let d = FlangerParams::default();
+    let t = d.targets;
+    let ms = |name, v: f32, max| ParamSpec::float(name, f32_default(v), "ms", 0.0, max);
+    map_transition(
+        vec![
+            ParamSpec::boolean("flangeEnable", false),
+            ParamSpec::choice("flangeShape", "sine", LFO_SHAPES),
+            ParamSpec::float("flangeRateHz", f32_default(t.rate_hz), "Hz", 0.0, 5.0),
+            ms("flangeDelayMs", t.delay_ms, 10.0),
+            ms("flangeDepthMs", t.depth_ms, 10.0),
+            ParamSpec::float("flangeFeedback", f32_default(t.feedback), "", -0.95, 0.95),
+            ParamSpec::float("flangeMix", f32_default(t.mix), "", 0.0, 1.0),
+            ParamSpec::float(
+                "flangeLoopHpfHz",
+                f32_default(t.loop_hpf_hz),
+                "Hz",
+                0.0,
+                2000.0,
+            ),
+            ParamSpec::float(
+                "flangeLoopLpfHz",
+                f32_default(t.loop_lpf_hz),
+                "Hz",
+                500.0,
+                20000.0,
+            ),
+            ms("flangeMinDelayMs", d.min_delay_ms, 10.0),
+            ms("flangeMaxDelayMs", d.max_delay_ms, 20.0),
+            ParamSpec::boolean("flangeDelayLaw", d.delay_law),
+            ParamSpec::boolean("flangeInterp", d.interp),
+            ParamSpec::boolean("flangeStereoMode", d.stereo_mode),
+            ParamSpec::float(
+                "flangeSpreadDeg",
+                f32_default(d.spread_deg),
+                "deg",
+                0.0,
+                180.0,
+            ),
+            ParamSpec::boolean("flangeLoudnessMode", d.loudness_mode),
+            ms("flangeLoudnessTcMs", d.loudness_tc_ms, 1000.0),
+            ParamSpec::float(
+                "flangeLoudnessMinGain",
+                f32_default(d.loudness_min_gain),
+                "",
+                0.0,
+                1.0,
+            ),
+            ParamSpec::float(
+                "flangeLoudnessMaxGain",
+                f32_default(d.loudness_max_gain),
+                "",
+                1.0,
+                4.0,
+            ),
+            ms("flangeDezipperRateMs", d.dezipper_rate_ms, 1000.0),
+            ms("flangeDezipperDelayMs", d.dezipper_delay_ms, 1000.0),
+            ms("flangeDezipperDepthMs", d.dezipper_depth_ms, 1000.0),
+            ms("flangeDezipperFeedbackMs", d.dezipper_feedback_ms, 1000.0),
+            ms("flangeDezipperWetMs", d.dezipper_wet_ms, 1000.0),
+            ms("flangeDezipperFilterMs", d.dezipper_filter_ms, 1000.0),
+        ],
+        transition,
+    )
+}
+
+/// The `pan*` keys read by `AutoPan::from_params` and `AutoPan::end_from_params`.
+fn pan_param_specs(transition: bool) -> Vec<ParamSpec> {
+    let mut specs = map_transition(
+        vec![
+            ParamSpec::float("pan", 0.0, "", -1.0, 1.0),
+            ParamSpec::float("panFreq", 0.0, "Hz", 0.0, 10.0),
+            ParamSpec::float("panRangeMin", 0.0, "", -1.0, 1.0),
+            ParamSpec::float("panRangeMax", 0.0, "", -1.0, 1.0),
+            ParamSpec::float("panPhase", 0.0, "rad", 0.0, TAU),
+        ],
+        transition,
+    );
+    // A transition keeps its start's pan law throughout.
+    let mut pan_type = ParamSpec::choice(
+        "panType",
+        "linear",
+        &["linear", "equal_power", "constant_power"],
+    );
+    if transition {
+        pan_type.start_key = Some("startPanType".to_string());
+    }
+    specs.insert(1, pan_type);
+    specs
+}
+
+/// The keys read by `carrier_from_params`.
+fn carrier_param_specs() -> Vec<ParamSpec> {
+    vec![
+        ParamSpec::choice("carrierWaveform", "sine", CARRIER_WAVEFORMS),
+        ParamSpec::json("carrierWavetable"),
+    ]
+}
+
+/// The `spatial*` keys read by `spatial_params_from_json`; every voice can be
+/// spatialized.
+fn spatial_param_specs() -> Vec<ParamSpec> {
+    let d = SpatialParams::default();
+    let num = |name, v: f32, unit, min, max| ParamSpec::float(name, f32_default(v), unit, min, max);
+    vec![
+        ParamSpec::boolean("spatialEnable", false),
+        ParamSpec::json("spatialTrajectory"),
+        num("spatialAzimuthDeg", d.azimuth_deg, "deg", -180.0, 180.0),
+        num("spatialDistanceM", d.distance_m, "m", 0.1, 20.0),
+        ParamSpec::choice(
+            "spatialDecoder",
+            "itd_head",
+            &["itd_head", "pan", "equal_power"],
+        ),
+        ParamSpec::boolean("spatialUseItdIld", d.use_itd_ild),
+        num("spatialHeadRadiusM", d.head_radius_m, "m", 0.05, 0.15),
+        num("spatialIldMaxDb", d.ild_max_db, "dB", 0.0, 12.0),
+        num("spatialIldXoverHz", d.ild_xover_hz, "Hz", 200.0, 2000.0),
+        num("spatialItdScale", d.itd_scale, "", 0.0, 2.0),
+        num("spatialEarAngleDeg", d.ear_angle_deg, "deg", 0.0, 90.0),
+        num("spatialMaxDegPerS", d.max_deg_per_s, "deg/s", 1.0, 720.0),
+        num(
+            "spatialMaxDelayStepSamples",
+            d.max_delay_step_samples,
+            "samples",
+            0.001,
+            1.0,
+        ),
+        num("spatialMinDistanceM", d.min_distance_m, "m", 0.01, 1.0),
+        num("spatialRefDistanceM", d.ref_distance_m, "m", 0.1, 10.0),
+        num("spatialRolloff", d.rolloff, "", 0.0, 4.0),
+        num("spatialHfRollDbPerM", d.hf_roll_db_per_m, "dB/m", 0.0, 6.0),
+        num(
+            "spatialDezipperThetaMs",
+            d.dezipper_theta_ms,
+            "ms",
+            0.0,
+            500.0,
+        ),
+        num(
+            "spatialDezipperDistMs",
+            d.dezipper_dist_ms,
+            "ms",
+            0.0,
+            500.0,
+        ),
+    ]
+}
+
+/// The keys read by `noise_params_from_json` and the noise voices.
+fn noise_param_specs(transition: bool) -> Vec<ParamSpec> {
+    let mut params = vec![
+        ParamSpec::float("amp", 1.0, "", 0.0, 2.0),
+        ParamSpec::choice("noise_type", "pink", NOISE_TYPES),
+        ParamSpec::json("noise_parameters").aliases(&["color_params"]),
+        ParamSpec::choice("lfo_waveform", "sine", LFO_SHAPES),
+    ];
+    if transition {
+        params.extend([
+            ParamSpec::float("lfo_freq", 1.0 / 12.0, "Hz", 0.0, 2.0).transition_snake(),
+            ParamSpec::float("lfo_phase_offset_deg", 0.0, "deg", 0.0, 360.0).transition_snake(),
+            ParamSpec::float("intra_phase_offset_deg", 0.0, "deg", 0.0, 360.0).transition_snake(),
+            ParamSpec::json("sweeps").transition_snake(),
+            ParamSpec::json("q").transition_snake(),
+            ParamSpec::json("casc").transition_snake(),
+        ]);
+    } else {
+        params.extend([
+            ParamSpec::float("lfo_freq", 1.0 / 12.0, "Hz", 0.0, 2.0),
+            ParamSpec::float("start_lfo_phase_offset_deg", 0.0, "deg", 0.0, 360.0),
+            ParamSpec::float("start_intra_phase_offset_deg", 0.0, "deg", 0.0, 360.0),
+            ParamSpec::json("sweeps"),
+            ParamSpec::json("notch_q"),
+            ParamSpec::json("casc"),
+        ]);
+    }
+    params.extend([
+        ParamSpec::int("seed", 0.0, u32::MAX as f64),
+        ParamSpec::float("initial_offset", 0.0, "s", 0.0, 60.0),
+        ParamSpec::float("post_offset", 0.0, "s", 0.0, 60.0),
+        ParamSpec::text("input_audio_path"),
+        ParamSpec::float("exponent", 1.0, "", -3.0, 3.0).optional(),
+        ParamSpec::float("high_exponent", 1.0, "", -3.0, 3.0).optional(),
+        ParamSpec::float("distribution_curve", 1.0, "", 0.1, 4.0).optional(),
+        ParamSpec::float("lowcut", 20.0, "Hz", 0.0, 20000.0).optional(),
+        ParamSpec::float("highcut", 20000.0, "Hz", 0.0, 20000.0).optional(),
+        ParamSpec::float("amplitude", 1.0, "", 0.0, 2.0).optional(),
+        ParamSpec::float("start_time", 0.0, "s", 0.0, 3600.0),
+        ParamSpec::float("fade_in", 0.0, "s", 0.0, 60.0),
+        ParamSpec::float("fade_out", 0.0, "s", 0.0, 60.0),
+        ParamSpec::json("static_notches"),
+    ]);
+    params
+}
+
+static SYNTH_REGISTRY: Lazy<Vec<SynthSpec>> = Lazy::new(|| {
+    let mut registry = vec![
+        SynthSpec::new(
+            "binaural_beat",
+            vec![
+                ParamSpec::float("ampL", 0.5, "", 0.0, 2.0),
+                ParamSpec::float("ampR", 0.5, "", 0.0, 2.0),
+                ParamSpec::float("baseFreq", 200.0, "Hz", 20.0, 2000.0),
+                ParamSpec::float("beatFreq", 4.0, "Hz", 0.0, 40.0),
+                ParamSpec::boolean("forceMono", false),
+                ParamSpec::boolean("leftHigh", false),
+                ParamSpec::float("startPhaseL", 0.0, "rad", 0.0, TAU),
+                ParamSpec::float("startPhaseR", 0.0, "rad", 0.0, TAU),
+                ParamSpec::float("ampOscDepthL", 0.0, "", 0.0, 1.0),
+                ParamSpec::float("ampOscFreqL", 0.0, "Hz", 0.0, 40.0),
+                ParamSpec::float("ampOscDepthR", 0.0, "", 0.0, 1.0),
+                ParamSpec::float("ampOscFreqR", 0.0, "Hz", 0.0, 40.0),
+                ParamSpec::float("freqOscRangeL", 0.0, "Hz", 0.0, 20.0),
+                ParamSpec::float("freqOscFreqL", 0.0, "Hz", 0.0, 40.0),
+                ParamSpec::float("freqOscRangeR", 0.0, "Hz", 0.0, 20.0),
+                ParamSpec::float("freqOscFreqR", 0.0, "Hz", 0.0, 40.0),
+                ParamSpec::float("freqOscSkewL", 0.0, "", -1.0, 1.0),
+                ParamSpec::float("freqOscSkewR", 0.0, "", -1.0, 1.0),
+                ParamSpec::float("freqOscPhaseOffsetL", 0.0, "rad", 0.0, TAU),
+                ParamSpec::float("freqOscPhaseOffsetR", 0.0, "rad", 0.0, TAU),
+                ParamSpec::float("ampOscPhaseOffsetL", 0.0, "rad", 0.0, TAU),
+                ParamSpec::float("ampOscPhaseOffsetR", 0.0, "rad", 0.0, TAU),
+                ParamSpec::float("ampOscSkewL", 0.0, "", -1.0, 1.0),
+                ParamSpec::float("ampOscSkewR", 0.0, "", -1.0, 1.0),
+                ParamSpec::float("phaseOscFreq", 0.0, "Hz", 0.0, 40.0),
+                ParamSpec::float("phaseOscRange", 0.0, "rad", 0.0, PI),
+                ParamSpec::choice("freqOscShape", "sine", LFO_SHAPES),
+            ],
+        )
+        .with(carrier_param_specs())
+        .with(flanger_param_specs(false))
+        .with(pan_param_specs(false)),
+        SynthSpec::new(
+            "binaural_beat_transition",
+            vec![
+                ParamSpec::float("ampL", 0.5, "", 0.0, 2.0).transition(),
+                ParamSpec::float("ampR", 0.5, "", 0.0, 2.0).transition(),
+                ParamSpec::float("baseFreq", 200.0, "Hz", 20.0, 2000.0).transition(),
+                ParamSpec::float("beatFreq", 4.0, "Hz", 0.0, 40.0).transition(),
+                ParamSpec::boolean("forceMono", false).transition(),
+                ParamSpec::boolean("leftHigh", false),
+                ParamSpec::float("startPhaseL", 0.0, "rad", 0.0, TAU).transition(),
+                ParamSpec::float("startPhaseR", 0.0, "rad", 0.0, TAU).transition(),
+                ParamSpec::float("phaseOscFreq", 0.0, "Hz", 0.0, 40.0).transition(),
+                ParamSpec::float("phaseOscRange", 0.0, "rad", 0.0, PI).transition(),
+                ParamSpec::float("ampOscDepthL", 0.0, "", 0.0, 1.0).transition(),
+                ParamSpec::float("ampOscFreqL", 0.0, "Hz", 0.0, 40.0).transition(),
+                ParamSpec::float("ampOscDepthR", 0.0, "", 0.0, 1.0).transition(),
+                ParamSpec::float("ampOscFreqR", 0.0, "Hz", 0.0, 40.0).transition(),
+                ParamSpec::float("ampOscPhaseOffsetL", 0.0, "rad", 0.0, TAU).transition(),
+                ParamSpec::float("ampOscPhaseOffsetR", 0.0, "rad", 0.0, TAU).transition(),
+                ParamSpec::float("freqOscRangeL", 0.0, "Hz", 0.0, 20.0).transition(),
+                ParamSpec::float("freqOscFreqL", 0.0, "Hz", 0.0, 40.0).transition(),
+                ParamSpec::float("freqOscRangeR", 0.0, "Hz", 0.0, 20.0).transition(),
+                ParamSpec::float("freqOscFreqR", 0.0, "Hz", 0.0, 40.0).transition(),
+                ParamSpec::float("freqOscSkewL", 0.0, "", -1.0, 1.0).transition(),
+                ParamSpec::float("freqOscSkewR", 0.0, "", -1.0, 1.0).transition(),
+                ParamSpec::float("freqOscPhaseOffsetL", 0.0, "rad", 0.0, TAU).transition(),
+                ParamSpec::float("freqOscPhaseOffsetR", 0.0, "rad", 0.0, TAU).transition(),
+                ParamSpec::float("ampOscSkewL", 0.0, "", -1.0, 1.0).transition(),
+                ParamSpec::float("ampOscSkewR", 0.0, "", -1.0, 1.0).transition(),
+                ParamSpec::float("initial_offset", 0.0, "s", 0.0, 60.0),
+                ParamSpec::float("post_offset", 0.0, "s", 0.0, 60.0),
+                ParamSpec::choice("freqOscShape", "sine", LFO_SHAPES),
+                ParamSpec::choice("transition_curve", "linear", TRANSITION_CURVES),
+            ],
+        )
+        .with(carrier_param_specs())
+        .with(flanger_param_specs(true))
+        .with(pan_param_specs(true)),
+        SynthSpec::new(
+            "binaural_chord",
+            vec![
+                ParamSpec::float("ampL", 0.5, "", 0.0, 2.0),
+                ParamSpec::float("ampR", 0.5, "", 0.0, 2.0),
+                ParamSpec::float("baseFreq", 200.0, "Hz", 20.0, 2000.0),
+                ParamSpec::float("beatFreq", 4.0, "Hz", 0.0, 40.0),
+                ParamSpec::boolean("leftHigh", false),
+                ParamSpec::choice("partialMode", "harmonics", PARTIAL_MODES),
+                ParamSpec {
+                    default: Value::from(DEFAULT_HARMONIC_COUNT),
+                    ..ParamSpec::int("harmonicCount", 1.0, MAX_CHORD_PARTIALS as f64)
+                },
+                ParamSpec::float("harmonicRolloff", 1.0, "", 0.0, 4.0),
+                ParamSpec::json("chordRatios"),
+                ParamSpec::json("partialAmps"),
+                ParamSpec::json("partialBeats"),
+            ],
+        ),
+        SynthSpec::new(
+            "isochronic_tone",
+            vec![
+                ParamSpec::float("amp", 0.5, "", 0.0, 1.0),
+                ParamSpec::float("ampL", 0.5, "", 0.0, 1.0),
+                ParamSpec::float("ampR", 0.5, "", 0.0, 1.0),
+                ParamSpec::float("baseFreq", 200.0, "Hz", 20.0, 2000.0),
+                ParamSpec::float("beatFreq", 4.0, "Hz", 0.0, 40.0),
+                ParamSpec::boolean("forceMono", false),
+                ParamSpec::float("startPhaseL", 0.0, "rad", 0.0, TAU),
+                ParamSpec::float("startPhaseR", 0.0, "rad", 0.0, TAU),
+                ParamSpec::float("ampOscDepthL", 0.0, "", 0.0, 1.0),
+                ParamSpec::float("ampOscFreqL", 0.0, "Hz", 0.0, 40.0),
+                ParamSpec::float("ampOscDepthR", 0.0, "", 0.0, 1.0),
+                ParamSpec::float("ampOscFreqR", 0.0, "Hz", 0.0, 40.0),
+                ParamSpec::float("freqOscRangeL", 0.0, "Hz", 0.0, 20.0),
+                ParamSpec::float("freqOscFreqL", 0.0, "Hz", 0.0, 40.0),
+                ParamSpec::float("freqOscRangeR", 0.0, "Hz", 0.0, 20.0),
+                ParamSpec::float("freqOscFreqR", 0.0, "Hz", 0.0, 40.0),
+                ParamSpec::float("freqOscSkewL", 0.0, "", -1.0, 1.0),
+                ParamSpec::float("freqOscSkewR", 0.0, "", -1.0, 1.0),
+                ParamSpec::float("freqOscPhaseOffsetL", 0.0, "rad", 0.0, TAU),
+                ParamSpec::float("freqOscPhaseOffsetR", 0.0, "rad", 0.0, TAU),
+                ParamSpec::float("ampOscPhaseOffsetL", 0.0, "rad", 0.0, TAU),
+                ParamSpec::float("ampOscPhaseOffsetR", 0.0, "rad", 0.0, TAU),
+                ParamSpec::float("ampOscSkewL", 0.0, "", -1.0, 1.0),
+                ParamSpec::float("ampOscSkewR", 0.0, "", -1.0, 1.0),
+                ParamSpec::float("phaseOscFreq", 0.0, "Hz", 0.0, 40.0),
+                ParamSpec::float("phaseOscRange", 0.0, "rad", 0.0, PI),
+                ParamSpec::float("rampPercent", 0.2, "", 0.0, 1.0),
+                ParamSpec::float("gapPercent", 0.15, "", 0.0, 1.0),
+                ParamSpec::float("pan", 0.0, "", -1.0, 1.0),
+                ParamSpec::float("panRangeMin", 0.0, "", -1.0, 1.0),
+                ParamSpec::float("panRangeMax", 0.0, "", -1.0, 1.0),
+                ParamSpec::float("panFreq", 0.0, "Hz", 0.0, 40.0),
+                ParamSpec::float("panPhase", 0.0, "rad", 0.0, TAU),
+            ],
+        )
+        .with(carrier_param_specs())
+        .with(flanger_param_specs(false)),
+        SynthSpec::new(
+            "isochronic_tone_transition",
+            vec![
+                ParamSpec::float("amp", 0.5, "", 0.0, 1.0),
+                ParamSpec::float("ampL", 0.5, "", 0.0, 1.0).transition(),
+                ParamSpec::float("ampR", 0.5, "", 0.0, 1.0).transition(),
+                ParamSpec::float("baseFreq", 200.0, "Hz", 20.0, 2000.0).transition(),
+                ParamSpec::float("beatFreq", 4.0, "Hz", 0.0, 40.0).transition(),
+                ParamSpec::boolean("forceMono", false).transition(),
+                ParamSpec::float("startPhaseL", 0.0, "rad", 0.0, TAU).transition(),
+                ParamSpec::float("startPhaseR", 0.0, "rad", 0.0, TAU).transition(),
+                ParamSpec::float("phaseOscFreq", 0.0, "Hz", 0.0, 40.0).transition(),
+                ParamSpec::float("phaseOscRange", 0.0, "rad", 0.0, PI).transition(),
+                ParamSpec::float("ampOscDepthL", 0.0, "", 0.0, 1.0).transition(),
+                ParamSpec::float("ampOscFreqL", 0.0, "Hz", 0.0, 40.0).transition(),
+                ParamSpec::float("ampOscDepthR", 0.0, "", 0.0, 1.0).transition(),
+                ParamSpec::float("ampOscFreqR", 0.0, "Hz", 0.0, 40.0).transition(),
+                ParamSpec::float("ampOscPhaseOffsetL", 0.0, "rad", 0.0, TAU).transition(),
+                ParamSpec::float("ampOscPhaseOffsetR", 0.0, "rad", 0.0, TAU).transition(),
+                ParamSpec::float("freqOscRangeL", 0.0, "Hz", 0.0, 20.0).transition(),
+                ParamSpec::float("freqOscFreqL", 0.0, "Hz", 0.0, 40.0).transition(),
+                ParamSpec::float("freqOscRangeR", 0.0, "Hz", 0.0, 20.0).transition(),
+                ParamSpec::float("freqOscFreqR", 0.0, "Hz", 0.0, 40.0).transition(),
+                ParamSpec::float("freqOscSkewL", 0.0, "", -1.0, 1.0).transition(),
+                ParamSpec::float("freqOscSkewR", 0.0, "", -1.0, 1.0).transition(),
+                ParamSpec::float("freqOscPhaseOffsetL", 0.0, "rad", 0.0, TAU).transition(),
+                ParamSpec::float("freqOscPhaseOffsetR", 0.0, "rad", 0.0, TAU).transition(),
+                ParamSpec::float("ampOscSkewL", 0.0, "", -1.0, 1.0).transition(),
+                ParamSpec::float("ampOscSkewR", 0.0, "", -1.0, 1.0).transition(),
+                ParamSpec::float("rampPercent", 0.2, "", 0.0, 1.0).transition(),
+                ParamSpec::float("gapPercent", 0.15, "", 0.0, 1.0).transition(),
+                ParamSpec::float("pan", 0.0, "", -1.0, 1.0),
+                ParamSpec::float("panRangeMin", 0.0, "", -1.0, 1.0).transition(),
+                ParamSpec::float("panRangeMax", 0.0, "", -1.0, 1.0).transition(),
+                ParamSpec::float("panFreq", 0.0, "Hz", 0.0, 40.0).transition(),
+                ParamSpec::float("panPhase", 0.0, "rad", 0.0, TAU).transition(),
+                ParamSpec::float("initial_offset", 0.0, "s", 0.0, 60.0),
+                ParamSpec::float("post_offset", 0.0, "s", 0.0, 60.0),
+                ParamSpec::choice("transition_curve", "linear", TRANSITION_CURVES),
+            ],
+        )
+        .with(carrier_param_specs())
+        .with(flanger_param_specs(true)),
+        SynthSpec::new(
+            "qam_beat",
+            vec![
+                ParamSpec::float("ampL", 0.5, "", 0.0, 1.0),
+                ParamSpec::float("ampR", 0.5, "", 0.0, 1.0),
+                ParamSpec::float("baseFreqL", 200.0, "Hz", 20.0, 2000.0),
+                ParamSpec::float("baseFreqR", 204.0, "Hz", 20.0, 2000.0),
+                ParamSpec::float("qamAmFreqL", 4.0, "Hz", 0.0, 40.0),
+                ParamSpec::float("qamAmDepthL", 0.5, "", 0.0, 1.0),
+                ParamSpec::float("qamAmPhaseOffsetL", 0.0, "rad", 0.0, TAU),
+                ParamSpec::float("qamAmFreqR", 4.0, "Hz", 0.0, 40.0),
+                ParamSpec::float("qamAmDepthR", 0.5, "", 0.0, 1.0),
+                ParamSpec::float("qamAmPhaseOffsetR", 0.0, "rad", 0.0, TAU),
+                ParamSpec::float("qamAm2FreqL", 0.0, "Hz", 0.0, 40.0),
+                ParamSpec::float("qamAm2DepthL", 0.0, "", 0.0, 1.0),
+                ParamSpec::float("qamAm2PhaseOffsetL", 0.0, "rad", 0.0, TAU),
+                ParamSpec::float("qamAm2FreqR", 0.0, "Hz", 0.0, 40.0),
+                ParamSpec::float("qamAm2DepthR", 0.0, "", 0.0, 1.0),
+                ParamSpec::float("qamAm2PhaseOffsetR", 0.0, "rad", 0.0, TAU),
+                ParamSpec::float("modShapeL", 1.0, "", 0.1, 10.0),
+                ParamSpec::float("modShapeR", 1.0, "", 0.1, 10.0),
+                ParamSpec::float("crossModDepth", 0.0, "", 0.0, 1.0),
+                ParamSpec::float("crossModDelay", 0.0, "s", 0.0, 10.0),
+                ParamSpec::float("harmonicDepth", 0.0, "", 0.0, 1.0),
+                ParamSpec::float("harmonicRatio", 2.0, "", 1.0, 8.0),
+                ParamSpec::float("subHarmonicFreq", 0.0, "Hz", 0.0, 200.0),
+                ParamSpec::float("subHarmonicDepth", 0.0, "", 0.0, 1.0),
+                ParamSpec::float("startPhaseL", 0.0, "rad", 0.0, TAU),
+                ParamSpec::float("startPhaseR", 0.0, "rad", 0.0, TAU),
+                ParamSpec::float("phaseOscFreq", 0.0, "Hz", 0.0, 40.0),
+                ParamSpec::float("phaseOscRange", 0.0, "rad", 0.0, PI),
+                ParamSpec::float("phaseOscPhaseOffset", 0.0, "rad", 0.0, TAU),
+                ParamSpec::boolean("beatingSidebands", false),
+                ParamSpec::float("sidebandOffset", 1.0, "Hz", 0.0, 20.0),
+                ParamSpec::float("sidebandDepth", 0.1, "", 0.0, 1.0),
+                ParamSpec::float("attackTime", 0.0, "s", 0.0, 10.0),
+                ParamSpec::float("releaseTime", 0.0, "s", 0.0, 10.0),
+            ],
+        )
+        .with(pan_param_specs(false)),
+        SynthSpec::new(
+            "qam_beat_transition",
+            vec![
+                ParamSpec::float("ampL", 0.5, "", 0.0, 1.0).transition(),
+                ParamSpec::float("ampR", 0.5, "", 0.0, 1.0).transition(),
+                ParamSpec::float("baseFreqL", 200.0, "Hz", 20.0, 2000.0).transition(),
+                ParamSpec::float("baseFreqR", 204.0, "Hz", 20.0, 2000.0).transition(),
+                ParamSpec::float("qamAmFreqL", 4.0, "Hz", 0.0, 40.0).transition(),
+                ParamSpec::float("qamAmFreqR", 4.0, "Hz", 0.0, 40.0).transition(),
+                ParamSpec::float("qamAmDepthL", 0.5, "", 0.0, 1.0).transition(),
+                ParamSpec::float("qamAmDepthR", 0.5, "", 0.0, 1.0).transition(),
+                ParamSpec::float("qamAmPhaseOffsetL", 0.0, "rad", 0.0, TAU).transition(),
+                ParamSpec::float("qamAmPhaseOffsetR", 0.0, "rad", 0.0, TAU).transition(),
+                ParamSpec::float("qamAm2FreqL", 0.0, "Hz", 0.0, 40.0).transition(),
+                ParamSpec::float("qamAm2FreqR", 0.0, "Hz", 0.0, 40.0).transition(),
+                ParamSpec::float("qamAm2DepthL", 0.0, "", 0.0, 1.0).transition(),
+                ParamSpec::float("qamAm2DepthR", 0.0, "", 0.0, 1.0).transition(),
+                ParamSpec::float("qamAm2PhaseOffsetL", 0.0, "rad", 0.0, TAU).transition(),
+                ParamSpec::float("qamAm2PhaseOffsetR", 0.0, "rad", 0.0, TAU).transition(),
+                ParamSpec::float("modShapeL", 1.0, "", 0.1, 10.0).transition(),
+                ParamSpec::float("modShapeR", 1.0, "", 0.1, 10.0).transition(),
+                ParamSpec::float("crossModDepth", 0.0, "", 0.0, 1.0).transition(),
+                ParamSpec::float("crossModDelay", 0.0, "s", 0.0, 10.0),
+                ParamSpec::float("harmonicRatio", 2.0, "", 1.0, 8.0),
+                ParamSpec::float("harmonicDepth", 0.0, "", 0.0, 1.0).transition(),
+                ParamSpec::float("subHarmonicFreq", 0.0, "Hz", 0.0, 200.0).transition(),
+                ParamSpec::float("subHarmonicDepth", 0.0, "", 0.0, 1.0).transition(),
+                ParamSpec::float("phaseOscFreq", 0.0, "Hz", 0.0, 40.0).transition(),
+                ParamSpec::float("phaseOscRange", 0.0, "rad", 0.0, PI).transition(),
+                ParamSpec::float("startPhaseL", 0.0, "rad", 0.0, TAU).transition(),
+                ParamSpec::float("startPhaseR", 0.0, "rad", 0.0, TAU).transition(),
+                ParamSpec::float("phaseOscPhaseOffset", 0.0, "rad", 0.0, TAU),
+                ParamSpec::boolean("beatingSidebands", false),
+                ParamSpec::float("sidebandOffset", 1.0, "Hz", 0.0, 20.0),
+                ParamSpec::float("sidebandDepth", 0.1, "", 0.0, 1.0),
+                ParamSpec::float("attackTime", 0.0, "s", 0.0, 10.0),
+                ParamSpec::float("releaseTime", 0.0, "s", 0.0, 10.0),
+                ParamSpec::float("initial_offset", 0.0, "s", 0.0, 60.0),
+                ParamSpec::float("post_offset", 0.0, "s", 0.0, 60.0),
+                ParamSpec::choice("transition_curve", "linear", TRANSITION_CURVES),
+            ],
+        )
+        .with(pan_param_specs(true)),
+        SynthSpec::new(
+            "rhythmic_waveshaping",
+            vec![
+                ParamSpec::float("amp", 0.25, "", 0.0, 1.0),
+                ParamSpec::float("carrierFreq", 200.0, "Hz", 20.0, 2000.0),
+                ParamSpec::float("modFreq", 4.0, "Hz", 0.0, 40.0),
+                ParamSpec::float("modDepth", 1.0, "", 0.0, 1.0),
+                ParamSpec::float("shapeAmount", 5.0, "", 0.0, 10.0),
+                ParamSpec::float("pan", 0.0, "", -1.0, 1.0),
+            ],
+        ),
+        SynthSpec::new(
+            "rhythmic_waveshaping_transition",
+            vec![
+                ParamSpec::float("amp", 0.25, "", 0.0, 1.0),
+                ParamSpec::float("carrierFreq", 200.0, "Hz", 20.0, 2000.0).transition(),
+                ParamSpec::float("modFreq", 4.0, "Hz", 0.0, 40.0).transition(),
+                ParamSpec::float("modDepth", 1.0, "", 0.0, 1.0).transition(),
+                ParamSpec::float("shapeAmount", 5.0, "", 0.0, 10.0).transition(),
+                ParamSpec::float("pan", 0.0, "", -1.0, 1.0),
+                ParamSpec::float("initial_offset", 0.0, "s", 0.0, 60.0),
+                ParamSpec::float("post_offset", 0.0, "s", 0.0, 60.0),
+                ParamSpec::choice("transition_curve", "linear", TRANSITION_CURVES),
+            ],
+        ),
+        SynthSpec::new(
+            "stereo_am_independent",
+            vec![
+                ParamSpec::float("amp", 0.25, "", 0.0, 1.0),
+                ParamSpec::float("carrierFreq", 200.0, "Hz", 20.0, 2000.0),
+                ParamSpec::float("modFreqL", 4.0, "Hz", 0.0, 40.0),
+                ParamSpec::float("modDepthL", 0.8, "", 0.0, 1.0),
+                ParamSpec::float("modPhaseL", 0.0, "rad", 0.0, TAU),
+                ParamSpec::float("modFreqR", 4.0, "Hz", 0.0, 40.0),
+                ParamSpec::float("modDepthR", 0.8, "", 0.0, 1.0),
+                ParamSpec::float("modPhaseR", 0.0, "rad", 0.0, TAU),
+                ParamSpec::float("stereo_width_hz", 0.2, "Hz", 0.0, 10.0),
+            ],
+        ),
+        SynthSpec::new(
+            "stereo_am_independent_transition",
+            vec![
+                ParamSpec::float("amp", 0.25, "", 0.0, 1.0),
+                ParamSpec::float("carrierFreq", 200.0, "Hz", 20.0, 2000.0)
+                    .transition()
+                    .end_default(250.0),
+                ParamSpec::float("modFreqL", 4.0, "Hz", 0.0, 40.0)
+                    .transition()
+                    .end_default(6.0),
+                ParamSpec::float("modDepthL", 0.8, "", 0.0, 1.0).transition(),
+                ParamSpec::float("modPhaseL", 0.0, "rad", 0.0, TAU).transition(),
+                ParamSpec::float("modFreqR", 4.1, "Hz", 0.0, 40.0)
+                    .transition()
+                    .end_default(5.9),
+                ParamSpec::float("modDepthR", 0.8, "", 0.0, 1.0).transition(),
+                ParamSpec::float("modPhaseR", 0.0, "rad", 0.0, TAU).transition(),
+                ParamSpec::float("stereoWidthHz", 0.2, "Hz", 0.0, 10.0).transition(),
+                ParamSpec::float("initial_offset", 0.0, "s", 0.0, 60.0),
+                ParamSpec::float("post_offset", 0.0, "s", 0.0, 60.0),
+                ParamSpec::choice("transition_curve", "linear", TRANSITION_CURVES),
+            ],
+        ),
+        SynthSpec::new(
+            "wave_shape_stereo_am",
+            vec![
+                ParamSpec::float("amp", 0.15, "", 0.0, 1.0),
+                ParamSpec::float("carrierFreq", 200.0, "Hz", 20.0, 2000.0),
+                ParamSpec::float("shapeModFreq", 4.0, "Hz", 0.0, 40.0),
+                ParamSpec::float("shapeModDepth", 0.8, "", 0.0, 1.0),
+                ParamSpec::float("shapeAmount", 0.5, "", 0.0, 10.0),
+                ParamSpec::float("stereoModFreqL", 4.1, "Hz", 0.0, 40.0),
+                ParamSpec::float("stereoModDepthL", 0.8, "", 0.0, 1.0),
+                ParamSpec::float("stereoModPhaseL", 0.0, "rad", 0.0, TAU),
+                ParamSpec::float("stereoModFreqR", 4.0, "Hz", 0.0, 40.0),
+                ParamSpec::float("stereoModDepthR", 0.8, "", 0.0, 1.0),
+                ParamSpec::float("stereoModPhaseR", FRAC_PI_2, "rad", 0.0, TAU),
+            ],
+        ),
+        SynthSpec::new(
+            "wave_shape_stereo_am_transition",
+            vec![
+                ParamSpec::float("amp", 0.15, "", 0.0, 1.0),
+                ParamSpec::float("carrierFreq", 200.0, "Hz", 20.0, 2000.0)
+                    .transition()
+                    .end_default(100.0),
+                ParamSpec::float("shapeModFreq", 4.0, "Hz", 0.0, 40.0)
+                    .transition()
+                    .end_default(8.0),
+                ParamSpec::float("shapeModDepth", 0.8, "", 0.0, 1.0).transition(),
+                ParamSpec::float("shapeAmount", 0.5, "", 0.0, 10.0).transition(),
+                ParamSpec::float("stereoModFreqL", 4.1, "Hz", 0.0, 40.0)
+                    .transition()
+                    .end_default(6.0),
+                ParamSpec::float("stereoModDepthL", 0.8, "", 0.0, 1.0).transition(),
+                ParamSpec::float("stereoModPhaseL", 0.0, "rad", 0.0, TAU).transition(),
+                ParamSpec::float("stereoModFreqR", 4.0, "Hz", 0.0, 40.0)
+                    .transition()
+                    .end_default(6.1),
+                ParamSpec::float("stereoModDepthR", 0.9, "", 0.0, 1.0).transition(),
+                ParamSpec::float("stereoModPhaseR", FRAC_PI_2, "rad", 0.0, TAU).transition(),
+                ParamSpec::float("initial_offset", 0.0, "s", 0.0, 60.0),
+                ParamSpec::float("post_offset", 0.0, "s", 0.0, 60.0),
+                ParamSpec::choice("transition_curve", "linear", TRANSITION_CURVES),
+            ],
+        ),
+        SynthSpec::new(
+            "spatial_angle_modulation",
+            vec![
+                ParamSpec::float("amp", 0.7, "", 0.0, 1.0),
+                ParamSpec::float("carrierFreq", 440.0, "Hz", 20.0, 2000.0),
+                ParamSpec::float("beatFreq", 4.0, "Hz", 0.0, 40.0),
+                ParamSpec::float("pathRadius", 1.0, "", 0.0, 2.0),
+            ],
+        ),
+        SynthSpec::new(
+            "spatial_angle_modulation_transition",
+            vec![
+                ParamSpec::float("amp", 0.7, "", 0.0, 1.0),
+                ParamSpec::float("carrierFreq", 440.0, "Hz", 20.0, 2000.0).transition(),
+                ParamSpec::float("beatFreq", 4.0, "Hz", 0.0, 40.0).transition(),
+                ParamSpec::float("pathRadius", 1.0, "", 0.0, 2.0).transition(),
+                ParamSpec::float("initial_offset", 0.0, "s", 0.0, 60.0),
+                ParamSpec::float("post_offset", 0.0, "s", 0.0, 60.0),
+                ParamSpec::choice("transition_curve", "linear", TRANSITION_CURVES),
+            ],
+        ),
+        SynthSpec::new(
+            "subliminal_encode",
+            vec![
+                ParamSpec::float("carrierFreq", 17500.0, "Hz", 15000.0, 20000.0),
+                ParamSpec::float("amp", 0.5, "", 0.0, 1.0),
+                ParamSpec::choice("mode", "sequence", MODES),
+                ParamSpec::choice("layout", "sequence", LAYOUTS),
+                ParamSpec::float("speed", 2.0, "x", 1.0, 8.0),
+                ParamSpec::float("snrDb", -15.0, "dB", -40.0, 0.0),
+                ParamSpec::json("audio_paths"),
+                ParamSpec::text("audio_path"),
+            ],
+        ),
+        SynthSpec::new("noise_swept_notch", noise_param_specs(false)).aliases(&["noise"]),
+        SynthSpec::new("noise_swept_notch_transition", noise_param_specs(true))
+            .aliases(&["noise_transition"]),
+    ];
+    // Monaural beats read exactly the binaural beat params.
+    for (binaural, monaural) in [
+        ("binaural_beat", "monaural_beat"),
+        ("binaural_beat_transition", "monaural_beat_transition"),
+    ] {
+        let params = registry
+            .iter()
+            .find(|s| s.name == binaural)
+            .map(|s| s.params.clone())
+            .unwrap_or_default();
+        registry.push(SynthSpec::new(monaural, params));
+    }
+    for spec in &mut registry {
+        spec.params.extend(spatial_param_specs());
+    }
+    registry
+});
+
+/// Every built-in synth function `create_voice` understands, with the
+/// parameters its voice reads. This is the source of truth for editors and
+/// validation. Synths registered through
+/// [`register_voice_factory`](crate::voice_factory::register_voice_factory)
+/// are not listed.
+pub fn synth_registry() -> &'static [SynthSpec] {
+    &SYNTH_REGISTRY
+}
+
+/// Look up a synth function by name or alias.
+pub fn synth_spec(synth_function_name: &str) -> Option<&'static SynthSpec> {
+    SYNTH_REGISTRY
+        .iter()
+        .find(|s| s.name == synth_function_name || s.aliases.contains(&synth_function_name))
+}
+
 pub fn voices_for_step(step: &StepData, sample_rate: f32) -> Vec<StepVoice> {
     let mut out: Vec<StepVoice> = Vec::new();
     for voice in &step.voices {
@@ -4187,6 +6545,17 @@ fn create_voice(data: &VoiceData, duration: f32, sample_rate: f32) -> Option<Ste
             "binaural_beat_transition" => VoiceKind::BinauralBeatTransition(
                 BinauralBeatTransitionVoice::new(&data.params, duration, sample_rate),
             ),
+            "monaural_beat" => {
+                VoiceKind::MonauralBeat(MonauralBeatVoice::new(&data.params, duration, sample_rate))
+            }
+            "monaural_beat_transition" => VoiceKind::MonauralBeatTransition(
+                MonauralBeatTransitionVoice::new(&data.params, duration, sample_rate),
+            ),
+            "binaural_chord" => VoiceKind::BinauralChord(BinauralChordVoice::new(
+                &data.params,
+                duration,
+                sample_rate,
+            )),
             "isochronic_tone" => VoiceKind::IsochronicTone(IsochronicToneVoice::new(
                 &data.params,
                 duration,
@@ -4246,13 +6615,38 @@ fn create_voice(data: &VoiceData, duration: f32, sample_rate: f32) -> Option<Ste
                     sample_rate,
                 ))
             }
-            _ => return None,
+            name => {
+                let factory = voice_factory(name)?;
+                VoiceKind::Custom(factory.create(&data.params, duration, sample_rate)?)
+            }
         };
 
+    let modulation = data.modulation.as_ref().filter(|m| !m.routes.is_empty());
+    if modulation.is_some() || !data.automation.is_empty() {
+        if let Some(spec) = synth_spec(&data.synth_function_name) {
+            voice = VoiceKind::Modulated(Box::new(ModulatedVoice::new(
+                Box::new(voice),
+                spec,
+                modulation,
+                &data.automation,
+                duration,
+                sample_rate,
+            )));
+        }
+    }
+    if let Some(spatial) = spatial_params_from_json(&data.params) {
+        voice = VoiceKind::Spatial(Box::new(SpatialVoice::new(
+            Box::new(voice),
+            spatial,
+            sample_rate,
+        )));
+    }
     if let Some(env) = &data.volume_envelope {
-        let env_vec = build_volume_envelope(env, duration, sample_rate as u32);
-        voice =
-            VoiceKind::VolumeEnvelope(Box::new(VolumeEnvelopeVoice::new(Box::new(voice), env_vec)));
+        let envelope = Envelope::new(env, duration, sample_rate as u32);
+        voice = VoiceKind::VolumeEnvelope(Box::new(VolumeEnvelopeVoice::new(
+            Box::new(voice),
+            envelope,
+        )));
     }
     let voice_type = match data.voice_type.to_lowercase().as_str() {
         "noise" => VoiceType::Noise,
@@ -4268,3 +6662,302 @@ fn create_voice(data: &VoiceData, duration: f32, sample_rate: f32) -> Option<Ste
         normalization_peak,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn params(value: Value) -> HashMap<String, Value> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    /// Largest absolute sample in each channel over `seconds` of `voice`.
+    fn channel_peaks(voice: &mut VoiceKind, seconds: f32, sample_rate: f32) -> (f32, f32) {
+        let mut out = vec![0.0; (seconds * sample_rate) as usize * 2];
+        voice.process(&mut out);
+        out.chunks_exact(2).fold((0.0f32, 0.0f32), |(l, r), f| {
+            (l.max(f[0].abs()), r.max(f[1].abs()))
+        })
+    }
+
+    #[test]
+    fn transition_pan_ends_fall_back_to_the_start() {
+        let p = params(json!({
+            "panRangeMin": 0.3, "panRangeMax": 0.3,
+            "startPanRangeMin": -1.0, "startPanRangeMax": -0.5,
+            "startPanFreq": 0.2, "startPanPhase": 1.0,
+        }));
+        let v = BinauralBeatTransitionVoice::new(&p, 10.0, 100.0);
+        assert!(v.start_pan.range_min == -1.0 && v.start_pan.range_max == -0.5);
+        assert!(v.end_pan == v.start_pan, "end keys default to the start");
+
+        let mut p = p;
+        p.insert("endPan".to_string(), json!(0.8));
+        p.insert("endPanFreq".to_string(), json!(0.0));
+        let v = BinauralBeatTransitionVoice::new(&p, 10.0, 100.0);
+        assert!(v.end_pan.range_min == 0.8 && v.end_pan.range_max == 0.8);
+        assert_eq!(v.end_pan.freq, 0.0);
+        assert_eq!(v.end_pan.phase, 1.0);
+        let halfway = v.start_pan.lerp(&v.end_pan, 0.5);
+        assert!((halfway.range_min + 0.1).abs() < 1e-6);
+        assert!((halfway.range_max - 0.15).abs() < 1e-6);
+    }
+
+    #[test]
+    fn equal_power_pan_raises_the_normalization_peak() {
+        let sr = 1000.0;
+        let hard_left = params(json!({
+            "ampL": 1.0, "ampR": 1.0, "baseFreq": 100.0, "beatFreq": 4.0,
+            "pan": -1.0, "panType": "equal_power",
+        }));
+        let mut v = VoiceKind::BinauralBeat(BinauralBeatVoice::new(&hard_left, 2.0, sr));
+        let peak = v.normalization_peak();
+        assert!((peak - std::f32::consts::SQRT_2).abs() < 1e-5, "{peak}");
+        let (left, right) = channel_peaks(&mut v, 2.0, sr);
+        assert!(
+            left <= peak + 1e-3 && left > 0.97 * peak,
+            "{left} vs {peak}"
+        );
+        assert!(right < 1e-3, "{right}");
+
+        let mut linear = hard_left.clone();
+        linear.insert("panType".to_string(), json!("linear"));
+        let v = VoiceKind::BinauralBeat(BinauralBeatVoice::new(&linear, 2.0, sr));
+        assert_eq!(v.normalization_peak(), 1.0);
+
+        // Sweeping from centre to a half-right sweep.
+        let sweep = params(json!({
+            "startAmpL": 1.0, "startAmpR": 1.0, "panType": "equal_power",
+            "endPanRangeMin": 0.0, "endPanRangeMax": 0.5, "endPanFreq": 1.0,
+        }));
+        let mut v =
+            VoiceKind::BinauralBeatTransition(BinauralBeatTransitionVoice::new(&sweep, 4.0, sr));
+        let peak = v.normalization_peak();
+        let expected = (0.75 * std::f32::consts::FRAC_PI_2).sin() * std::f32::consts::SQRT_2;
+        assert!((peak - expected).abs() < 1e-5, "{peak} vs {expected}");
+        let (left, right) = channel_peaks(&mut v, 4.0, sr);
+        assert!(left.max(right) <= peak + 1e-3, "{left} {right} vs {peak}");
+
+        // The start's law holds for the whole transition.
+        let mut linear_start = sweep.clone();
+        linear_start.insert("panType".to_string(), json!("linear"));
+        linear_start.insert("endPanType".to_string(), json!("equal_power"));
+        let v = VoiceKind::BinauralBeatTransition(BinauralBeatTransitionVoice::new(
+            &linear_start,
+            4.0,
+            sr,
+        ));
+        assert_eq!(v.normalization_peak(), 1.0);
+        let spec = synth_spec("binaural_beat_transition").unwrap();
+        assert!(spec.param_keys().any(|k| k == "startPanType"));
+        assert!(!spec.param_keys().any(|k| k == "endPanType"));
+    }
+
+    #[test]
+    fn isochronic_voices_run_the_flanger_within_their_peak() {
+        let sr = 8000.0;
+        let p = params(json!({
+            "ampL": 1.0, "ampR": 1.0, "baseFreq": 100.0, "beatFreq": 4.0,
+            "flangeEnable": true, "flangeFeedback": 0.8, "flangeMix": 0.8,
+        }));
+        let mut dry = p.clone();
+        dry.insert("flangeEnable".to_string(), json!(false));
+        let mut plain = VoiceKind::IsochronicTone(IsochronicToneVoice::new(&dry, 1.0, sr));
+        assert_eq!(plain.normalization_peak(), 1.0);
+
+        let voices = [
+            VoiceKind::IsochronicTone(IsochronicToneVoice::new(&p, 1.0, sr)),
+            VoiceKind::IsochronicToneTransition(IsochronicToneTransitionVoice::new(&p, 1.0, sr)),
+        ];
+        let mut reference = vec![0.0; 8000 * 2];
+        plain.process(&mut reference);
+        for mut v in voices {
+            let peak = v.normalization_peak();
+            assert!(peak > 1.0, "{peak}");
+            let mut out = vec![0.0; 8000 * 2];
+            v.process(&mut out);
+            assert_ne!(out, reference, "the flanger colours the tone");
+            let measured = out.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+            assert!(measured <= peak, "{measured} vs {peak}");
+        }
+    }
+
+    #[test]
+    fn spatial_peak_counts_both_sources_in_each_ear() {
+        let sr = 48_000.0;
+        // Identical full-scale channels, so both virtual sources add up.
+        let p = params(json!({
+            "ampL": 1.0, "ampR": 1.0, "baseFreq": 100.0, "beatFreq": 0.0,
+        }));
+        // Facing front, then orbiting once.
+        for trajectory in [Vec::new(), vec![(0.0, 0.0), (1.0, 360.0)]] {
+            let params = SpatialParams {
+                trajectory: trajectory
+                    .into_iter()
+                    .map(|(time, azimuth_deg)| TrajectoryPoint {
+                        time,
+                        azimuth_deg,
+                        distance_m: 1.0,
+                    })
+                    .collect(),
+                ..SpatialParams::default()
+            };
+            let mut v = VoiceKind::Spatial(Box::new(SpatialVoice::new(
+                Box::new(VoiceKind::BinauralBeat(BinauralBeatVoice::new(&p, 1.0, sr))),
+                params,
+                sr,
+            )));
+            let peak = v.normalization_peak();
+            let (left, right) = channel_peaks(&mut v, 1.0, sr);
+            let measured = left.max(right);
+            assert!(measured <= peak, "{measured} vs {peak}");
+            assert!(measured > 1.2, "both sources reach each ear: {measured}");
+        }
+    }
+
+    #[test]
+    fn monaural_beats_sum_both_carriers_in_each_ear() {
+        let sr = 1000.0;
+        let p = params(json!({
+            "ampL": 0.5, "ampR": 0.5, "baseFreq": 100.0, "beatFreq": 4.0,
+        }));
+        let mut v = VoiceKind::MonauralBeat(MonauralBeatVoice::new(&p, 2.0, sr));
+        let peak = v.normalization_peak();
+        assert_eq!(peak, 1.0);
+        let mut out = vec![0.0; 2000 * 2];
+        v.process(&mut out[..1000 * 2]);
+
+        // Phases carry over to a fresh voice, which carries on seamlessly.
+        let (phase_l, phase_r) = v.get_phases().expect("monaural beats keep phases");
+        let mut next = VoiceKind::MonauralBeat(MonauralBeatVoice::new(&p, 1.0, sr));
+        next.set_phases(phase_l, phase_r);
+        assert_eq!(next.get_phases(), Some((phase_l, phase_r)));
+        v.process(&mut out[1000 * 2..]);
+        let mut continued = vec![0.0; 1000 * 2];
+        next.process(&mut continued);
+        for (a, b) in out[1000 * 2..].iter().zip(&continued) {
+            assert!((a - b).abs() < 1e-4, "{a} vs {b}");
+        }
+
+        assert!(out.chunks_exact(2).all(|f| f[0] == f[1]), "both ears match");
+        let window_peak = |from: usize| {
+            out[from * 2..(from + 20) * 2]
+                .iter()
+                .fold(0.0f32, |m, s| m.max(s.abs()))
+        };
+        // The carriers start in phase, cancel half a beat later and meet
+        // again a whole beat (0.25 s) in.
+        for beat in 0..7 {
+            let start = beat * 250;
+            assert!(window_peak(start + 115) < 0.15, "beat {beat}");
+            assert!(window_peak(start + 240) > 0.9, "beat {beat}");
+        }
+        let loudest = out.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(loudest <= peak + 1e-3 && loudest > 0.9 * peak, "{loudest}");
+
+        let p = params(json!({
+            "startAmpL": 0.5, "startAmpR": 0.5, "endAmpL": 0.8, "endAmpR": 0.4,
+            "startBaseFreq": 100.0, "endBaseFreq": 100.0,
+            "startBeatFreq": 4.0, "endBeatFreq": 8.0,
+        }));
+        let mut v =
+            VoiceKind::MonauralBeatTransition(MonauralBeatTransitionVoice::new(&p, 2.0, sr));
+        let peak = v.normalization_peak();
+        assert!((peak - 1.2).abs() < 1e-6, "{peak}");
+        let mut out = vec![0.0; 2000 * 2];
+        v.process(&mut out);
+        assert!(out.chunks_exact(2).all(|f| f[0] == f[1]), "both ears match");
+        let loudest = out.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(loudest <= peak + 1e-3 && loudest > 0.9, "{loudest}");
+    }
+
+    #[test]
+    fn chord_partials_fade_out_below_nyquist() {
+        let sr = 1000.0;
+        for (freq, gain) in [(310.0, 1.0), (450.0, 0.5), (500.0, 0.0)] {
+            let p = params(json!({
+                "baseFreq": freq, "beatFreq": 0.0, "harmonicCount": 1,
+                "ampL": 1.0, "ampR": 1.0,
+            }));
+            let mut v = VoiceKind::BinauralChord(BinauralChordVoice::new(&p, 1.0, sr));
+            let (left, right) = channel_peaks(&mut v, 1.0, sr);
+            assert!((left - gain).abs() < 0.02, "{freq} Hz: {left}");
+            assert!((right - gain).abs() < 0.02, "{freq} Hz: {right}");
+        }
+    }
+
+    #[test]
+    fn spec_defaults_match_the_constructor_defaults() {
+        let sample_rate = 1000.0;
+        for spec in synth_registry() {
+            let defaults: HashMap<String, Value> = spec.default_params().into_iter().collect();
+            let empty = HashMap::new();
+            if let Some(transition) = match spec.name {
+                "noise_swept_notch" => Some(false),
+                "noise_swept_notch_transition" => Some(true),
+                _ => None,
+            } {
+                // Noise is generated on a worker thread, so compare what the
+                // voice is built from rather than its output.
+                let build = |p: &HashMap<String, Value>| {
+                    let noise = noise_params_from_json(p, 1.0, sample_rate as u32, transition);
+                    format!("{} {noise:?}", get_f32(p, "amp", 1.0))
+                };
+                assert_eq!(build(&defaults), build(&empty), "{}", spec.name);
+                continue;
+            }
+            let render = |params: &HashMap<String, Value>| {
+                let data: VoiceData = serde_json::from_value(json!({
+                    "synth_function_name": spec.name,
+                    "params": params,
+                }))
+                .unwrap();
+                let mut out = vec![0.0; 1000];
+                if let Some(mut voice) = create_voice(&data, 1.0, sample_rate) {
+                    voice.kind.process(&mut out);
+                }
+                out
+            };
+            let from_spec = render(&defaults);
+            let from_constructor = render(&empty);
+            let mismatch = from_spec
+                .iter()
+                .zip(&from_constructor)
+                .position(|(a, b)| (a - b).abs() > 1e-5);
+            assert!(
+                mismatch.is_none(),
+                "{}: sample {mismatch:?} differs between spec and constructor defaults",
+                spec.name
+            );
+        }
+    }
+
+    #[test]
+    fn some_transition_starts_ignore_the_plain_key() {
+        // These voices have always read only `startX` for their start value;
+        // a plain `X` left over from a static voice must not move it.
+        let render = |synth: &str, params: Value| {
+            let data: VoiceData = serde_json::from_value(json!({
+                "synth_function_name": synth,
+                "params": params,
+            }))
+            .unwrap();
+            let mut voice = create_voice(&data, 1.0, 1000.0).unwrap();
+            let mut out = vec![0.0; 1000];
+            voice.kind.process(&mut out);
+            out
+        };
+        for (synth, key) in [
+            ("isochronic_tone_transition", "baseFreq"),
+            ("isochronic_tone_transition", "beatFreq"),
+            ("stereo_am_independent_transition", "carrierFreq"),
+            ("stereo_am_independent_transition", "modFreqL"),
+            ("wave_shape_stereo_am_transition", "carrierFreq"),
+            ("wave_shape_stereo_am_transition", "shapeModFreq"),
+        ] {
+            let plain = render(synth, json!({ key: 300.0 }));
+            assert_eq!(plain, render(synth, json!({})), "{synth} {key}");
+        }
+    }
+}